@@ -1,5 +1,15 @@
-use opensrdk_linear_algebra::{sparse::SparseTensor, Matrix, Tensor};
+use crate::{matrix_expression::operations::cholesky::lower_half, SymbolicError};
+use opensrdk_linear_algebra::{
+    indices_cartesian_product, matrix::ge::sy_he::po::trf::POTRF, sparse::SparseTensor, Matrix,
+    RankIndex, Tensor,
+};
 use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    hash::{Hash, Hasher},
+    ops::Div,
+};
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum ConstantValue {
@@ -8,6 +18,33 @@ pub enum ConstantValue {
     Matrix(Matrix),
 }
 
+/// Zeros of both signs hash alike, as they compare equal.
+fn hash_elem<H: Hasher>(v: f64, state: &mut H) {
+    if v == 0.0 { 0.0f64 } else { v }.to_bits().hash(state);
+}
+
+impl Hash for ConstantValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            ConstantValue::Scalar(v) => hash_elem(*v, state),
+            ConstantValue::Tensor(v) => {
+                self.sizes().hash(state);
+                let mut elems = v.elems().iter().collect::<Vec<_>>();
+                elems.sort_by(|a, b| a.0.cmp(b.0));
+                for (indices, &e) in elems {
+                    indices.hash(state);
+                    hash_elem(e, state);
+                }
+            }
+            ConstantValue::Matrix(v) => {
+                self.sizes().hash(state);
+                v.elems().iter().for_each(|&e| hash_elem(e, state));
+            }
+        }
+    }
+}
+
 impl ConstantValue {
     pub fn sizes(&self) -> Vec<usize> {
         match self {
@@ -35,55 +72,95 @@ impl ConstantValue {
         }
     }
 
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ConstantValue::Scalar(_) => "scalar",
+            ConstantValue::Tensor(_) => "tensor",
+            ConstantValue::Matrix(_) => "matrix",
+        }
+    }
+
+    pub fn try_into_scalar(&self) -> Result<f64, SymbolicError> {
+        match self {
+            ConstantValue::Scalar(v) => Ok(*v),
+            _ => Err(self.type_mismatch("scalar")),
+        }
+    }
+
     pub fn into_scalar(&self) -> f64 {
-        if let ConstantValue::Scalar(v) = self {
-            *v
-        } else {
-            panic!()
+        self.try_into_scalar().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_into_tensor(self) -> Result<SparseTensor, SymbolicError> {
+        match self {
+            ConstantValue::Tensor(v) => Ok(v),
+            _ => Err(self.type_mismatch("tensor")),
         }
     }
 
     pub fn into_tensor(self) -> SparseTensor {
-        if let ConstantValue::Tensor(v) = self {
-            v
-        } else {
-            panic!()
+        self.try_into_tensor().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_as_tensor_ref(&self) -> Result<&SparseTensor, SymbolicError> {
+        match self {
+            ConstantValue::Tensor(v) => Ok(v),
+            _ => Err(self.type_mismatch("tensor")),
         }
     }
 
-    pub fn into_tensor_ref(&self) -> &SparseTensor {
-        if let ConstantValue::Tensor(v) = self {
-            v
-        } else {
-            panic!()
+    pub fn as_tensor_ref(&self) -> &SparseTensor {
+        self.try_as_tensor_ref().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_into_matrix(self) -> Result<Matrix, SymbolicError> {
+        match self {
+            ConstantValue::Matrix(v) => Ok(v),
+            _ => Err(self.type_mismatch("matrix")),
         }
     }
 
     pub fn into_matrix(self) -> Matrix {
-        if let ConstantValue::Matrix(v) = self {
-            v
-        } else {
-            panic!()
+        self.try_into_matrix().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_as_matrix_ref(&self) -> Result<&Matrix, SymbolicError> {
+        match self {
+            ConstantValue::Matrix(v) => Ok(v),
+            _ => Err(self.type_mismatch("matrix")),
         }
     }
 
-    pub fn into_matrix_ref(&self) -> &Matrix {
-        if let ConstantValue::Matrix(v) = self {
-            v
-        } else {
-            panic!()
+    pub fn as_matrix_ref(&self) -> &Matrix {
+        self.try_as_matrix_ref().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn type_mismatch(&self, expected: &'static str) -> SymbolicError {
+        SymbolicError::TypeMismatch {
+            expected,
+            found: self.kind(),
+        }
+    }
+
+    fn check_operands(&self, rhs: &ConstantValue) -> Result<(), SymbolicError> {
+        match (self, rhs) {
+            (ConstantValue::Scalar(_), _) | (_, ConstantValue::Scalar(_)) => Ok(()),
+            _ if self.sizes() != rhs.sizes() => Err(SymbolicError::DimensionMismatch),
+            _ => Ok(()),
         }
     }
 }
 
 impl ConstantValue {
-    pub fn add(&self, rhs: ConstantValue) -> ConstantValue {
-        match (self, rhs) {
+    pub fn try_add(&self, rhs: ConstantValue) -> Result<ConstantValue, SymbolicError> {
+        self.check_operands(&rhs)?;
+        let result = match (self, rhs) {
             (ConstantValue::Scalar(lhs), ConstantValue::Scalar(rhs)) => {
                 ConstantValue::Scalar(lhs + rhs)
             }
+            // The operators of the tensors only visit the elements stored, so the implicit zeros are mapped here.
             (ConstantValue::Scalar(lhs), ConstantValue::Tensor(rhs)) => {
-                ConstantValue::Tensor(lhs + rhs)
+                ConstantValue::Tensor(rhs).map(|r| lhs + r)
             }
             (ConstantValue::Scalar(lhs), ConstantValue::Matrix(rhs)) => {
                 ConstantValue::Matrix(lhs + rhs)
@@ -91,48 +168,60 @@ impl ConstantValue {
             (ConstantValue::Tensor(lhs), ConstantValue::Tensor(rhs)) => {
                 ConstantValue::Tensor(lhs + rhs)
             }
-            (ConstantValue::Tensor(lhs), ConstantValue::Scalar(rhs)) => {
-                ConstantValue::Tensor(lhs.clone() + rhs)
-            }
+            (ConstantValue::Tensor(_), ConstantValue::Scalar(rhs)) => self.clone().map(|l| l + rhs),
             (ConstantValue::Matrix(lhs), ConstantValue::Matrix(rhs)) => {
                 ConstantValue::Matrix(lhs + rhs)
             }
             (ConstantValue::Matrix(lhs), ConstantValue::Scalar(rhs)) => {
                 ConstantValue::Matrix(lhs.clone() + rhs)
             }
-            _ => panic!(),
-        }
+            // The products of matrices evaluate to tensors, so the matrix is regarded as a tensor of the same sizes.
+            (lhs, rhs) => ConstantValue::Tensor(lhs.to_tensor() + rhs.to_tensor()),
+        };
+
+        Ok(result)
     }
 
-    pub fn sub(&self, rhs: ConstantValue) -> ConstantValue {
-        match (self, rhs) {
+    pub fn add(&self, rhs: ConstantValue) -> ConstantValue {
+        self.try_add(rhs).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_sub(&self, rhs: ConstantValue) -> Result<ConstantValue, SymbolicError> {
+        self.check_operands(&rhs)?;
+        let result = match (self, rhs) {
             (ConstantValue::Scalar(lhs), ConstantValue::Scalar(rhs)) => {
                 ConstantValue::Scalar(lhs - rhs)
             }
+            // The operators of the tensors and matrices subtract the tensor from the scalar the other way around.
             (ConstantValue::Scalar(lhs), ConstantValue::Tensor(rhs)) => {
-                ConstantValue::Tensor(lhs - rhs)
+                ConstantValue::Tensor(rhs).map(|r| lhs - r)
             }
             (ConstantValue::Scalar(lhs), ConstantValue::Matrix(rhs)) => {
-                ConstantValue::Matrix(lhs - rhs)
+                ConstantValue::Matrix(rhs).map(|r| lhs - r)
             }
             (ConstantValue::Tensor(lhs), ConstantValue::Tensor(rhs)) => {
                 ConstantValue::Tensor(lhs - rhs)
             }
-            (ConstantValue::Tensor(lhs), ConstantValue::Scalar(rhs)) => {
-                ConstantValue::Tensor(lhs.clone() - rhs)
-            }
+            (ConstantValue::Tensor(_), ConstantValue::Scalar(rhs)) => self.clone().map(|l| l - rhs),
             (ConstantValue::Matrix(lhs), ConstantValue::Matrix(rhs)) => {
                 ConstantValue::Matrix(lhs - rhs)
             }
             (ConstantValue::Matrix(lhs), ConstantValue::Scalar(rhs)) => {
                 ConstantValue::Matrix(lhs.clone() - rhs)
             }
-            _ => panic!(),
-        }
+            (lhs, rhs) => ConstantValue::Tensor(lhs.to_tensor() - rhs.to_tensor()),
+        };
+
+        Ok(result)
     }
 
-    pub fn mul(&self, rhs: ConstantValue) -> ConstantValue {
-        match (self, rhs) {
+    pub fn sub(&self, rhs: ConstantValue) -> ConstantValue {
+        self.try_sub(rhs).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_mul(&self, rhs: ConstantValue) -> Result<ConstantValue, SymbolicError> {
+        self.check_operands(&rhs)?;
+        let result = match (self, rhs) {
             (ConstantValue::Scalar(lhs), ConstantValue::Scalar(rhs)) => {
                 ConstantValue::Scalar(lhs * rhs)
             }
@@ -154,21 +243,25 @@ impl ConstantValue {
             (ConstantValue::Matrix(lhs), ConstantValue::Scalar(rhs)) => {
                 ConstantValue::Matrix(lhs.clone() * rhs)
             }
-            _ => panic!(),
-        }
+            (lhs, rhs) => ConstantValue::Tensor(lhs.to_tensor() * rhs.to_tensor()),
+        };
+
+        Ok(result)
     }
 
-    pub fn div(self, rhs: &ConstantValue) -> ConstantValue {
-        match (self, rhs) {
+    pub fn mul(&self, rhs: ConstantValue) -> ConstantValue {
+        self.try_mul(rhs).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_div(self, rhs: &ConstantValue) -> Result<ConstantValue, SymbolicError> {
+        self.check_operands(rhs)?;
+        let result = match (self, rhs) {
             (ConstantValue::Scalar(lhs), ConstantValue::Scalar(rhs)) => {
                 ConstantValue::Scalar(lhs / rhs)
             }
-            (ConstantValue::Scalar(lhs), ConstantValue::Tensor(rhs)) => {
-                ConstantValue::Tensor(lhs / rhs.clone())
-            }
-            (ConstantValue::Scalar(lhs), ConstantValue::Matrix(rhs)) => {
-                ConstantValue::Matrix(lhs / rhs.clone())
-            }
+            // The operators of the tensors and matrices divide the tensor by the scalar the other way around.
+            (ConstantValue::Scalar(lhs), ConstantValue::Tensor(_)) => rhs.clone().map(|r| lhs / r),
+            (ConstantValue::Scalar(lhs), ConstantValue::Matrix(_)) => rhs.clone().map(|r| lhs / r),
             (ConstantValue::Tensor(lhs), ConstantValue::Tensor(rhs)) => {
                 ConstantValue::Tensor(lhs / rhs)
             }
@@ -181,7 +274,348 @@ impl ConstantValue {
             (ConstantValue::Matrix(lhs), ConstantValue::Scalar(rhs)) => {
                 ConstantValue::Matrix(lhs / rhs)
             }
-            _ => panic!(),
+            (lhs, rhs) => ConstantValue::Tensor(lhs.to_tensor() / rhs.to_tensor()),
+        };
+
+        Ok(result)
+    }
+}
+
+impl Div<&ConstantValue> for ConstantValue {
+    type Output = ConstantValue;
+
+    fn div(self, rhs: &ConstantValue) -> Self::Output {
+        self.try_div(rhs).unwrap_or_else(|e| panic!("{}", e))
+    }
+}
+
+impl ConstantValue {
+    pub fn map(self, f: impl Fn(f64) -> f64) -> ConstantValue {
+        match self {
+            ConstantValue::Scalar(v) => ConstantValue::Scalar(f(v)),
+            ConstantValue::Tensor(v) => {
+                if f(0.0) == 0.0 {
+                    let mut v = v;
+                    v.elems_mut().values_mut().for_each(|e| *e = f(*e));
+                    return ConstantValue::Tensor(v);
+                }
+
+                // Implicit zeros are mapped to non-zero values, so every index is visited.
+                let sizes = ConstantValue::Tensor(v.clone()).sizes();
+                let elems = indices_cartesian_product(&sizes)
+                    .into_iter()
+                    .map(|indices| {
+                        let e = f(v[&indices]);
+                        (indices, e)
+                    })
+                    .collect();
+                ConstantValue::Tensor(SparseTensor::from(sizes, elems).unwrap())
+            }
+            ConstantValue::Matrix(mut v) => {
+                v.elems_mut().iter_mut().for_each(|e| *e = f(*e));
+                ConstantValue::Matrix(v)
+            }
+        }
+    }
+
+    /// Maps each fiber along `rank` through `f`, which returns either one value or as many values as the fiber has.
+    /// Values of lower rank are regarded as having fibers of length 1.
+    pub fn map_rank(
+        &self,
+        rank: RankIndex,
+        f: impl Fn(&[f64]) -> Vec<f64>,
+    ) -> Result<ConstantValue, SymbolicError> {
+        let mut sizes = self.sizes();
+        if rank >= sizes.len() {
+            return Ok(self.clone().map(|v| f(&[v])[0]));
+        }
+
+        let t = self.to_tensor();
+        let len = sizes[rank];
+        sizes[rank] = 1;
+        let mut result_len = len;
+        let mut elems = HashMap::new();
+        for mut indices in indices_cartesian_product(&sizes) {
+            let fiber = (0..len)
+                .map(|j| {
+                    indices[rank] = j;
+                    t[&indices]
+                })
+                .collect::<Vec<_>>();
+            let mapped = f(&fiber);
+            result_len = mapped.len();
+            for (j, v) in mapped.into_iter().enumerate() {
+                if v != 0.0 {
+                    indices[rank] = j;
+                    elems.insert(indices.clone(), v);
+                }
+            }
+        }
+        sizes[rank] = result_len;
+
+        Ok(ConstantValue::Tensor(SparseTensor::from(sizes, elems)?))
+    }
+
+    pub fn zip_map(
+        &self,
+        rhs: &ConstantValue,
+        f: impl Fn(f64, f64) -> f64,
+    ) -> Result<ConstantValue, SymbolicError> {
+        match (self, rhs) {
+            (ConstantValue::Scalar(l), ConstantValue::Scalar(r)) => {
+                Ok(ConstantValue::Scalar(f(*l, *r)))
+            }
+            (ConstantValue::Scalar(l), r) => Ok(r.clone().map(|r| f(*l, r))),
+            (l, ConstantValue::Scalar(r)) => Ok(l.clone().map(|l| f(l, *r))),
+            (ConstantValue::Matrix(l), ConstantValue::Matrix(r)) => {
+                if !l.is_same_size(r) {
+                    return Err(SymbolicError::DimensionMismatch);
+                }
+                let elems = l
+                    .elems()
+                    .iter()
+                    .zip(r.elems().iter())
+                    .map(|(&l, &r)| f(l, r))
+                    .collect();
+                Ok(ConstantValue::Matrix(Matrix::from(l.rows(), elems)?))
+            }
+            (l, r) => {
+                let sizes = l.sizes();
+                if sizes != r.sizes() {
+                    return Err(SymbolicError::DimensionMismatch);
+                }
+                let (l, r) = (l.to_tensor(), r.to_tensor());
+                let elems = indices_cartesian_product(&sizes)
+                    .into_iter()
+                    .map(|indices| {
+                        let e = f(l[&indices], r[&indices]);
+                        (indices, e)
+                    })
+                    .filter(|(_, e)| *e != 0.0)
+                    .collect();
+                Ok(ConstantValue::Tensor(SparseTensor::from(sizes, elems)?))
+            }
         }
     }
+
+    pub fn to_tensor(&self) -> SparseTensor {
+        match self {
+            ConstantValue::Scalar(v) => {
+                SparseTensor::from(vec![], vec![(vec![], *v)].into_iter().collect()).unwrap()
+            }
+            ConstantValue::Tensor(v) => v.clone(),
+            ConstantValue::Matrix(v) => {
+                let mut elems = HashMap::new();
+                for j in 0..v.cols() {
+                    for i in 0..v.rows() {
+                        if v[(i, j)] != 0.0 {
+                            elems.insert(vec![i, j], v[(i, j)]);
+                        }
+                    }
+                }
+                SparseTensor::from(vec![v.rows(), v.cols()], elems).unwrap()
+            }
+        }
+    }
+
+    pub fn to_matrix(&self) -> Result<Matrix, SymbolicError> {
+        match self {
+            ConstantValue::Scalar(v) => Ok(Matrix::from(1, vec![*v])?),
+            ConstantValue::Tensor(v) => {
+                let v = v.reduce_1dimension_rank();
+                match v.rank() {
+                    0 => Ok(Matrix::from(1, vec![v[&[]]])?),
+                    1 => Ok(Matrix::from(v.size(0), v.to_vec())?),
+                    2 => Ok(v.to_mat()),
+                    _ => Err(SymbolicError::DimensionMismatch),
+                }
+            }
+            ConstantValue::Matrix(v) => Ok(v.clone()),
+        }
+    }
+
+    pub fn t(&self) -> Result<ConstantValue, SymbolicError> {
+        match self {
+            ConstantValue::Scalar(v) => Ok(ConstantValue::Scalar(*v)),
+            _ => Ok(ConstantValue::Matrix(self.to_matrix()?.t())),
+        }
+    }
+
+    pub fn inv(&self) -> Result<ConstantValue, SymbolicError> {
+        match self {
+            ConstantValue::Scalar(v) => Ok(ConstantValue::Scalar(1.0 / v)),
+            _ => Ok(ConstantValue::Matrix(self.to_matrix()?.getrf()?.getri()?)),
+        }
+    }
+
+    pub fn det(&self) -> Result<ConstantValue, SymbolicError> {
+        match self {
+            ConstantValue::Scalar(v) => Ok(ConstantValue::Scalar(*v)),
+            _ => {
+                let lu = self.to_matrix()?.getrf()?;
+                // Each row interchange recorded by the LU decomposition flips the sign.
+                let sign =
+                    lu.1.iter()
+                        .enumerate()
+                        .filter(|&(i, &p)| p as usize != i + 1)
+                        .count();
+                let det = lu.0.trdet();
+                Ok(ConstantValue::Scalar(if sign % 2 == 0 {
+                    det
+                } else {
+                    -det
+                }))
+            }
+        }
+    }
+
+    /// The lower triangular `L` with `A = L L^T`, for a positive definite `A`.
+    pub fn cholesky(&self) -> Result<ConstantValue, SymbolicError> {
+        match self {
+            ConstantValue::Scalar(v) => Ok(ConstantValue::Scalar(v.sqrt())),
+            _ => {
+                // The upper triangle is left as it was by potrf.
+                let POTRF(mut l) = self.to_matrix()?.potrf()?;
+                for j in 1..l.cols() {
+                    for i in 0..j {
+                        l[(i, j)] = 0.0;
+                    }
+                }
+                Ok(ConstantValue::Matrix(l))
+            }
+        }
+    }
+
+    /// The lower triangle in the ranks 0 and 1 with the halved diagonal, keeping the other ranks.
+    pub fn lower_half(&self) -> Result<ConstantValue, SymbolicError> {
+        match self {
+            ConstantValue::Scalar(v) => Ok(ConstantValue::Scalar(0.5 * v)),
+            ConstantValue::Matrix(m) => Ok(ConstantValue::Matrix(lower_half(m))),
+            ConstantValue::Tensor(t) => {
+                let elems = t
+                    .elems()
+                    .iter()
+                    .filter_map(|(indices, &v)| {
+                        let (i, j) = (indices[0], indices.get(1).copied().unwrap_or(0));
+                        match i.cmp(&j) {
+                            Ordering::Less => None,
+                            Ordering::Equal => Some((indices.clone(), 0.5 * v)),
+                            Ordering::Greater => Some((indices.clone(), v)),
+                        }
+                    })
+                    .collect();
+                Ok(ConstantValue::Tensor(SparseTensor::from(
+                    self.sizes(),
+                    elems,
+                )?))
+            }
+        }
+    }
+
+    /// `A^-1 B` for a positive definite `A`.
+    pub fn solve(&self, b: &ConstantValue) -> Result<ConstantValue, SymbolicError> {
+        match self {
+            ConstantValue::Scalar(v) => Ok(b.clone().map(|b| b / v)),
+            _ => ConstantValue::matrix_like(self.to_matrix()?.potrf()?.potrs(b.to_matrix()?)?, b),
+        }
+    }
+
+    /// Puts the matrix back into a vector when `like` is one, as the sizes of the expressions tell.
+    pub(crate) fn matrix_like(
+        m: Matrix,
+        like: &ConstantValue,
+    ) -> Result<ConstantValue, SymbolicError> {
+        match like {
+            ConstantValue::Tensor(v) if v.rank() == 1 => {
+                let elems = m
+                    .elems()
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, &e)| e != 0.0)
+                    .map(|(i, &e)| (vec![i], e))
+                    .collect();
+                Ok(ConstantValue::Tensor(SparseTensor::from(
+                    vec![m.rows()],
+                    elems,
+                )?))
+            }
+            _ => Ok(ConstantValue::Matrix(m)),
+        }
+    }
+
+    /// The logarithm of the determinant of a positive definite matrix.
+    pub fn log_det(&self) -> Result<ConstantValue, SymbolicError> {
+        match self {
+            ConstantValue::Scalar(v) => Ok(ConstantValue::Scalar(v.ln())),
+            _ => {
+                let POTRF(l) = self.to_matrix()?.potrf()?;
+                let log_det = (0..l.rows()).map(|i| l[(i, i)].ln()).sum::<f64>();
+                Ok(ConstantValue::Scalar(2.0 * log_det))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use opensrdk_linear_algebra::{Matrix, Tensor};
+
+    use crate::{ConstantValue, SymbolicError};
+
+    #[test]
+    fn it_works() {
+        let a = ConstantValue::Tensor(vec![1.0, 2.0].into());
+        let b = ConstantValue::Matrix(Matrix::from(2, vec![1.0; 4]).unwrap());
+
+        assert!(matches!(
+            a.try_into_scalar(),
+            Err(SymbolicError::TypeMismatch { .. })
+        ));
+        assert!(matches!(
+            a.try_as_matrix_ref(),
+            Err(SymbolicError::TypeMismatch { .. })
+        ));
+        assert_eq!(a.as_tensor_ref().size(0), 2);
+        assert_eq!(b.as_matrix_ref().rows(), 2);
+        assert!(matches!(
+            a.try_mul(b),
+            Err(SymbolicError::DimensionMismatch)
+        ));
+        assert!(matches!(
+            a.try_add(ConstantValue::Tensor(vec![1.0; 3].into())),
+            Err(SymbolicError::DimensionMismatch)
+        ));
+        assert_eq!(
+            a.try_add(ConstantValue::Scalar(1.0)).unwrap(),
+            ConstantValue::Tensor(vec![2.0, 3.0].into())
+        );
+        assert_eq!(
+            ConstantValue::Scalar(3.0) / &ConstantValue::Scalar(2.0),
+            ConstantValue::Scalar(1.5)
+        );
+    }
+
+    #[test]
+    fn it_works2() {
+        let a = ConstantValue::Tensor(vec![1.0, 0.0, 4.0].into());
+        let b = ConstantValue::Matrix(Matrix::from(2, vec![1.0, 2.0, 4.0, 8.0]).unwrap());
+        let s = ConstantValue::Scalar(2.0);
+
+        assert_eq!(
+            s.try_sub(a.clone()).unwrap().to_tensor(),
+            ConstantValue::Tensor(vec![1.0, 2.0, -2.0].into()).to_tensor()
+        );
+        assert_eq!(
+            a.try_add(s.clone()).unwrap().to_tensor(),
+            ConstantValue::Tensor(vec![3.0, 2.0, 6.0].into()).to_tensor()
+        );
+        assert_eq!(
+            s.clone().try_div(&b).unwrap(),
+            ConstantValue::Matrix(Matrix::from(2, vec![2.0, 1.0, 0.5, 0.25]).unwrap())
+        );
+        assert_eq!(
+            s.try_sub(b).unwrap(),
+            ConstantValue::Matrix(Matrix::from(2, vec![1.0, 0.0, -2.0, -6.0]).unwrap())
+        );
+    }
 }
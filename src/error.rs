@@ -0,0 +1,65 @@
+use crate::Size;
+use opensrdk_linear_algebra::{MatrixError, TensorError};
+
+#[derive(thiserror::Error, Debug)]
+pub enum SymbolicError {
+    #[error("Variable {0} is not assigned.")]
+    UnassignedVariable(String),
+    #[error("Variable {id} has sizes {expected:?} but is assigned a value with sizes {actual:?}")]
+    VariableSizeMismatch {
+        id: String,
+        expected: Vec<Size>,
+        actual: Vec<usize>,
+    },
+    #[error("Variable {id} appears with both sizes {lhs:?} and {rhs:?}.")]
+    VariableSizeConflict {
+        id: String,
+        lhs: Vec<Size>,
+        rhs: Vec<Size>,
+    },
+    #[error("Element {0:?} of the partial variable is not a scalar.")]
+    NonScalarElement(Vec<usize>),
+    #[error("Cannot {operation} expressions of sizes {lhs:?} and {rhs:?}.")]
+    SizeMismatch {
+        operation: String,
+        lhs: Vec<Size>,
+        rhs: Vec<Size>,
+    },
+    #[error("Expected a {expected} value but found a {found}.")]
+    TypeMismatch {
+        expected: &'static str,
+        found: &'static str,
+    },
+    #[error("Cannot {operation} a {lhs} and a {rhs}.")]
+    UnsupportedOperands {
+        operation: String,
+        lhs: &'static str,
+        rhs: &'static str,
+    },
+    #[error("Rank {rank} is not 1-dimension in terms[{}] and terms[{}].", terms[0], terms[1])]
+    RankConflict { rank: usize, terms: [usize; 2] },
+    #[error(
+        "Dimension {dim} is bound to {bound} but a rank of length {actual} is assigned to it."
+    )]
+    DimensionConflict {
+        dim: String,
+        bound: usize,
+        actual: usize,
+    },
+    #[error("The program takes {expected} values but {actual} are given.")]
+    InputCountMismatch { expected: usize, actual: usize },
+    #[error("Output {0} has no value of its own, like a Kronecker delta outside a dot product.")]
+    NonStandaloneOutput(usize),
+    #[error("Wildcard {0} of the replacement is not bound by the pattern.")]
+    UnboundWildcard(String),
+    #[error("The rules still rewrite the expression after {0} passes.")]
+    RewriteNotConverged(usize),
+    #[error("Dimension mismatch.")]
+    DimensionMismatch,
+    #[error("The dimension of the Kronecker delta cannot be determined.")]
+    UndeterminedSize,
+    #[error("Matrix error: {0}")]
+    Matrix(#[from] MatrixError),
+    #[error("Tensor error: {0}")]
+    Tensor(#[from] TensorError),
+}
@@ -1,37 +1,101 @@
-use crate::{AbstractSize, ConstantValue, Expression, ExpressionArray};
-use std::collections::HashMap;
+use crate::{ConstantValue, Expression, ExpressionArray, ExpressionRef, Size, SymbolicError};
+use opensrdk_linear_algebra::indices_cartesian_product;
+use std::{cell::RefCell, collections::HashMap};
+
+thread_local! {
+    /// The results of the shared nodes assigned in the running `try_assign`.
+    static ASSIGNED: RefCell<Option<HashMap<usize, (ExpressionRef, Expression)>>> = const { RefCell::new(None) };
+}
 
 impl Expression {
-    pub fn assign(self, variables: &HashMap<&str, ConstantValue>) -> Expression {
+    pub fn try_assign(
+        self,
+        variables: &HashMap<&str, ConstantValue>,
+    ) -> Result<Expression, SymbolicError> {
+        let dims = self.bind_dims(variables)?;
+        let outer = ASSIGNED.with(|a| a.replace(Some(HashMap::new())));
+        let result = self._assign(variables, &dims);
+        ASSIGNED.with(|a| a.replace(outer));
+
+        result
+    }
+
+    pub(crate) fn _assign(
+        self,
+        variables: &HashMap<&str, ConstantValue>,
+        dims: &HashMap<String, usize>,
+    ) -> Result<Expression, SymbolicError> {
         match self {
-            Expression::Variable(id, sizes) => {
-                let v = variables.get(id.as_str());
-
-                match v {
-                    Some(v) => {
-                        if sizes != v.sizes().into_abstract_size() {
-                            panic!("Variable {} has sizes {:?} but is assigned a value with sizes {:?}", id, sizes, v.sizes());
-                        }
-                        v.clone().into()
-                    }
-                    None => Expression::Variable(id.clone(), sizes.clone()),
-                }
+            Expression::Variable(id, sizes) => match variables.get(id.as_str()) {
+                Some(v) => Ok(v.clone().into()),
+                None => Ok(Expression::Variable(
+                    id,
+                    Size::substitute_dims(&sizes, dims),
+                )),
+            },
+            Expression::Constant(_) => Ok(self),
+            Expression::PartialVariable(v) => {
+                let elems = indices_cartesian_product(v.sizes())
+                    .into_iter()
+                    .map(|indices| {
+                        let e = v[indices.as_slice()].clone()._assign(variables, dims)?;
+                        Ok((indices, e))
+                    })
+                    .collect::<Result<HashMap<_, _>, SymbolicError>>()?;
+
+                Ok(Expression::PartialVariable(ExpressionArray::from_factory(
+                    v.sizes().to_vec(),
+                    |indices| elems[indices].clone(),
+                )))
             }
-            Expression::Constant(_) => self,
-            Expression::PartialVariable(v) => Expression::PartialVariable(
-                ExpressionArray::from_factory(v.sizes().to_vec(), |indices| {
-                    v[indices].clone().assign(variables)
-                }),
-            ),
-            Expression::Add(l, r) => l.assign(variables) + r.assign(variables),
-            Expression::Sub(l, r) => l.assign(variables) - r.assign(variables),
-            Expression::Mul(l, r) => l.assign(variables) * r.assign(variables),
-            Expression::Div(l, r) => l.assign(variables) / r.assign(variables),
-            Expression::Neg(v) => -v.assign(variables),
-            Expression::Transcendental(v) => v.assign(variables),
-            Expression::Tensor(v) => v.assign(variables),
-            Expression::Matrix(v) => v.assign(variables),
+            Expression::Add(l, r) => l
+                ._assign(variables, dims)?
+                .try_add(r._assign(variables, dims)?),
+            Expression::Sub(l, r) => l
+                ._assign(variables, dims)?
+                .try_sub(r._assign(variables, dims)?),
+            Expression::Mul(l, r) => l
+                ._assign(variables, dims)?
+                .try_mul(r._assign(variables, dims)?),
+            Expression::Div(l, r) => l
+                ._assign(variables, dims)?
+                .try_div(r._assign(variables, dims)?),
+            Expression::Neg(v) => Ok(-v._assign(variables, dims)?),
+            Expression::Transcendental(v) => v._assign(variables, dims),
+            Expression::Tensor(v) => v._assign(variables, dims),
+            Expression::Matrix(v) => v._assign(variables, dims),
+        }
+    }
+
+    pub fn assign(self, variables: &HashMap<&str, ConstantValue>) -> Expression {
+        self.try_assign(variables)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+}
+
+impl ExpressionRef {
+    /// Assigns the node once per `try_assign` however many times it is shared.
+    pub(crate) fn _assign(
+        self,
+        variables: &HashMap<&str, ConstantValue>,
+        dims: &HashMap<String, usize>,
+    ) -> Result<Expression, SymbolicError> {
+        if let Some(result) = ASSIGNED.with(|a| {
+            a.borrow()
+                .as_ref()
+                .and_then(|a| a.get(&self.id()).map(|(_, result)| result.clone()))
+        }) {
+            return Ok(result);
         }
+
+        let result = self.as_ref().clone()._assign(variables, dims)?;
+        ASSIGNED.with(|a| {
+            if let Some(a) = a.borrow_mut().as_mut() {
+                a.insert(self.id(), (self.clone(), result.clone()));
+            }
+        });
+
+        Ok(result)
     }
 }
 
@@ -41,7 +105,10 @@ mod tests {
 
     use opensrdk_linear_algebra::sparse::SparseTensor;
 
-    use crate::{new_variable, new_variable_tensor, AbstractSize, ConstantValue, Expression};
+    use crate::{
+        new_variable, new_variable_tensor, AbstractSize, ConstantValue, Expression, Size,
+        SymbolicError,
+    };
 
     #[test]
     fn it_works1() {
@@ -77,4 +144,18 @@ mod tests {
             ))
         )
     }
+
+    #[test]
+    fn it_works3() {
+        let id = "x";
+        let ex = new_variable_tensor(id.to_string(), vec![Size::Many]);
+        let mut hash = HashMap::new();
+        hash.insert(id, ConstantValue::Scalar(2.0));
+
+        let result = (ex.clone() * 3.0).try_assign(&hash);
+        assert!(matches!(
+            result,
+            Err(SymbolicError::VariableSizeMismatch { .. })
+        ));
+    }
 }
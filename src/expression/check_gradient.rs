@@ -0,0 +1,196 @@
+use crate::{ConstantValue, Expression, Size, SymbolicError};
+use opensrdk_linear_algebra::{indices_cartesian_product, sparse::SparseTensor, Tensor};
+use std::collections::HashMap;
+
+/// The worst disagreement between the derivative by a variable and its central finite difference.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GradientCheck {
+    pub variable_id: String,
+    /// The indices of the element of the expression where the worst error is found.
+    pub indices: Vec<usize>,
+    /// The indices of the element of the variable where the worst error is found.
+    pub variable_indices: Vec<usize>,
+    pub symbolic: f64,
+    pub numeric: f64,
+    /// `|symbolic - numeric|` divided by the larger of their magnitudes, or by 1 when both are smaller.
+    pub relative_error: f64,
+}
+
+fn moved(value: &ConstantValue, indices: &[usize], delta: f64) -> ConstantValue {
+    match value {
+        ConstantValue::Scalar(v) => ConstantValue::Scalar(v + delta),
+        ConstantValue::Tensor(v) => {
+            let mut v = v.clone();
+            v[indices] += delta;
+            ConstantValue::Tensor(v)
+        }
+        ConstantValue::Matrix(v) => {
+            let mut v = v.clone();
+            v[(indices[0], indices[1])] += delta;
+            ConstantValue::Matrix(v)
+        }
+    }
+}
+
+/// All the indices of a value of `sizes`, which is the only one of a scalar.
+fn all_indices(sizes: &[usize]) -> Vec<Vec<usize>> {
+    match sizes.len() {
+        0 => vec![vec![]],
+        _ => indices_cartesian_product(sizes),
+    }
+}
+
+/// The element of `t` at `indices`, where the ranks `t` does not have or has of size 1 are broadcast.
+fn broadcast_elem(t: &SparseTensor, indices: &[usize]) -> f64 {
+    let indices = (0..t.rank())
+        .map(|rank| match t.size(rank) {
+            1 => 0,
+            _ => indices.get(rank).copied().unwrap_or(0),
+        })
+        .collect::<Vec<_>>();
+
+    t[&indices]
+}
+
+impl Expression {
+    /// Compares `differential` with central finite differences of step `eps` at `assignment`, element by element, and reports the worst relative error for each of `variable_ids`.
+    pub fn check_gradient(
+        &self,
+        variable_ids: &[&str],
+        assignment: &HashMap<&str, ConstantValue>,
+        eps: f64,
+    ) -> Result<Vec<GradientCheck>, SymbolicError> {
+        let rank = Size::effective_rank(&self.sizes());
+        let output_sizes = self.evaluate(assignment)?.sizes();
+
+        self.differential(variable_ids)
+            .iter()
+            .zip(variable_ids.iter())
+            .map(|(d, &id)| {
+                let value = Expression::evaluate_variable(id, assignment)?;
+                let variable_sizes = value.sizes();
+                // The ranks of the derivative follow both those of the expression and those of the variable.
+                let offset = rank.max(variable_sizes.len());
+                let symbolic = d.evaluate(assignment)?.to_tensor();
+
+                let mut worst = None::<GradientCheck>;
+                for variable_indices in all_indices(&variable_sizes) {
+                    let difference = |delta: f64| {
+                        let mut moved_assignment = assignment.clone();
+                        moved_assignment.insert(id, moved(&value, &variable_indices, delta));
+                        self.evaluate(&moved_assignment).map(|v| v.to_tensor())
+                    };
+                    let (plus, minus) = (difference(eps)?, difference(-eps)?);
+
+                    for indices in all_indices(&output_sizes) {
+                        let mut derivative_indices = indices.clone();
+                        derivative_indices.resize(offset, 0);
+                        derivative_indices.extend(variable_indices.iter());
+
+                        let s = broadcast_elem(&symbolic, &derivative_indices);
+                        let n = (broadcast_elem(&plus, &indices)
+                            - broadcast_elem(&minus, &indices))
+                            / (2.0 * eps);
+                        let relative_error = (s - n).abs() / s.abs().max(n.abs()).max(1.0);
+
+                        if worst
+                            .as_ref()
+                            .map_or(true, |w| relative_error > w.relative_error)
+                        {
+                            worst = Some(GradientCheck {
+                                variable_id: id.to_owned(),
+                                indices,
+                                variable_indices: variable_indices.clone(),
+                                symbolic: s,
+                                numeric: n,
+                                relative_error,
+                            });
+                        }
+                    }
+                }
+
+                Ok(worst.unwrap())
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use opensrdk_linear_algebra::Matrix;
+
+    use crate::{new_variable, new_variable_tensor, ConstantValue, Expression, Size};
+
+    #[test]
+    fn it_works() {
+        let x = new_variable("x".to_string());
+        let y = new_variable_tensor("y".to_string(), vec![Size::Many]);
+        let a = new_variable_tensor("a".to_string(), vec![Size::Many, Size::Many]);
+
+        let expression = (x.clone() * x.clone().sin()).exp()
+            + Expression::from(vec![1.0, -2.0, 0.5]).dot(y.clone().tanh(), &[[0, 0]]) * x.clone()
+            + a.clone().det();
+
+        let mut hash = HashMap::new();
+        hash.insert("x", ConstantValue::Scalar(0.3));
+        hash.insert("y", ConstantValue::Tensor(vec![0.2, -0.4, 1.1].into()));
+        hash.insert(
+            "a",
+            ConstantValue::Matrix(Matrix::from(2, vec![2.0, 0.5, 0.3, 1.5]).unwrap()),
+        );
+
+        let checks = expression
+            .check_gradient(&["x", "y", "a"], &hash, 1e-6)
+            .unwrap();
+
+        assert_eq!(checks.len(), 3);
+        for check in checks.iter() {
+            assert!(check.relative_error < 1e-6, "{:?}", check);
+        }
+        assert_eq!(checks[1].variable_id, "y");
+    }
+
+    #[test]
+    fn it_works2() {
+        let x = new_variable_tensor("x".to_string(), vec![Size::Many]);
+        let a = Expression::from(Matrix::from(2, vec![1.0, 3.0, 2.0, 4.0]).unwrap());
+
+        // A tensor-valued expression is checked for every element of it.
+        let expression = a.dot(x.clone().exp(), &[[1, 0]]);
+
+        let mut hash = HashMap::new();
+        hash.insert("x", ConstantValue::Tensor(vec![0.5, -1.0].into()));
+
+        let checks = expression.check_gradient(&["x"], &hash, 1e-6).unwrap();
+        assert!(checks[0].relative_error < 1e-6, "{:?}", checks[0]);
+    }
+
+    #[test]
+    fn it_works3() {
+        let a = new_variable_tensor("a".to_string(), vec![Size::Many, Size::Many]);
+        let u = Expression::from(vec![1.0, -2.0]);
+        let v = Expression::from(vec![0.5, 3.0]);
+
+        let mut hash = HashMap::new();
+        hash.insert(
+            "a",
+            ConstantValue::Matrix(Matrix::from(2, vec![2.0, 0.5, 0.3, 1.5]).unwrap()),
+        );
+
+        // The inputs are not symmetric, so the transposes are told apart.
+        for expression in [
+            u.clone()
+                .dot(a.clone().t(), &[[0, 0]])
+                .dot(v.clone(), &[[1, 0]]),
+            u.clone()
+                .dot(a.clone().inv(), &[[0, 0]])
+                .dot(v.clone(), &[[1, 0]]),
+            a.clone().t().inv(),
+        ] {
+            let checks = expression.check_gradient(&["a"], &hash, 1e-6).unwrap();
+            assert!(checks[0].relative_error < 1e-6, "{:?}", checks[0]);
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use crate::{Expression, ExpressionArray, ExpressionRef, SymbolicError, TensorExpression};
+use opensrdk_linear_algebra::indices_cartesian_product;
+use std::collections::HashMap;
+
+fn pair(children: Vec<Expression>) -> (ExpressionRef, ExpressionRef) {
+    let mut children = children.into_iter();
+    let l = children.next().unwrap();
+    let r = children.next().unwrap();
+
+    (l.into(), r.into())
+}
+
+pub(crate) fn is_kronecker_deltas(e: &Expression) -> bool {
+    match e {
+        Expression::Tensor(v) => matches!(
+            v.as_ref(),
+            TensorExpression::KroneckerDeltas(_) | TensorExpression::SizedKroneckerDeltas(_)
+        ),
+        _ => false,
+    }
+}
+
+impl Expression {
+    /// Whether the node has a value of its own, given whether each of its children has one.
+    /// Kronecker deltas only have one as terms of a dot product.
+    pub(crate) fn is_standalone(&self, children: &[bool]) -> bool {
+        match self {
+            _ if is_kronecker_deltas(self) => false,
+            Expression::Tensor(v) => match v.as_ref() {
+                TensorExpression::DotProduct { terms, .. } => terms
+                    .iter()
+                    .zip(children)
+                    .all(|(t, &c)| c || is_kronecker_deltas(t)),
+                _ => children.iter().all(|&c| c),
+            },
+            _ => children.iter().all(|&c| c),
+        }
+    }
+
+    /// The operands of the node, in the order `with_children` takes them back.
+    pub(crate) fn children(&self) -> Vec<Expression> {
+        match self {
+            Expression::Variable(_, _) | Expression::Constant(_) => vec![],
+            Expression::PartialVariable(v) => indices_cartesian_product(v.sizes())
+                .iter()
+                .map(|indices| v[indices.as_slice()].clone())
+                .collect(),
+            Expression::Add(l, r)
+            | Expression::Sub(l, r)
+            | Expression::Mul(l, r)
+            | Expression::Div(l, r) => vec![l.as_ref().clone(), r.as_ref().clone()],
+            Expression::Neg(v) => vec![v.as_ref().clone()],
+            Expression::Transcendental(v) => v.children(),
+            Expression::Tensor(v) => v.children(),
+            Expression::Matrix(v) => v.children(),
+        }
+    }
+
+    /// Rebuilds the node on the given operands as it is, without any of the rewrites the operators do.
+    pub(crate) fn with_children(&self, children: Vec<Expression>) -> Expression {
+        match self {
+            Expression::Variable(_, _) | Expression::Constant(_) => self.clone(),
+            Expression::PartialVariable(v) => {
+                let elems = indices_cartesian_product(v.sizes())
+                    .into_iter()
+                    .zip(children)
+                    .collect::<HashMap<_, _>>();
+                Expression::PartialVariable(ExpressionArray::from_factory(
+                    v.sizes().to_vec(),
+                    |indices| elems[indices].clone(),
+                ))
+            }
+            Expression::Add(_, _) => {
+                let (l, r) = pair(children);
+                Expression::Add(l, r)
+            }
+            Expression::Sub(_, _) => {
+                let (l, r) = pair(children);
+                Expression::Sub(l, r)
+            }
+            Expression::Mul(_, _) => {
+                let (l, r) = pair(children);
+                Expression::Mul(l, r)
+            }
+            Expression::Div(_, _) => {
+                let (l, r) = pair(children);
+                Expression::Div(l, r)
+            }
+            Expression::Neg(_) => Expression::Neg(children[0].clone().into()),
+            Expression::Transcendental(v) => v.with_children(children),
+            Expression::Tensor(v) => v.with_children(children),
+            Expression::Matrix(v) => v.with_children(children),
+        }
+    }
+
+    /// Rebuilds the node on the given operands with the operators, which apply their rewrites.
+    pub(crate) fn rebuilt(&self, children: Vec<Expression>) -> Result<Expression, SymbolicError> {
+        let mut operands = children.clone().into_iter();
+        let mut next = || operands.next().unwrap();
+
+        match self {
+            Expression::Add(_, _) => next().try_add(next()),
+            Expression::Sub(_, _) => next().try_sub(next()),
+            Expression::Mul(_, _) => next().try_mul(next()),
+            Expression::Div(_, _) => next().try_div(next()),
+            Expression::Neg(_) => Ok(-next()),
+            Expression::Transcendental(v) => v.rebuilt(children),
+            Expression::Tensor(v) => Ok(v.rebuilt(children)),
+            Expression::Matrix(v) => v.rebuilt(children),
+            _ => Ok(self.with_children(children)),
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use crate::{ConstantValue, Expression, ExpressionRef, Size, SymbolicError};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Expressions rewritten to refer to the intermediate subexpressions they share.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CommonSubexpressions {
+    /// The intermediates in the order they are computed. Each may refer to the ones before it as a variable.
+    pub bindings: Vec<(String, Expression)>,
+    pub outputs: Vec<Expression>,
+}
+
+struct Occurrences {
+    counts: HashMap<usize, usize>,
+    order: Vec<ExpressionRef>,
+}
+
+impl Occurrences {
+    fn visit(&mut self, e: &Expression) -> ExpressionRef {
+        let node = ExpressionRef::from(e.clone());
+        if let Some(count) = self.counts.get_mut(&node.id()) {
+            *count += 1;
+            return node;
+        }
+
+        self.counts.insert(node.id(), 1);
+        e.children().iter().for_each(|c| {
+            self.visit(c);
+        });
+        self.order.push(node.clone());
+
+        node
+    }
+}
+
+impl CommonSubexpressions {
+    /// Binds every non-trivial subexpression which appears more than once among `expressions`, e.g. a value and all of its derivatives.
+    pub fn new(expressions: &[Expression]) -> Self {
+        let mut occurrences = Occurrences {
+            counts: HashMap::new(),
+            order: vec![],
+        };
+        let roots = expressions
+            .iter()
+            .map(|e| occurrences.visit(e))
+            .collect::<Vec<_>>();
+
+        let used = expressions
+            .iter()
+            .flat_map(|e| e.variable_ids())
+            .map(|id| id.to_owned())
+            .collect::<HashSet<_>>();
+        let mut ids = (0..)
+            .map(|i| format!("_{}", i))
+            .filter(|id| !used.contains(id));
+
+        let mut bindings = vec![];
+        let mut rewritten = HashMap::<usize, (Expression, bool)>::new();
+        for node in occurrences.order.iter() {
+            let (children, standalone): (Vec<_>, Vec<_>) = node
+                .children()
+                .iter()
+                .map(|c| rewritten[&ExpressionRef::from(c.clone()).id()].clone())
+                .unzip();
+            let is_leaf = children.is_empty();
+            let standalone = node.is_standalone(&standalone);
+            let e = node.with_children(children);
+
+            let e = if occurrences.counts[&node.id()] > 1 && !is_leaf && standalone {
+                let id = ids.next().unwrap();
+                let variable = Expression::Variable(id.clone(), node.sizes());
+                bindings.push((id, e));
+                variable
+            } else {
+                e
+            };
+            rewritten.insert(node.id(), (e, standalone));
+        }
+
+        let outputs = roots.iter().map(|r| rewritten[&r.id()].0.clone()).collect();
+
+        Self { bindings, outputs }
+    }
+
+    fn variable_sizes(&self) -> HashMap<&str, &[Size]> {
+        let bound = self
+            .bindings
+            .iter()
+            .map(|(id, _)| id.as_str())
+            .collect::<HashSet<_>>();
+
+        self.bindings
+            .iter()
+            .map(|(_, e)| e)
+            .chain(self.outputs.iter())
+            .flat_map(|e| e.variable_sizes())
+            .filter(|(id, _)| !bound.contains(id))
+            .collect()
+    }
+
+    /// Computes each intermediate once, and then the outputs.
+    pub fn evaluate(
+        &self,
+        variables: &HashMap<&str, ConstantValue>,
+    ) -> Result<Vec<ConstantValue>, SymbolicError> {
+        Size::bind_dims(self.variable_sizes(), variables)?;
+
+        let mut values = variables
+            .iter()
+            .map(|(&id, v)| (id, v.clone()))
+            .collect::<HashMap<_, _>>();
+        for (id, e) in self.bindings.iter() {
+            let v = e._evaluate(&values)?;
+            values.insert(id.as_str(), v);
+        }
+
+        self.outputs.iter().map(|e| e._evaluate(&values)).collect()
+    }
+
+    /// One line per intermediate and per output, the intermediates written as `t_{i}`.
+    pub fn tex_code(&self, symbols: &HashMap<&str, &str>) -> Vec<String> {
+        let names = self
+            .bindings
+            .iter()
+            .enumerate()
+            .map(|(i, (id, _))| (id.as_str(), format!("t_{{{}}}", i)))
+            .collect::<Vec<_>>();
+        let mut symbols = symbols.clone();
+        symbols.extend(names.iter().map(|(id, name)| (*id, name.as_str())));
+
+        names
+            .iter()
+            .zip(self.bindings.iter())
+            .map(|((_, name), (_, e))| format!("{} = {}", name, e.tex_code(&symbols)))
+            .chain(self.outputs.iter().map(|e| e.tex_code(&symbols)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{
+        new_variable, new_variable_tensor, CommonSubexpressions, ConstantValue, Expression, Size,
+    };
+
+    #[test]
+    fn it_works() {
+        let x = new_variable("x".to_string());
+        let y = new_variable("y".to_string());
+
+        let shared = (x.clone() * y.clone()).exp();
+        let f = shared.clone() + shared.clone().sin();
+        let mut expressions = vec![f.clone()];
+        expressions.extend(f.differential(&["x", "y"]));
+
+        let cse = CommonSubexpressions::new(&expressions);
+
+        let mut hash = HashMap::new();
+        hash.insert("x", ConstantValue::Scalar(0.3));
+        hash.insert("y", ConstantValue::Scalar(-1.2));
+
+        let values = cse.evaluate(&hash).unwrap();
+        for (value, e) in values.into_iter().zip(expressions.iter()) {
+            let expected = e.evaluate(&hash).unwrap().into_scalar();
+            assert!((value.into_scalar() - expected).abs() < 1e-12);
+        }
+
+        let symbols = vec![("x", "x"), ("y", "y")].into_iter().collect();
+        let lines = cse.tex_code(&symbols);
+        assert_eq!(lines.len(), cse.bindings.len() + expressions.len());
+        assert!(lines[0].starts_with("t_{0} = "));
+        // exp(xy) is written once and referred to by the value and both derivatives.
+        assert!(lines[..cse.bindings.len()]
+            .iter()
+            .any(|l| l.contains(r"\exp")));
+    }
+
+    #[test]
+    fn it_works2() {
+        let x = new_variable_tensor("x".to_owned(), vec![Size::Many]);
+        let mu = new_variable_tensor("mu".to_owned(), vec![Size::Many]);
+        let precision = new_variable_tensor("lambda".to_owned(), vec![Size::Many; 2]);
+
+        let pdf = (-0.5
+            * ((x.clone() - mu.clone())
+                .dot(precision, &[[0, 0]])
+                .dot(x.clone() - mu.clone(), &[[1, 0]])))
+        .exp();
+        let mut expressions = vec![pdf.clone()];
+        expressions.extend(pdf.differential(&["x", "mu", "lambda"]));
+
+        let cse = CommonSubexpressions::new(&expressions);
+        let exp = cse
+            .bindings
+            .iter()
+            .filter(|(_, e)| matches!(e, Expression::Transcendental(_)))
+            .count();
+        assert_eq!(exp, 1);
+
+        let symbols = vec![("x", "x"), ("mu", r"\mu"), ("lambda", r"\Lambda")]
+            .into_iter()
+            .collect();
+        let lines = cse.tex_code(&symbols);
+        assert_eq!(lines.len(), cse.bindings.len() + expressions.len());
+    }
+}
@@ -1,27 +1,137 @@
-use crate::Expression;
+use crate::{Expression, ExpressionRef, Size};
+use std::{cell::RefCell, collections::HashMap};
+
+type Differentials = HashMap<(usize, usize), (ExpressionRef, Vec<Expression>)>;
+
+thread_local! {
+    /// The derivatives of the shared nodes computed in the running `differential`.
+    static DIFFERENTIALS: RefCell<Option<Differentials>> = const { RefCell::new(None) };
+}
 
 impl Expression {
+    /// Runs `f` with its own memo of derivatives starting from `seeds`, and restores the outer one afterwards.
+    pub(crate) fn with_differentials<T>(seeds: Differentials, f: impl FnOnce() -> T) -> T {
+        let outer = DIFFERENTIALS.with(|d| d.replace(Some(seeds)));
+        let result = f();
+        DIFFERENTIALS.with(|d| d.replace(outer));
+
+        result
+    }
+
     pub fn differential(&self, variable_ids: &[&str]) -> Vec<Expression> {
+        Expression::with_differentials(HashMap::new(), || {
+            self._differential(variable_ids, Size::effective_rank(&self.sizes()))
+        })
+    }
+
+    pub(crate) fn _differential(
+        &self,
+        variable_ids: &[&str],
+        rank_offset: usize,
+    ) -> Vec<Expression> {
         match self {
-            Expression::Variable(id, sizes) => Expression::diff_variable(id, sizes, variable_ids),
+            Expression::Variable(id, sizes) => {
+                Expression::diff_variable(id, sizes, variable_ids, rank_offset)
+            }
             Expression::Constant(_) => vec![0.0.into(); variable_ids.len()],
-            Expression::PartialVariable(v) => Expression::diff_partial_variable(v, variable_ids),
-            Expression::Add(l, r) => Expression::diff_add(l, r, variable_ids),
-            Expression::Sub(l, r) => Expression::diff_sub(l, r, variable_ids),
-            Expression::Mul(l, r) => Expression::diff_mul(l, r, variable_ids),
-            Expression::Div(l, r) => Expression::diff_div(l, r, variable_ids),
-            Expression::Neg(v) => Expression::diff_neg(v, variable_ids),
-            Expression::Transcendental(v) => v.differential(variable_ids),
-            Expression::Tensor(v) => v.differential(variable_ids),
-            Expression::Matrix(v) => v.differential(variable_ids),
+            Expression::PartialVariable(v) => {
+                Expression::diff_partial_variable(v, variable_ids, rank_offset)
+            }
+            Expression::Add(l, r) => Expression::diff_add(l, r, variable_ids, rank_offset),
+            Expression::Sub(l, r) => Expression::diff_sub(l, r, variable_ids, rank_offset),
+            Expression::Mul(l, r) => Expression::diff_mul(l, r, variable_ids, rank_offset),
+            Expression::Div(l, r) => Expression::diff_div(l, r, variable_ids, rank_offset),
+            Expression::Neg(v) => Expression::diff_neg(v, variable_ids, rank_offset),
+            Expression::Transcendental(v) => v._differential(variable_ids, rank_offset),
+            Expression::Tensor(v) => v._differential(variable_ids, rank_offset),
+            Expression::Matrix(v) => v._differential(variable_ids, rank_offset),
         }
     }
+
+    /// Second derivatives, where `hessian(variable_ids)[i][j]` is the derivative of `differential(variable_ids)[j]` by `variable_ids[i]`.
+    ///
+    /// Every derivative places its ranks after the ranks of the expression it differentiates.
+    /// For a scalar expression and variables `i` and `j` of ranks `ri` and `rj`, the ranks of `j` are `rj..2rj` and those of `i` are `max(ri, 2rj)..max(ri, 2rj)+ri`; the other ranks are of size one.
+    pub fn hessian(&self, variable_ids: &[&str]) -> Vec<Vec<Expression>> {
+        let second = self
+            .differential(variable_ids)
+            .iter()
+            .map(|d| d.differential(variable_ids))
+            .collect::<Vec<_>>();
+
+        (0..variable_ids.len())
+            .map(|i| second.iter().map(|dj| dj[i].clone()).collect())
+            .collect()
+    }
+
+    /// The `n`-th derivative by `variable_id`, each derivative placing its ranks after those of the previous one.
+    pub fn nth_differential(&self, variable_id: &str, n: usize) -> Expression {
+        (0..n).fold(self.clone(), |d, _| {
+            d.differential(&[variable_id]).remove(0)
+        })
+    }
+
+    /// The derivatives of a scalar node by each of its distinct operands, reusing the rules of `_differential` by seeding the operands as the variables.
+    pub(crate) fn partials(&self) -> Vec<(ExpressionRef, Expression)> {
+        let mut operands = Vec::<ExpressionRef>::new();
+        for c in self.children() {
+            let c = ExpressionRef::from(c);
+            if !operands.contains(&c) {
+                operands.push(c);
+            }
+        }
+
+        let n = operands.len();
+        let seeds = operands
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                let seed = (0..n)
+                    .map(|j| if i == j { 1.0 } else { 0.0 }.into())
+                    .collect();
+                ((c.id(), 0), (c.clone(), seed))
+            })
+            .collect();
+        let ids = vec![""; n];
+
+        let result = Expression::with_differentials(seeds, || self._differential(&ids, 0));
+
+        operands.into_iter().zip(result).collect()
+    }
+}
+
+impl ExpressionRef {
+    /// Differentiates the node once per `differential` however many times it is shared.
+    pub(crate) fn _differential(
+        &self,
+        variable_ids: &[&str],
+        rank_offset: usize,
+    ) -> Vec<Expression> {
+        let key = (self.id(), rank_offset);
+        if let Some(result) = DIFFERENTIALS.with(|d| {
+            d.borrow()
+                .as_ref()
+                .and_then(|d| d.get(&key).map(|(_, result)| result.clone()))
+        }) {
+            return result;
+        }
+
+        let result = self.as_ref()._differential(variable_ids, rank_offset);
+        DIFFERENTIALS.with(|d| {
+            if let Some(d) = d.borrow_mut().as_mut() {
+                d.insert(key, (self.clone(), result.clone()));
+            }
+        });
+
+        result
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::*;
-    use std::iter::once;
+    use opensrdk_linear_algebra::{sparse::SparseTensor, Matrix};
+    use std::{collections::HashMap, iter::once};
 
     #[test]
     fn it_works() {
@@ -91,4 +201,76 @@ mod tests {
         println!("{:#?}", diff_sigma.tex_code(&tex_symbols));
         println!("{:#?}", diff_anpan.tex_code(&tex_symbols));
     }
+
+    #[test]
+    fn it_works6() {
+        let x = new_variable_tensor("x".to_string(), vec![Size::Many]);
+        let a = new_variable_tensor("a".to_string(), vec![Size::Many, Size::Many]);
+        let expression = x.clone().dot(a, &[[0, 0]]).dot(x, &[[1, 0]]);
+        let hessian = expression.hessian(&["x"]);
+
+        let mut hash = HashMap::new();
+        hash.insert("x", ConstantValue::Tensor(vec![1.0, 2.0].into()));
+        hash.insert(
+            "a",
+            ConstantValue::Matrix(Matrix::from(2, vec![1.0, 3.0, 2.0, 4.0]).unwrap()),
+        );
+
+        let result = hessian[0][0].evaluate(&hash).unwrap();
+
+        // A + A^T
+        let mut elems = HashMap::new();
+        elems.insert(vec![0, 0, 0], 2.0);
+        elems.insert(vec![0, 0, 1], 5.0);
+        elems.insert(vec![0, 1, 0], 5.0);
+        elems.insert(vec![0, 1, 1], 8.0);
+        assert_eq!(
+            result,
+            ConstantValue::Tensor(SparseTensor::from(vec![1, 2, 2], elems).unwrap())
+        );
+    }
+
+    #[test]
+    fn it_works7() {
+        let x = new_variable("x".to_string());
+        let y = new_variable("y".to_string());
+        let expression = x.clone().pow(2.0.into()) * y.clone();
+        let hessian = expression.hessian(&["x", "y"]);
+
+        let mut hash = HashMap::new();
+        hash.insert("x", ConstantValue::Scalar(3.0));
+        hash.insert("y", ConstantValue::Scalar(5.0));
+
+        let result = hessian
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|h| h.evaluate(&hash).unwrap().into_scalar())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(result, vec![vec![10.0, 6.0], vec![6.0, 0.0]]);
+    }
+
+    #[test]
+    fn it_works8() {
+        let x = new_variable("x".to_string());
+        let expression = x.clone().pow(3.0.into());
+
+        let hash = once(("x", ConstantValue::Scalar(2.0))).collect();
+
+        assert_eq!(
+            expression.nth_differential("x", 0).evaluate(&hash).unwrap(),
+            ConstantValue::Scalar(8.0)
+        );
+        assert_eq!(
+            expression.nth_differential("x", 2).evaluate(&hash).unwrap(),
+            ConstantValue::Scalar(12.0)
+        );
+        assert_eq!(
+            expression.nth_differential("x", 3).evaluate(&hash).unwrap(),
+            ConstantValue::Scalar(6.0)
+        );
+    }
 }
@@ -0,0 +1,61 @@
+use crate::{Expression, MatrixExpression, TensorExpression, TranscendentalExpression};
+
+/// The cost of the operation of a node, whose operands are given as placeholders.
+/// The cost of an expression is the sum over its nodes, which has to be positive for the nodes with operands.
+pub trait CostFunction {
+    fn cost(&self, node: &Expression) -> f64;
+
+    fn total(&self, e: &Expression) -> f64 {
+        self.cost(e) + e.children().iter().map(|c| self.total(c)).sum::<f64>()
+    }
+}
+
+/// The number of nodes.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct NodeCount;
+
+impl CostFunction for NodeCount {
+    fn cost(&self, _: &Expression) -> f64 {
+        1.0
+    }
+}
+
+/// A rough count of the floating point operations to evaluate each element.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct EvaluationCost;
+
+impl CostFunction for EvaluationCost {
+    fn cost(&self, node: &Expression) -> f64 {
+        match node {
+            Expression::Variable(_, _) | Expression::Constant(_) => 0.0,
+            Expression::PartialVariable(_) => 1.0,
+            Expression::Add(_, _) | Expression::Sub(_, _) | Expression::Neg(_) => 1.0,
+            Expression::Mul(_, _) => 1.0,
+            Expression::Div(_, _) => 4.0,
+            Expression::Transcendental(v) => match v.as_ref() {
+                TranscendentalExpression::Abs(_)
+                | TranscendentalExpression::Sign(_)
+                | TranscendentalExpression::Heaviside(_) => 1.0,
+                TranscendentalExpression::Pow(_, _) | TranscendentalExpression::Log(_, _) => 40.0,
+                TranscendentalExpression::Gamma(_)
+                | TranscendentalExpression::LnGamma(_)
+                | TranscendentalExpression::Digamma(_)
+                | TranscendentalExpression::Polygamma(_, _) => 100.0,
+                _ => 20.0,
+            },
+            Expression::Tensor(v) => match v.as_ref() {
+                TensorExpression::KroneckerDeltas(_)
+                | TensorExpression::SizedKroneckerDeltas(_) => 0.0,
+                TensorExpression::DotProduct { .. } | TensorExpression::DirectProduct(_) => 2.0,
+                TensorExpression::LogSumExp { .. } | TensorExpression::Softmax { .. } => 40.0,
+            },
+            Expression::Matrix(v) => match v.as_ref() {
+                MatrixExpression::T(_) | MatrixExpression::LowerHalf(_) => 1.0,
+                MatrixExpression::Inv(_) | MatrixExpression::Det(_) => 200.0,
+                // The Cholesky decomposition takes half the operations of the LU decomposition.
+                MatrixExpression::Cholesky(_) | MatrixExpression::LogDet(_) => 100.0,
+                MatrixExpression::Solve(_, _) => 150.0,
+            },
+        }
+    }
+}
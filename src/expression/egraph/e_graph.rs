@@ -0,0 +1,331 @@
+use super::cost::CostFunction;
+use crate::{
+    rewrite::pattern::wildcard_name, ConstantValue, Expression, ExpressionRef, Pattern, Rule,
+};
+use std::collections::{HashMap, HashSet};
+
+/// The node with its operands replaced by placeholders, which identifies its operation.
+fn template(e: &Expression) -> ExpressionRef {
+    let placeholders = vec![Expression::Variable("$".to_owned(), vec![]); e.children().len()];
+    e.with_children(placeholders).into()
+}
+
+#[derive(Clone, Debug)]
+struct ENode {
+    template: ExpressionRef,
+    children: Vec<usize>,
+}
+
+#[derive(Clone, Debug)]
+struct EClass {
+    nodes: Vec<ENode>,
+    /// One of the expressions of the class, which the constraints of the patterns are checked with.
+    representative: Expression,
+    constant: Option<ConstantValue>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    /// No rule adds anything new any more.
+    Saturated,
+    IterationLimit,
+    NodeLimit,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SaturationLimits {
+    pub max_iterations: usize,
+    pub max_nodes: usize,
+}
+
+impl Default for SaturationLimits {
+    fn default() -> Self {
+        Self {
+            max_iterations: 16,
+            max_nodes: 10000,
+        }
+    }
+}
+
+/// Classes of expressions known to be equal, sharing their operands.
+/// Every rewrite adds the rewritten form to the class instead of replacing it, so that none of the forms is lost to a greedy choice.
+#[derive(Clone, Debug, Default)]
+pub struct EGraph {
+    parents: Vec<usize>,
+    classes: Vec<EClass>,
+    memo: HashMap<(usize, Vec<usize>), usize>,
+}
+
+impl EGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The id of the class, which changes when it is merged into another.
+    pub fn find(&self, mut id: usize) -> usize {
+        while self.parents[id] != id {
+            id = self.parents[id];
+        }
+        id
+    }
+
+    pub fn class_count(&self) -> usize {
+        (0..self.classes.len())
+            .filter(|&id| self.find(id) == id)
+            .count()
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.canonical_classes()
+            .map(|id| self.classes[id].nodes.len())
+            .sum()
+    }
+
+    fn canonical_classes(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.classes.len()).filter(move |&id| self.find(id) == id)
+    }
+
+    /// Adds the expression and all its subexpressions, and returns the id of its class.
+    pub fn add(&mut self, e: &Expression) -> usize {
+        let children = e.children().iter().map(|c| self.add(c)).collect();
+        self.add_node(template(e), children)
+    }
+
+    fn add_node(&mut self, template: ExpressionRef, children: Vec<usize>) -> usize {
+        let children = children.iter().map(|&c| self.find(c)).collect::<Vec<_>>();
+        let key = (template.id(), children.clone());
+        if let Some(&id) = self.memo.get(&key) {
+            return self.find(id);
+        }
+
+        let id = self.classes.len();
+        let representative = template.with_children(
+            children
+                .iter()
+                .map(|&c| self.classes[c].representative.clone())
+                .collect(),
+        );
+        let constant = match template.as_ref() {
+            Expression::Constant(v) => Some(v.clone()),
+            _ => None,
+        };
+        self.parents.push(id);
+        self.classes.push(EClass {
+            nodes: vec![ENode {
+                template: template.clone(),
+                children: children.clone(),
+            }],
+            representative,
+            constant,
+        });
+        self.memo.insert(key, id);
+
+        // Folds the operations on constants with the operators.
+        let constants = children
+            .iter()
+            .map(|&c| self.classes[c].constant.clone().map(Expression::from))
+            .collect::<Option<Vec<_>>>();
+        if let Some(constants) = constants.filter(|c| !c.is_empty()) {
+            if let Ok(Expression::Constant(v)) = template.rebuilt(constants) {
+                let folded = self.add(&v.into());
+                self.union(id, folded);
+            }
+        }
+
+        self.find(id)
+    }
+
+    /// Merges the classes, and returns whether they were different.
+    /// `rebuild` has to follow to merge the classes which become equal in turn.
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+
+        self.parents[b] = a;
+        let merged = std::mem::take(&mut self.classes[b].nodes);
+        self.classes[a].nodes.extend(merged);
+        if self.classes[a].constant.is_none() {
+            self.classes[a].constant = self.classes[b].constant.take();
+        }
+
+        true
+    }
+
+    /// Merges the classes with nodes of the same operation on the same classes, until there are none.
+    pub fn rebuild(&mut self) {
+        loop {
+            let mut memo = HashMap::new();
+            let mut merges = vec![];
+            for id in self.canonical_classes().collect::<Vec<_>>() {
+                let mut nodes = std::mem::take(&mut self.classes[id].nodes);
+                for node in nodes.iter_mut() {
+                    node.children = node.children.iter().map(|&c| self.find(c)).collect();
+                }
+                let mut seen = HashSet::new();
+                nodes.retain(|n| seen.insert((n.template.id(), n.children.clone())));
+
+                for n in nodes.iter() {
+                    let key = (n.template.id(), n.children.clone());
+                    match memo.get(&key) {
+                        Some(&other) => merges.push((other, id)),
+                        None => {
+                            memo.insert(key, id);
+                        }
+                    }
+                }
+                self.classes[id].nodes = nodes;
+            }
+
+            self.memo = memo;
+            let mut merged = false;
+            for (a, b) in merges {
+                merged |= self.union(a, b);
+            }
+            if !merged {
+                return;
+            }
+        }
+    }
+
+    /// Whether the two expressions are in the same class.
+    pub fn is_equivalent(&mut self, a: &Expression, b: &Expression) -> bool {
+        let (a, b) = (self.add(a), self.add(b));
+        self.rebuild();
+        self.find(a) == self.find(b)
+    }
+
+    /// The bindings of the wildcards of `pattern` with which it matches a node of the class.
+    fn ematch(
+        &self,
+        pattern: &Pattern,
+        p: &Expression,
+        class: usize,
+        bindings: HashMap<String, usize>,
+    ) -> Vec<HashMap<String, usize>> {
+        let class = self.find(class);
+        if let Some(name) = wildcard_name(p) {
+            if let Some(&bound) = bindings.get(name) {
+                if self.find(bound) != class {
+                    return vec![];
+                }
+                return vec![bindings];
+            }
+            let e = match &self.classes[class].constant {
+                Some(v) => v.clone().into(),
+                None => self.classes[class].representative.clone(),
+            };
+            if !pattern.allows(name, &e) {
+                return vec![];
+            }
+            let mut bindings = bindings;
+            bindings.insert(name.to_owned(), class);
+            return vec![bindings];
+        }
+
+        let operation = template(p);
+        let pattern_children = p.children();
+        self.classes[class]
+            .nodes
+            .iter()
+            .filter(|n| n.template.id() == operation.id())
+            .flat_map(|n| {
+                pattern_children.iter().zip(n.children.iter()).fold(
+                    vec![bindings.clone()],
+                    |states, (pc, &c)| {
+                        states
+                            .into_iter()
+                            .flat_map(|b| self.ematch(pattern, pc, c, b))
+                            .collect()
+                    },
+                )
+            })
+            .collect()
+    }
+
+    fn add_instance(&mut self, e: &Expression, bindings: &HashMap<String, usize>) -> usize {
+        if let Some(name) = wildcard_name(e) {
+            return self.find(bindings[name]);
+        }
+        let children = e
+            .children()
+            .iter()
+            .map(|c| self.add_instance(c, bindings))
+            .collect();
+        self.add_node(template(e), children)
+    }
+
+    /// Applies all the rules to all the classes, adding what they rewrite into, until nothing new is added or a limit is reached.
+    pub fn saturate(&mut self, rules: &[Rule], limits: &SaturationLimits) -> StopReason {
+        self.rebuild();
+        for _ in 0..limits.max_iterations {
+            let counts = (self.node_count(), self.class_count());
+
+            let mut matches = vec![];
+            for (k, rule) in rules.iter().enumerate() {
+                for class in self.canonical_classes() {
+                    let pattern = rule.pattern();
+                    for bindings in
+                        self.ematch(pattern, pattern.expression(), class, HashMap::new())
+                    {
+                        matches.push((k, class, bindings));
+                    }
+                }
+            }
+
+            for (k, class, bindings) in matches {
+                let rewritten = self.add_instance(rules[k].replacement(), &bindings);
+                self.union(class, rewritten);
+                if self.classes.len() > limits.max_nodes {
+                    self.rebuild();
+                    return StopReason::NodeLimit;
+                }
+            }
+            self.rebuild();
+
+            if (self.node_count(), self.class_count()) == counts {
+                return StopReason::Saturated;
+            }
+        }
+
+        StopReason::IterationLimit
+    }
+
+    /// The cheapest expression of the class and its cost.
+    pub fn extract(&self, class: usize, cost: &impl CostFunction) -> (f64, Expression) {
+        let mut best = HashMap::<usize, (f64, usize)>::new();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for id in self.canonical_classes() {
+                for (k, node) in self.classes[id].nodes.iter().enumerate() {
+                    let children = node
+                        .children
+                        .iter()
+                        .map(|&c| best.get(&self.find(c)).map(|b| b.0))
+                        .sum::<Option<f64>>();
+                    let total = match children {
+                        Some(children) => cost.cost(&node.template) + children,
+                        None => continue,
+                    };
+                    if best.get(&id).map_or(true, |b| total < b.0) {
+                        best.insert(id, (total, k));
+                        changed = true;
+                    }
+                }
+            }
+        }
+
+        let class = self.find(class);
+        (best[&class].0, self.build(class, &best))
+    }
+
+    fn build(&self, class: usize, best: &HashMap<usize, (f64, usize)>) -> Expression {
+        let class = self.find(class);
+        let node = &self.classes[class].nodes[best[&class].1];
+        let children = node.children.iter().map(|&c| self.build(c, best)).collect();
+
+        node.template.with_children(children)
+    }
+}
@@ -0,0 +1,8 @@
+pub mod cost;
+pub mod e_graph;
+pub mod optimize;
+pub mod rules;
+
+pub use cost::*;
+pub use e_graph::*;
+pub use rules::*;
@@ -0,0 +1,162 @@
+use super::{algebraic_rules, CostFunction, EGraph, SaturationLimits};
+use crate::{Expression, Rule};
+
+impl Expression {
+    /// The cheapest equivalent of the expression found with the algebraic rules.
+    pub fn optimize(&self, cost: &impl CostFunction) -> Expression {
+        self.optimize_with(&algebraic_rules(), cost, &SaturationLimits::default())
+    }
+
+    /// Saturates an e-graph of the expression with `rules`, and extracts the cheapest expression equal to it.
+    pub fn optimize_with(
+        &self,
+        rules: &[Rule],
+        cost: &impl CostFunction,
+        limits: &SaturationLimits,
+    ) -> Expression {
+        let mut graph = EGraph::new();
+        let root = graph.add(self);
+        graph.saturate(rules, limits);
+
+        let (optimized_cost, optimized) = graph.extract(root, cost);
+        // Keeps the expression itself when the extracted one is no cheaper.
+        if optimized_cost < cost.total(self) {
+            optimized
+        } else {
+            self.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{
+        algebraic_rules, log_det_rules, new_variable, new_variable_tensor, positive_rules,
+        ConstantValue, CostFunction, EGraph, EvaluationCost, Expression, MatrixExpression,
+        NodeCount, SaturationLimits, Size, StopReason, TranscendentalExpression,
+    };
+    use opensrdk_linear_algebra::Matrix;
+
+    #[test]
+    fn it_works() {
+        let a = new_variable("a".to_string());
+        let b = new_variable("b".to_string());
+
+        let product = a.clone().exp() * b.clone().exp();
+        let sum = (a.clone() + b.clone()).exp();
+        assert_ne!(product, sum);
+
+        let mut graph = EGraph::new();
+        graph.add(&product);
+        graph.add(&sum);
+        let reason = graph.saturate(&algebraic_rules(), &SaturationLimits::default());
+        assert_eq!(reason, StopReason::Saturated);
+        assert!(graph.is_equivalent(&product, &sum));
+        assert!(!graph.is_equivalent(&product, &a.clone().exp()));
+
+        let optimized = product.optimize(&NodeCount);
+        assert_eq!(NodeCount.total(&optimized), 4.0);
+        assert!(matches!(
+            &optimized,
+            Expression::Transcendental(v) if matches!(v.as_ref(), TranscendentalExpression::Exp(_))
+        ));
+    }
+
+    #[test]
+    fn it_works2() {
+        let x = new_variable("x".to_string());
+        let y = new_variable("y".to_string());
+
+        let expression = (x.clone() * y.clone()).exp() * x.clone().exp() / (-y.clone()).exp()
+            + x.clone() * y.clone() * 3.0
+            + y.clone() * x.clone() * 2.0;
+        let derivative = expression.differential(&["x"])[0].clone();
+
+        let optimized = derivative.optimize(&EvaluationCost);
+        assert!(EvaluationCost.total(&optimized) < EvaluationCost.total(&derivative));
+
+        for (vx, vy) in [(0.3, -0.2), (1.1, 0.7), (-0.5, 2.0)] {
+            let mut hash = HashMap::new();
+            hash.insert("x", ConstantValue::Scalar(vx));
+            hash.insert("y", ConstantValue::Scalar(vy));
+            let expected = derivative.evaluate(&hash).unwrap().into_scalar();
+            let actual = optimized.evaluate(&hash).unwrap().into_scalar();
+            assert!((expected - actual).abs() < 1e-10 * expected.abs().max(1.0));
+        }
+    }
+
+    #[test]
+    fn it_works3() {
+        let a = new_variable_tensor("a".to_string(), vec![Size::Many, Size::Many]);
+        let b = new_variable_tensor("b".to_string(), vec![Size::Many, Size::Many]);
+
+        // The rules apply inside matrix expressions, and the limits stop rules which keep producing new forms.
+        let expression = a.clone().t().t().det() * b.clone().det() * a.clone().det();
+        let optimized = expression.optimize(&EvaluationCost);
+        // det(b) det(a)^2, with one determinant of each matrix.
+        assert_eq!(EvaluationCost.total(&optimized), 2.0 * 200.0 + 40.0 + 1.0);
+
+        let limits = SaturationLimits {
+            max_iterations: 100,
+            max_nodes: 50,
+        };
+        let mut graph = EGraph::new();
+        graph.add(&(a.clone() + b.clone() + a.clone() * b.clone() + b.clone().t()));
+        assert_eq!(
+            graph.saturate(&algebraic_rules(), &limits),
+            StopReason::NodeLimit
+        );
+    }
+
+    #[test]
+    fn it_works4() {
+        let x = new_variable("x".to_string());
+        let y = new_variable("y".to_string());
+
+        // ln(xy) is defined for negative x and y, where ln x + ln y is not, so they are only equal for positive ones.
+        let sum = x.clone().ln() + y.clone().ln();
+        let product = (x.clone() * y.clone()).ln();
+
+        let mut graph = EGraph::new();
+        graph.add(&sum);
+        graph.add(&product);
+        graph.saturate(&algebraic_rules(), &SaturationLimits::default());
+        assert!(!graph.is_equivalent(&sum, &product));
+
+        let rules = algebraic_rules()
+            .into_iter()
+            .chain(positive_rules())
+            .collect::<Vec<_>>();
+        graph.saturate(&rules, &SaturationLimits::default());
+        assert!(graph.is_equivalent(&sum, &product));
+    }
+
+    #[test]
+    fn it_works5() {
+        let a = new_variable_tensor("a".to_string(), vec![Size::Many, Size::Many]);
+        let mut hash = HashMap::new();
+        hash.insert(
+            "a",
+            ConstantValue::Matrix(Matrix::from(2, vec![1.0, 2.0, 0.0, 5.0]).unwrap()),
+        );
+
+        // The matrix is not symmetric, so its log determinant differs from the logarithm of its determinant.
+        let expression = a.clone().det().ln();
+        let optimized = expression.optimize(&EvaluationCost);
+        let value = optimized.evaluate(&hash).unwrap().into_scalar();
+        assert!((value - 5.0f64.ln()).abs() < 1e-12);
+
+        let rules = algebraic_rules()
+            .into_iter()
+            .chain(log_det_rules())
+            .collect::<Vec<_>>();
+        let optimized =
+            expression.optimize_with(&rules, &EvaluationCost, &SaturationLimits::default());
+        assert!(matches!(
+            &optimized,
+            Expression::Matrix(v) if matches!(v.as_ref(), MatrixExpression::LogDet(_))
+        ));
+    }
+}
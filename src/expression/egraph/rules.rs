@@ -0,0 +1,141 @@
+use crate::{
+    new_wildcard, Constraint, Expression, MatrixExpression, Pattern, Rule, TranscendentalExpression,
+};
+
+// The patterns are built without the operators, which would rewrite them.
+fn add(l: &Expression, r: &Expression) -> Expression {
+    Expression::Add(l.clone().into(), r.clone().into())
+}
+
+fn sub(l: &Expression, r: &Expression) -> Expression {
+    Expression::Sub(l.clone().into(), r.clone().into())
+}
+
+fn mul(l: &Expression, r: &Expression) -> Expression {
+    Expression::Mul(l.clone().into(), r.clone().into())
+}
+
+fn div(l: &Expression, r: &Expression) -> Expression {
+    Expression::Div(l.clone().into(), r.clone().into())
+}
+
+fn neg(v: &Expression) -> Expression {
+    Expression::Neg(v.clone().into())
+}
+
+fn exp(v: &Expression) -> Expression {
+    TranscendentalExpression::Exp(v.clone().into()).into()
+}
+
+fn ln(v: &Expression) -> Expression {
+    TranscendentalExpression::Ln(v.clone().into()).into()
+}
+
+fn pow(base: &Expression, exponent: &Expression) -> Expression {
+    TranscendentalExpression::Pow(base.clone().into(), exponent.clone().into()).into()
+}
+
+fn det(v: &Expression) -> Expression {
+    MatrixExpression::Det(v.clone().into()).into()
+}
+
+fn rule(name: &str, pattern: impl Into<Pattern>, replacement: Expression) -> Rule {
+    Rule::new(name.to_owned(), pattern.into(), replacement)
+}
+
+/// Rewrites the logarithms of determinants into log determinants, which are computed by the Cholesky decomposition.
+/// The matrices are assumed to be positive definite, such as the covariance matrices of the Gaussian likelihoods.
+/// They are not among `algebraic_rules`, since the log determinant of another matrix is not the logarithm of its determinant.
+pub fn log_det_rules() -> Vec<Rule> {
+    let a = new_wildcard("a".to_owned());
+
+    vec![rule(
+        "ln-det",
+        ln(&det(&a)),
+        MatrixExpression::LogDet(a.clone().into()).into(),
+    )]
+}
+
+/// Identities of the logarithms and the powers which only hold for positive arguments and bases, such as the variances and the densities.
+/// They are not among `algebraic_rules`, so they are chained to them where the positivity is known.
+pub fn positive_rules() -> Vec<Rule> {
+    let a = new_wildcard("a".to_owned());
+    let b = new_wildcard("b".to_owned());
+    let c = new_wildcard("c".to_owned());
+
+    vec![
+        rule("ln-mul", add(&ln(&a), &ln(&b)), ln(&mul(&a, &b))),
+        rule("ln-pow", ln(&pow(&a, &b)), mul(&b, &ln(&a))),
+        rule(
+            "pow-add",
+            mul(&pow(&a, &b), &pow(&a, &c)),
+            pow(&a, &add(&b, &c)),
+        ),
+    ]
+}
+
+/// Identities of the elementwise operations and the matrix operations, in both directions where both forms can be cheaper.
+/// The ones replacing an expression by a scalar constant only apply to scalars, which keeps the sizes.
+pub fn algebraic_rules() -> Vec<Rule> {
+    let a = new_wildcard("a".to_owned());
+    let b = new_wildcard("b".to_owned());
+    let c = new_wildcard("c".to_owned());
+    let zero = Expression::from(0.0);
+    let one = Expression::from(1.0);
+    let scalar = |p: Expression| Pattern::new(p).constrain("a", Constraint::Sizes(vec![]));
+
+    vec![
+        rule("add-commute", add(&a, &b), add(&b, &a)),
+        rule("mul-commute", mul(&a, &b), mul(&b, &a)),
+        rule("add-assoc", add(&add(&a, &b), &c), add(&a, &add(&b, &c))),
+        rule("mul-assoc", mul(&mul(&a, &b), &c), mul(&a, &mul(&b, &c))),
+        rule(
+            "distribute",
+            mul(&a, &add(&b, &c)),
+            add(&mul(&a, &b), &mul(&a, &c)),
+        ),
+        rule(
+            "factor",
+            add(&mul(&a, &b), &mul(&a, &c)),
+            mul(&a, &add(&b, &c)),
+        ),
+        rule("sub-neg", sub(&a, &b), add(&a, &neg(&b))),
+        rule("neg-sub", add(&a, &neg(&b)), sub(&a, &b)),
+        rule("neg-mul", neg(&mul(&a, &b)), mul(&neg(&a), &b)),
+        rule("mul-neg", mul(&neg(&a), &b), neg(&mul(&a, &b))),
+        rule("neg-neg", neg(&neg(&a)), a.clone()),
+        rule("add-zero", add(&a, &zero), a.clone()),
+        rule("mul-one", mul(&a, &one), a.clone()),
+        rule("div-one", div(&a, &one), a.clone()),
+        rule("mul-zero", scalar(mul(&a, &zero)), zero.clone()),
+        rule("sub-self", scalar(sub(&a, &a)), zero.clone()),
+        rule("div-mul", div(&a, &b), mul(&a, &pow(&b, &(-1.0).into()))),
+        rule("mul-div", mul(&a, &pow(&b, &(-1.0).into())), div(&a, &b)),
+        rule("div-div", div(&div(&a, &b), &c), div(&a, &mul(&b, &c))),
+        rule("exp-add", mul(&exp(&a), &exp(&b)), exp(&add(&a, &b))),
+        rule("exp-split", exp(&add(&a, &b)), mul(&exp(&a), &exp(&b))),
+        rule("exp-sub", div(&exp(&a), &exp(&b)), exp(&sub(&a, &b))),
+        rule("ln-exp", ln(&exp(&a)), a.clone()),
+        rule("pow-square", mul(&a, &a), pow(&a, &2.0.into())),
+        rule("pow-one", pow(&a, &one), a.clone()),
+        rule(
+            "t-t",
+            Expression::from(MatrixExpression::T(
+                Expression::from(MatrixExpression::T(a.clone().into())).into(),
+            )),
+            a.clone(),
+        ),
+        rule(
+            "inv-inv",
+            Expression::from(MatrixExpression::Inv(
+                Expression::from(MatrixExpression::Inv(a.clone().into())).into(),
+            )),
+            a.clone(),
+        ),
+        rule(
+            "det-t",
+            det(&Expression::from(MatrixExpression::T(a.clone().into()))),
+            det(&a),
+        ),
+    ]
+}
@@ -0,0 +1,202 @@
+use crate::{
+    expression::children::is_kronecker_deltas, tensor_expression::operations::dot::DotProduct,
+    ConstantValue, Expression, SymbolicError,
+};
+use rayon::prelude::*;
+use std::collections::HashMap;
+
+impl Expression {
+    pub fn evaluate(
+        &self,
+        variables: &HashMap<&str, ConstantValue>,
+    ) -> Result<ConstantValue, SymbolicError> {
+        self.bind_dims(variables)?;
+        self._evaluate(variables)
+    }
+
+    /// Evaluates the expression for each of the assignments in parallel.
+    pub fn evaluate_batch(
+        &self,
+        batch: &[HashMap<&str, ConstantValue>],
+    ) -> Result<Vec<ConstantValue>, SymbolicError> {
+        batch
+            .par_iter()
+            .map(|variables| self.evaluate(variables))
+            .collect()
+    }
+
+    pub(crate) fn _evaluate(
+        &self,
+        variables: &HashMap<&str, ConstantValue>,
+    ) -> Result<ConstantValue, SymbolicError> {
+        match self {
+            Expression::Variable(id, _) => Expression::evaluate_variable(id, variables),
+            Expression::Constant(v) => Ok(v.clone()),
+            Expression::PartialVariable(v) => Expression::evaluate_partial_variable(v, variables),
+            Expression::Add(l, r) => l._evaluate(variables)?.try_add(r._evaluate(variables)?),
+            Expression::Sub(l, r) => l._evaluate(variables)?.try_sub(r._evaluate(variables)?),
+            Expression::Mul(l, r) if is_kronecker_deltas(l) || is_kronecker_deltas(r) => {
+                Expression::evaluate_deltas(l.as_ref().clone(), r.as_ref().clone(), variables)
+            }
+            Expression::Div(l, r) if is_kronecker_deltas(l) => {
+                Expression::evaluate_deltas(l.as_ref().clone(), 1.0 / r.as_ref().clone(), variables)
+            }
+            Expression::Neg(v) if is_kronecker_deltas(v) => {
+                Expression::evaluate_deltas(v.as_ref().clone(), (-1.0).into(), variables)
+            }
+            Expression::Mul(l, r) => l._evaluate(variables)?.try_mul(r._evaluate(variables)?),
+            Expression::Div(l, r) => l._evaluate(variables)?.try_div(&r._evaluate(variables)?),
+            Expression::Neg(v) => Ok(v._evaluate(variables)?.map(|v| -v)),
+            Expression::Transcendental(v) => v._evaluate(variables),
+            Expression::Tensor(v) => v._evaluate(variables),
+            Expression::Matrix(v) => v._evaluate(variables),
+        }
+    }
+}
+
+impl Expression {
+    /// Kronecker deltas have no value of their own, so their products with the other factors, which `differential` leaves for the derivatives of tensor variables, are evaluated as elementwise dot products.
+    fn evaluate_deltas(
+        l: Expression,
+        r: Expression,
+        variables: &HashMap<&str, ConstantValue>,
+    ) -> Result<ConstantValue, SymbolicError> {
+        vec![l, r]
+            .into_iter()
+            .try_dot_product(&[HashMap::new(), HashMap::new()])?
+            ._evaluate(variables)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use opensrdk_linear_algebra::{sparse::SparseTensor, Matrix};
+
+    use crate::{
+        new_partial_variable, new_variable, new_variable_tensor, ConstantValue, Expression,
+        ExpressionArray, Size,
+    };
+
+    #[test]
+    fn it_works1() {
+        let x = new_variable("x".to_string());
+        let mu = new_variable("mu".to_string());
+        let sigma = new_variable("sigma".to_string());
+        let expression = -(x - mu).pow(2.0.into()) / (2.0 * sigma.pow(2.0.into()));
+
+        let mut hash = HashMap::new();
+        hash.insert("x", ConstantValue::Scalar(3.0));
+        hash.insert("mu", ConstantValue::Scalar(1.0));
+        hash.insert("sigma", ConstantValue::Scalar(2.0));
+
+        let result = expression.evaluate(&hash).unwrap();
+
+        assert_eq!(result, ConstantValue::Scalar(-0.5));
+    }
+
+    #[test]
+    fn it_works2() {
+        let x = new_variable("x".to_string());
+        let expression = x.clone() * 2.0;
+
+        let result = expression.evaluate(&HashMap::new());
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn it_works3() {
+        let x = new_variable_tensor("x".to_string(), vec![Size::Many]);
+        let a = new_variable_tensor("a".to_string(), vec![Size::Many, Size::Many]);
+        let expression = x.clone().dot(a, &[[0, 0]]).dot(x, &[[1, 0]]);
+
+        let mut hash = HashMap::new();
+        hash.insert("x", ConstantValue::Tensor(vec![1.0, 2.0].into()));
+        hash.insert(
+            "a",
+            ConstantValue::Matrix(Matrix::from(2, vec![1.0, 3.0, 2.0, 4.0]).unwrap()),
+        );
+
+        let result = expression.evaluate(&hash).unwrap();
+
+        // [1 2] [[1 2] [3 4]] [1 2]^T = 27
+        assert_eq!(result.elems(), vec![27.0]);
+    }
+
+    #[test]
+    fn it_works4() {
+        let x = new_variable("x".to_string());
+        let array = ExpressionArray::from_factory(vec![2, 2], |indices| {
+            (indices[0] + 2 * indices[1]) as f64 * x.clone()
+        });
+        let expression = new_partial_variable(array);
+
+        let mut hash = HashMap::new();
+        hash.insert("x", ConstantValue::Scalar(2.0));
+
+        let result = expression.evaluate(&hash).unwrap();
+
+        let mut elems = HashMap::new();
+        elems.insert(vec![1, 0], 2.0);
+        elems.insert(vec![0, 1], 4.0);
+        elems.insert(vec![1, 1], 6.0);
+        assert_eq!(
+            result,
+            ConstantValue::Tensor(SparseTensor::from(vec![2, 2], elems).unwrap())
+        );
+    }
+
+    #[test]
+    fn it_works5() {
+        let n = 40;
+        let theta = new_variable("theta".to_string());
+        let points = (0..n).map(|i| i as f64 / n as f64).collect::<Vec<_>>();
+        let gram = new_partial_variable(ExpressionArray::from_factory(vec![n, n], |indices| {
+            let d = points[indices[0]] - points[indices[1]];
+            (-theta.clone() * (d * d)).exp()
+        }));
+
+        let batch = [0.5, 1.0, 2.0]
+            .iter()
+            .map(|&t| {
+                let mut hash = HashMap::new();
+                hash.insert("theta", ConstantValue::Scalar(t));
+                hash
+            })
+            .collect::<Vec<_>>();
+
+        let results = gram.evaluate_batch(&batch).unwrap();
+
+        for (result, t) in results.iter().zip([0.5, 1.0, 2.0]) {
+            let tensor = result.clone().into_tensor();
+            let d: f64 = points[3] - points[7];
+            assert!((tensor[&[3, 7]] - (-t * d * d).exp()).abs() < 1e-12);
+            assert_eq!(tensor[&[5, 5]], 1.0);
+        }
+    }
+
+    #[test]
+    fn it_works6() {
+        let b = new_variable_tensor("b".to_string(), vec![Size::Many, Size::Many]);
+        let sigma = new_variable("sigma".to_string());
+        let identity = ConstantValue::Matrix(Matrix::from(2, vec![1.0, 0.0, 0.0, 1.0]).unwrap());
+        // K + σ^2 I, whose product evaluates to a tensor and whose identity is a matrix.
+        let expression =
+            b.clone().dot(b.t(), &[[1, 0]]) + sigma.pow(2.0.into()) * Expression::from(identity);
+
+        let mut hash = HashMap::new();
+        hash.insert(
+            "b",
+            ConstantValue::Matrix(Matrix::from(2, vec![1.0, 3.0, 2.0, 4.0]).unwrap()),
+        );
+        hash.insert("sigma", ConstantValue::Scalar(0.5));
+
+        let result = expression.evaluate(&hash).unwrap().to_matrix().unwrap();
+
+        // [[1 2] [3 4]] [[1 3] [2 4]] + 0.25 I
+        let expected = Matrix::from(2, vec![5.25, 11.0, 11.0, 25.25]).unwrap();
+        assert_eq!(result, expected);
+    }
+}
@@ -0,0 +1,309 @@
+use crate::{Expression, ExpressionRef, Interval, SymbolicError, TranscendentalExpression};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum IntervalIssueKind {
+    /// Part of the ranges of the operands is outside the domain of the node.
+    OutOfDomain,
+    /// The range of the divisor contains zero.
+    DivisionByZero,
+    /// The range of the node is unbounded though those of its operands are bounded, or the constant has elements which are not finite.
+    Unbounded,
+}
+
+/// A node whose range is invalid or unbounded, where the issue first appears.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IntervalIssue {
+    pub expression: Expression,
+    pub kind: IntervalIssueKind,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct IntervalEvaluation {
+    /// Contains every value the expression takes over the ranges of its variables.
+    /// It only encloses the values where the expression is defined when there are `OutOfDomain` or `DivisionByZero` issues.
+    pub interval: Interval,
+    pub issues: Vec<IntervalIssue>,
+}
+
+fn not_scalar(found: &'static str) -> SymbolicError {
+    SymbolicError::TypeMismatch {
+        expected: "scalar",
+        found,
+    }
+}
+
+/// The issue of `e` given the ranges of its operands, other than unboundedness.
+fn domain_issue(e: &TranscendentalExpression, args: &[Interval]) -> Option<IntervalIssueKind> {
+    let outside = |domain: Interval| !args[0].is_empty() && args[0].intersect(&domain) != args[0];
+    let violated = match e {
+        TranscendentalExpression::Pow(_, _) => {
+            let (base, exponent) = (args[0], args[1]);
+            let integer = exponent.is_point() && exponent.lo.fract() == 0.0;
+            if base.contains(0.0) && exponent.lo < 0.0 {
+                return Some(IntervalIssueKind::DivisionByZero);
+            }
+            !integer && base.lo < 0.0
+        }
+        TranscendentalExpression::Log(_, _) => {
+            if args[0].contains(1.0) {
+                return Some(IntervalIssueKind::DivisionByZero);
+            }
+            args[0].lo <= 0.0 || args[1].lo <= 0.0
+        }
+        TranscendentalExpression::Ln(_) => args[0].lo <= 0.0,
+        TranscendentalExpression::Log1p(_) => args[0].lo <= -1.0,
+        TranscendentalExpression::Asin(_) | TranscendentalExpression::Acos(_) => {
+            outside(Interval::new(-1.0, 1.0))
+        }
+        TranscendentalExpression::Tan(_) => args[0].contains_tan_pole(),
+        TranscendentalExpression::Gamma(_)
+        | TranscendentalExpression::LnGamma(_)
+        | TranscendentalExpression::Digamma(_)
+        | TranscendentalExpression::Polygamma(_, _) => args[0].contains_non_positive_integer(),
+        _ => false,
+    };
+
+    if violated {
+        Some(IntervalIssueKind::OutOfDomain)
+    } else {
+        None
+    }
+}
+
+fn transcendental(e: &TranscendentalExpression, args: &[Interval]) -> Interval {
+    let x = args[0];
+    match e {
+        TranscendentalExpression::Abs(_) => x.abs(),
+        TranscendentalExpression::Pow(_, _) => x.pow(&args[1]),
+        TranscendentalExpression::Exp(_) => x.exp(),
+        TranscendentalExpression::Log(_, _) => x.log(&args[1]),
+        TranscendentalExpression::Ln(_) => x.ln(),
+        TranscendentalExpression::Sin(_) => x.sin(),
+        TranscendentalExpression::Cos(_) => x.cos(),
+        TranscendentalExpression::Tan(_) => x.tan(),
+        TranscendentalExpression::Sign(_) => x.sign(),
+        TranscendentalExpression::Heaviside(_) => x.heaviside(),
+        TranscendentalExpression::Sinh(_) => x.sinh(),
+        TranscendentalExpression::Cosh(_) => x.cosh(),
+        TranscendentalExpression::Tanh(_) => x.tanh(),
+        TranscendentalExpression::Asin(_) => x.asin(),
+        TranscendentalExpression::Acos(_) => x.acos(),
+        TranscendentalExpression::Atan(_) => x.atan(),
+        TranscendentalExpression::Atan2(_, _) => x.atan2(&args[1]),
+        TranscendentalExpression::Erf(_) => x.erf(),
+        TranscendentalExpression::Erfc(_) => x.erfc(),
+        TranscendentalExpression::Gamma(_) => x.gamma(),
+        TranscendentalExpression::LnGamma(_) => x.ln_gamma(),
+        TranscendentalExpression::Digamma(_) => x.polygamma(0),
+        TranscendentalExpression::Sigmoid(_) => x.sigmoid(),
+        TranscendentalExpression::Softplus(_) => x.softplus(),
+        TranscendentalExpression::Log1p(_) => x.log1p(),
+        TranscendentalExpression::Expm1(_) => x.expm1(),
+        TranscendentalExpression::Polygamma(n, _) => x.polygamma(*n),
+    }
+}
+
+struct IntervalPass<'a> {
+    ranges: &'a HashMap<&'a str, Interval>,
+    intervals: HashMap<usize, Interval>,
+    nodes: Vec<ExpressionRef>,
+    issues: Vec<IntervalIssue>,
+}
+
+impl<'a> IntervalPass<'a> {
+    fn visit(&mut self, e: &Expression) -> Result<Interval, SymbolicError> {
+        let node = ExpressionRef::from(e.clone());
+        if let Some(interval) = self.intervals.get(&node.id()) {
+            return Ok(*interval);
+        }
+
+        let args = e
+            .children()
+            .iter()
+            .map(|c| self.visit(c))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut issue = None;
+        let interval = match e {
+            Expression::Variable(id, _) => *self
+                .ranges
+                .get(id.as_str())
+                .ok_or_else(|| SymbolicError::UnassignedVariable(id.to_owned()))?,
+            Expression::Constant(v) => {
+                // Each element is enclosed by the range of all of them, including the zeros a sparse tensor does not store.
+                let elems = v.elems();
+                let implicit_zero = elems.len() < v.sizes().iter().product::<usize>();
+                if elems.iter().any(|v| !v.is_finite()) {
+                    issue = Some(IntervalIssueKind::Unbounded);
+                }
+                elems.iter().chain(implicit_zero.then_some(&0.0)).fold(
+                    Interval::empty(),
+                    |i, &v| match v.is_nan() {
+                        true => Interval::entire(),
+                        false => i.hull(&Interval::point(v)),
+                    },
+                )
+            }
+            Expression::Add(_, _) => args[0] + args[1],
+            Expression::Sub(_, _) => args[0] - args[1],
+            Expression::Mul(_, _) => args[0] * args[1],
+            Expression::Div(_, _) => {
+                if args[1].contains(0.0) {
+                    issue = Some(IntervalIssueKind::DivisionByZero);
+                }
+                args[0] / args[1]
+            }
+            Expression::Neg(_) => -args[0],
+            Expression::Transcendental(v) => {
+                issue = domain_issue(v, &args);
+                transcendental(v, &args)
+            }
+            Expression::PartialVariable(_) => return Err(not_scalar("partial variable")),
+            Expression::Tensor(_) => return Err(not_scalar("tensor")),
+            Expression::Matrix(_) => return Err(not_scalar("matrix")),
+        };
+
+        let bounded_args = args.iter().all(|a| a.is_bounded());
+        if issue.is_none() && !args.is_empty() && bounded_args && !interval.is_bounded() {
+            issue = Some(IntervalIssueKind::Unbounded);
+        }
+        if let Some(kind) = issue {
+            self.issues.push(IntervalIssue {
+                expression: e.clone(),
+                kind,
+            });
+        }
+
+        self.intervals.insert(node.id(), interval);
+        self.nodes.push(node);
+
+        Ok(interval)
+    }
+}
+
+impl Expression {
+    /// Encloses the values of the expression over `ranges` of its variables, and reports the nodes where the enclosure becomes invalid or unbounded.
+    /// The range of a tensor variable or a non-scalar constant bounds each of its elements, so only elementwise operations are supported.
+    pub fn evaluate_interval(
+        &self,
+        ranges: &HashMap<&str, Interval>,
+    ) -> Result<IntervalEvaluation, SymbolicError> {
+        let mut pass = IntervalPass {
+            ranges,
+            intervals: HashMap::new(),
+            nodes: vec![],
+            issues: vec![],
+        };
+        let interval = pass.visit(self)?;
+
+        Ok(IntervalEvaluation {
+            interval,
+            issues: pass.issues,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use opensrdk_linear_algebra::sparse::SparseTensor;
+
+    use crate::{new_variable, ConstantValue, Expression, Interval, IntervalIssueKind};
+
+    #[test]
+    fn it_works() {
+        let x = new_variable("x".to_string());
+        let y = new_variable("y".to_string());
+        let expression = (x.clone() * y.clone()).sin().exp() / (1.0 + x.clone().pow(2.0.into()))
+            + y.clone().atan2(x.clone()).sigmoid()
+            + (x.clone() + 3.0).ln_gamma()
+            - x.clone().pow(y.clone());
+
+        let mut ranges = HashMap::new();
+        ranges.insert("x", Interval::new(0.5, 2.0));
+        ranges.insert("y", Interval::new(-1.0, 1.5));
+
+        let evaluation = expression.evaluate_interval(&ranges).unwrap();
+        assert!(evaluation.issues.is_empty());
+        assert!(evaluation.interval.is_bounded());
+
+        for i in 0..=20 {
+            for j in 0..=20 {
+                let mut hash = HashMap::new();
+                hash.insert("x", ConstantValue::Scalar(0.5 + 1.5 * i as f64 / 20.0));
+                hash.insert("y", ConstantValue::Scalar(-1.0 + 2.5 * j as f64 / 20.0));
+                let v = expression.evaluate(&hash).unwrap().into_scalar();
+                assert!(evaluation.interval.contains(v), "{}", v);
+            }
+        }
+    }
+
+    #[test]
+    fn it_works2() {
+        let x = new_variable("x".to_string());
+        let ln = x.clone().ln();
+        let quotient = 1.0 / (x.clone() - 1.0);
+        let expression = ln.clone() + quotient.clone();
+
+        let mut ranges = HashMap::new();
+        ranges.insert("x", Interval::new(-1.0, 2.0));
+
+        let evaluation = expression.evaluate_interval(&ranges).unwrap();
+        assert_eq!(evaluation.interval, Interval::entire());
+        assert_eq!(evaluation.issues.len(), 2);
+        assert_eq!(evaluation.issues[0].expression, ln);
+        assert_eq!(evaluation.issues[0].kind, IntervalIssueKind::OutOfDomain);
+        assert_eq!(evaluation.issues[1].expression, quotient);
+        assert_eq!(evaluation.issues[1].kind, IntervalIssueKind::DivisionByZero);
+
+        // Overflow is found at the node where it first happens.
+        let exp = x.clone().exp();
+        let expression = exp.clone() * 2.0;
+        ranges.insert("x", Interval::new(0.0, 800.0));
+        let evaluation = expression.evaluate_interval(&ranges).unwrap();
+        assert_eq!(evaluation.issues.len(), 1);
+        assert_eq!(evaluation.issues[0].expression, exp);
+        assert_eq!(evaluation.issues[0].kind, IntervalIssueKind::Unbounded);
+
+        let expression: Expression = x.clone().sin() * Expression::from(vec![1.0, -2.0]);
+        let evaluation = expression.evaluate_interval(&ranges).unwrap();
+        assert!(evaluation.interval.contains(-2.0) && evaluation.interval.lo > -2.0 - 1e-12);
+        assert!(ranges.remove("x").is_some());
+        assert!(expression.evaluate_interval(&ranges).is_err());
+    }
+
+    #[test]
+    fn it_works3() {
+        let x = new_variable("x".to_string());
+        let mut ranges = HashMap::new();
+        ranges.insert("x", Interval::new(1.0, 2.0));
+
+        // The elements the sparse tensor does not store are zeros.
+        let mut elems = HashMap::new();
+        elems.insert(vec![0], 3.0);
+        elems.insert(vec![2], 5.0);
+        let sparse = Expression::from(ConstantValue::Tensor(
+            SparseTensor::from(vec![3], elems).unwrap(),
+        ));
+        let evaluation = (x.clone() * sparse).evaluate_interval(&ranges).unwrap();
+        assert!(evaluation.interval.contains(0.0) && evaluation.interval.contains(10.0));
+        assert!(evaluation.interval.lo > -1e-12 && evaluation.interval.hi < 10.0 + 1e-12);
+        assert!(evaluation.issues.is_empty());
+
+        let infinite = Expression::from(vec![1.0, f64::INFINITY]);
+        let evaluation = (x.clone() + infinite.clone())
+            .evaluate_interval(&ranges)
+            .unwrap();
+        assert!(!evaluation.interval.is_bounded());
+        assert_eq!(evaluation.issues.len(), 1);
+        assert_eq!(evaluation.issues[0].expression, infinite);
+        assert_eq!(evaluation.issues[0].kind, IntervalIssueKind::Unbounded);
+
+        let evaluation = (x * f64::NAN).evaluate_interval(&ranges).unwrap();
+        assert_eq!(evaluation.interval, Interval::entire());
+        assert_eq!(evaluation.issues[0].kind, IntervalIssueKind::Unbounded);
+    }
+}
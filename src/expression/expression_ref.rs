@@ -0,0 +1,234 @@
+use crate::{Expression, Size};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, HashSet},
+    fmt,
+    hash::{Hash, Hasher},
+    ops::Deref,
+    sync::{Arc, Mutex, OnceLock, Weak},
+};
+
+/// A shared child of an expression.
+/// Structurally identical expressions are interned into the same node, so comparing and hashing only look at the address.
+#[derive(Clone)]
+pub struct ExpressionRef(Arc<Node>);
+
+struct Node {
+    expression: Expression,
+    sizes: OnceLock<Vec<Size>>,
+    variable_sizes: OnceLock<HashMap<String, Vec<Size>>>,
+}
+
+struct Interner {
+    nodes: HashMap<u64, Vec<Weak<Node>>>,
+    sweep_at: usize,
+}
+
+const SHARDS: usize = 64;
+
+/// The shard of the interner holding the nodes of `hash`.
+/// The nodes are spread over shards locked apart, so that the threads building expressions seldom wait for each other.
+fn interner(hash: u64) -> &'static Mutex<Interner> {
+    static INTERNER: OnceLock<Vec<Mutex<Interner>>> = OnceLock::new();
+    let shards = INTERNER.get_or_init(|| {
+        (0..SHARDS)
+            .map(|_| {
+                Mutex::new(Interner {
+                    nodes: HashMap::new(),
+                    sweep_at: 1024,
+                })
+            })
+            .collect()
+    });
+
+    &shards[hash as usize % SHARDS]
+}
+
+impl ExpressionRef {
+    pub fn new(expression: Expression) -> Self {
+        let mut hasher = DefaultHasher::new();
+        expression.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        let mut interner = interner(hash).lock().unwrap_or_else(|e| e.into_inner());
+        if interner.nodes.len() >= interner.sweep_at {
+            // Forget the nodes which are no longer referred to.
+            interner.nodes.retain(|_, bucket| {
+                bucket.retain(|node| node.strong_count() > 0);
+                !bucket.is_empty()
+            });
+            interner.sweep_at = 1024.max(2 * interner.nodes.len());
+        }
+
+        let bucket = interner.nodes.entry(hash).or_default();
+        if let Some(node) = bucket
+            .iter()
+            .filter_map(Weak::upgrade)
+            .find(|node| node.expression == expression)
+        {
+            return ExpressionRef(node);
+        }
+        bucket.retain(|node| node.strong_count() > 0);
+
+        let node = Arc::new(Node {
+            expression,
+            sizes: OnceLock::new(),
+            variable_sizes: OnceLock::new(),
+        });
+        bucket.push(Arc::downgrade(&node));
+
+        ExpressionRef(node)
+    }
+
+    /// The address of the node, shared by all the structurally identical expressions alive.
+    pub fn id(&self) -> usize {
+        Arc::as_ptr(&self.0) as usize
+    }
+
+    /// The sizes of the node, computed once however many times it is shared.
+    pub fn sizes(&self) -> Vec<Size> {
+        self.0
+            .sizes
+            .get_or_init(|| self.0.expression.sizes())
+            .clone()
+    }
+
+    /// The variables of the node, collected once however many times it is shared.
+    pub fn variable_sizes(&self) -> HashMap<&str, &[Size]> {
+        self.0
+            .variable_sizes
+            .get_or_init(|| {
+                self.0
+                    .expression
+                    .variable_sizes()
+                    .into_iter()
+                    .map(|(id, sizes)| (id.to_owned(), sizes.to_vec()))
+                    .collect()
+            })
+            .iter()
+            .map(|(id, sizes)| (id.as_str(), sizes.as_slice()))
+            .collect()
+    }
+
+    pub fn variable_ids(&self) -> HashSet<&str> {
+        self.variable_sizes().into_keys().collect()
+    }
+}
+
+impl Deref for ExpressionRef {
+    type Target = Expression;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0.expression
+    }
+}
+
+impl AsRef<Expression> for ExpressionRef {
+    fn as_ref(&self) -> &Expression {
+        &self.0.expression
+    }
+}
+
+impl From<Expression> for ExpressionRef {
+    fn from(expression: Expression) -> Self {
+        ExpressionRef::new(expression)
+    }
+}
+
+impl PartialEq for ExpressionRef {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Hash for ExpressionRef {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id().hash(state);
+    }
+}
+
+impl fmt::Debug for ExpressionRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.expression.fmt(f)
+    }
+}
+
+impl Serialize for ExpressionRef {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.expression.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ExpressionRef {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Expression::deserialize(deserializer).map(ExpressionRef::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rayon::prelude::*;
+    use std::collections::HashMap;
+
+    use crate::{new_variable, ConstantValue, Expression, ExpressionRef};
+
+    #[test]
+    fn it_works() {
+        let x = new_variable("x".to_string());
+        let y = new_variable("y".to_string());
+
+        let a = ExpressionRef::from((x.clone() + y.clone()).sin());
+        let b = ExpressionRef::from((x.clone() + y.clone()).sin());
+        let c = ExpressionRef::from((y.clone() + x.clone()).sin());
+        assert_eq!(a.id(), b.id());
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+
+        // Children are shared between the trees built from the same parts.
+        let sum = x.clone() + y.clone();
+        if let (Expression::Add(l1, _), Expression::Add(l2, _)) = (&sum, &(x.clone() + 2.0)) {
+            assert_eq!(l1.id(), l2.id());
+        } else {
+            unreachable!();
+        }
+
+        let json = ron::to_string(&a.as_ref()).unwrap();
+        let d = ExpressionRef::from(ron::from_str::<Expression>(&json).unwrap());
+        assert_eq!(a, d);
+    }
+
+    #[test]
+    fn it_works2() {
+        let x = new_variable("x".to_string());
+
+        // Every level refers to the previous one twice, so walking it as a tree would take 2^40 steps.
+        let mut e = x.clone();
+        for _ in 0..40 {
+            e = (e.clone() * e.clone()).sin() + e.clone();
+        }
+
+        let diff = e.differential(&["x"])[0].clone();
+        let mut hash = HashMap::new();
+        hash.insert("x", ConstantValue::Scalar(0.0));
+        assert_eq!(diff.assign(&hash), Expression::from(1.0));
+        assert_eq!(e.assign(&hash), Expression::from(0.0));
+    }
+
+    #[test]
+    fn it_works3() {
+        let x = new_variable("x".to_string());
+
+        // The same nodes are built on many threads at once, which share them whichever shard they are in.
+        let ids = (0..64)
+            .into_par_iter()
+            .map(|_| {
+                (0..100)
+                    .map(|i| ExpressionRef::from((x.clone() * i as f64).exp()))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        for refs in ids.iter() {
+            assert!(refs.iter().zip(ids[0].iter()).all(|(a, b)| a == b));
+        }
+    }
+}
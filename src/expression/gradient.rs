@@ -0,0 +1,360 @@
+use crate::{Expression, ExpressionRef, MatrixExpression, Size, SymbolicError, TensorExpression};
+use opensrdk_linear_algebra::RankIndex;
+use std::collections::{HashMap, HashSet};
+
+/// Whether the adjoint sweep goes through the node by the rules of its operands' partial derivatives.
+fn is_elementwise(node: &ExpressionRef) -> bool {
+    let elementwise = matches!(
+        node.as_ref(),
+        Expression::Add(_, _)
+            | Expression::Sub(_, _)
+            | Expression::Mul(_, _)
+            | Expression::Div(_, _)
+            | Expression::Neg(_)
+            | Expression::Transcendental(_)
+    );
+
+    elementwise && Size::effective_rank(&node.sizes()) == 0
+}
+
+/// Whether the adjoint sweep goes through the node, rather than differentiating it forward as a whole.
+fn is_swept(node: &ExpressionRef) -> bool {
+    is_elementwise(node)
+        || match node.as_ref() {
+            Expression::Tensor(v) => matches!(v.as_ref(), TensorExpression::DotProduct { .. }),
+            Expression::Matrix(v) => matches!(
+                v.as_ref(),
+                MatrixExpression::T(_) | MatrixExpression::Inv(_) | MatrixExpression::Det(_)
+            ),
+            _ => false,
+        }
+}
+
+fn visit(node: ExpressionRef, visited: &mut HashSet<usize>, order: &mut Vec<ExpressionRef>) {
+    if !visited.insert(node.id()) {
+        return;
+    }
+    if is_swept(&node) {
+        for c in node.children() {
+            visit(c.into(), visited, order);
+        }
+    }
+    order.push(node);
+}
+
+fn open(rank: RankIndex) -> String {
+    format!("${}", rank)
+}
+
+impl Expression {
+    /// The same derivatives as `differential`, computed by one reverse sweep from the output to the variables.
+    ///
+    /// The adjoint of each node is built once and shared by the derivatives of all the variables, so the cost does not grow with the number of them.
+    /// The adjoint of a node has its sizes, and the sweep goes through scalar arithmetic and transcendental functions, dot products, transposes, inverses and determinants.
+    /// The other nodes are differentiated forward as a whole, and their derivatives are contracted with their adjoints.
+    pub fn gradient(&self, variable_ids: &[&str]) -> Vec<Expression> {
+        self.try_gradient(variable_ids)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_gradient(&self, variable_ids: &[&str]) -> Result<Vec<Expression>, SymbolicError> {
+        if Size::effective_rank(&self.sizes()) != 0 {
+            return Ok(self.differential(variable_ids));
+        }
+
+        let root = ExpressionRef::from(self.clone());
+        let mut order = vec![];
+        visit(root.clone(), &mut HashSet::new(), &mut order);
+
+        Expression::with_differentials(HashMap::new(), || {
+            Expression::sweep(&root, &order, variable_ids)
+        })
+    }
+
+    fn sweep(
+        root: &ExpressionRef,
+        order: &[ExpressionRef],
+        variable_ids: &[&str],
+    ) -> Result<Vec<Expression>, SymbolicError> {
+        let variable_sizes = root.variable_sizes();
+        let variable_ranks = variable_ids
+            .iter()
+            .map(|id| variable_sizes.get(id).map_or(0, |sizes| sizes.len()))
+            .collect::<Vec<_>>();
+
+        let mut adjoints = HashMap::<usize, Expression>::new();
+        adjoints.insert(root.id(), 1.0.into());
+        let mut gradient = vec![Expression::from(0.0); variable_ids.len()];
+
+        for node in order.iter().rev() {
+            let adjoint = match adjoints.remove(&node.id()) {
+                Some(adjoint) => adjoint,
+                None => continue,
+            };
+
+            let contributions = match node.as_ref() {
+                Expression::Constant(_) => continue,
+                Expression::Variable(id, sizes) if Size::effective_rank(sizes) == 0 => {
+                    if let Some(i) = variable_ids.iter().position(|&v| v == id.as_str()) {
+                        gradient[i] = gradient[i].clone() + adjoint;
+                    }
+                    continue;
+                }
+                e if is_elementwise(node) => Some(
+                    e.partials()
+                        .into_iter()
+                        .map(|(operand, partial)| (operand, adjoint.clone() * partial))
+                        .collect(),
+                ),
+                _ if is_swept(node) => Expression::adjoint_contributions(node, &adjoint),
+                _ => None,
+            };
+
+            match contributions {
+                Some(contributions) => {
+                    for (operand, contribution) in contributions {
+                        let sum = match adjoints.remove(&operand.id()) {
+                            Some(sum) => sum + contribution,
+                            None => contribution,
+                        };
+                        adjoints.insert(operand.id(), sum);
+                    }
+                }
+                None if Size::effective_rank(&node.sizes()) == 0 => {
+                    for (g, d) in gradient.iter_mut().zip(node._differential(variable_ids, 0)) {
+                        *g = g.clone() + adjoint.clone() * d;
+                    }
+                }
+                None => {
+                    let ds = node._differential(variable_ids, node.sizes().len());
+                    for (i, d) in ds.into_iter().enumerate() {
+                        if d == Expression::from(0.0) {
+                            continue;
+                        }
+                        let contribution = Expression::contract_adjoint(
+                            &adjoint,
+                            node.sizes().len(),
+                            d,
+                            variable_ranks[i],
+                        )?;
+                        gradient[i] = gradient[i].clone() + contribution;
+                    }
+                }
+            }
+        }
+
+        Ok(gradient)
+    }
+
+    /// The adjoints of the operands of a dot product, a transpose, an inverse or a determinant, or none if they cannot be built.
+    fn adjoint_contributions(
+        node: &ExpressionRef,
+        adjoint: &Expression,
+    ) -> Option<Vec<(ExpressionRef, Expression)>> {
+        match node.as_ref() {
+            Expression::Tensor(v) => match v.as_ref() {
+                TensorExpression::DotProduct {
+                    terms,
+                    rank_combinations,
+                } => {
+                    let adjoints = TensorExpression::dot_product_adjoint_expressions(
+                        terms,
+                        rank_combinations,
+                        adjoint,
+                    )
+                    .ok()?;
+                    Some(
+                        terms
+                            .iter()
+                            .zip(adjoints)
+                            .filter_map(|(t, a)| a.map(|a| (t.clone().into(), a)))
+                            .collect(),
+                    )
+                }
+                _ => None,
+            },
+            Expression::Matrix(v) => {
+                let (a, contribution) = match v.as_ref() {
+                    MatrixExpression::T(a) => (a, adjoint.clone().try_t().ok()?),
+                    // -A^-T G A^-T
+                    MatrixExpression::Inv(a) => {
+                        let inv_t = node.as_ref().clone().try_t().ok()?;
+                        let product = inv_t
+                            .clone()
+                            .try_dot(adjoint.clone(), &[[1, 0]])
+                            .ok()?
+                            .try_dot(inv_t, &[[1, 0]])
+                            .ok()?;
+                        (a, -product)
+                    }
+                    // G |A| A^-T
+                    MatrixExpression::Det(a) => {
+                        let inv_t = a.as_ref().clone().try_inv().ok()?.try_t().ok()?;
+                        // The adjoint may be a tensor of size one, which the product with a matrix does not broadcast.
+                        let contribution = (adjoint.clone() * node.as_ref().clone())
+                            .try_dot(inv_t, &[])
+                            .ok()?;
+                        (a, contribution)
+                    }
+                    _ => return None,
+                };
+                Some(vec![(a.clone(), contribution)])
+            }
+            _ => None,
+        }
+    }
+
+    /// Contracts the adjoint of a node of `rank` ranks with the derivative `d` of the node by a variable of `variable_rank` ranks.
+    /// The ranks of the variable are placed after its own ranks, where `differential` of a scalar places them.
+    fn contract_adjoint(
+        adjoint: &Expression,
+        rank: usize,
+        d: Expression,
+        variable_rank: usize,
+    ) -> Result<Expression, SymbolicError> {
+        let adjoint_combination = adjoint
+            .sizes()
+            .iter()
+            .enumerate()
+            .filter(|(_, size)| !size.is_one())
+            .map(|(r, _)| (r, open(r)))
+            .collect::<HashMap<_, _>>();
+
+        let (from, to) = (variable_rank.max(rank), variable_rank);
+        let mut d_combination = adjoint_combination.clone();
+        let mut kept = vec![];
+        if from != to {
+            for (r, size) in d.sizes().iter().enumerate().skip(from) {
+                if !size.is_one() {
+                    let id = format!("$d{}", r - from);
+                    d_combination.insert(r, id.clone());
+                    kept.push((to + r - from, id));
+                }
+            }
+        }
+
+        TensorExpression::try_dot_product_keeping(
+            vec![adjoint.clone(), d],
+            vec![adjoint_combination, d_combination],
+            &kept,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use opensrdk_linear_algebra::{indices_cartesian_product, sparse::SparseTensor};
+
+    use crate::{new_variable, new_variable_tensor, ConstantValue, Expression, Size};
+
+    #[test]
+    fn it_works() {
+        let n = 50;
+        let ids = (0..n).map(|i| format!("u_{{{}}}", i)).collect::<Vec<_>>();
+        let u = ids
+            .iter()
+            .map(|id| new_variable(id.clone()))
+            .collect::<Vec<_>>();
+        let theta = new_variable("theta".to_string());
+
+        let expression = u
+            .iter()
+            .zip(u.iter().skip(1))
+            .map(|(a, b)| ((a.clone() - b.clone()).pow(2.0.into()) * theta.clone()).exp())
+            .fold(Expression::from(0.0), |acc, k| acc + k)
+            .ln();
+
+        let mut variable_ids = ids.iter().map(|id| id.as_str()).collect::<Vec<_>>();
+        variable_ids.push("theta");
+
+        let gradient = expression.gradient(&variable_ids);
+        let differential = expression.differential(&variable_ids);
+
+        let mut hash = HashMap::new();
+        for (i, id) in ids.iter().enumerate() {
+            hash.insert(id.as_str(), ConstantValue::Scalar((i as f64 * 0.7).sin()));
+        }
+        hash.insert("theta", ConstantValue::Scalar(-0.3));
+
+        for (g, d) in gradient.iter().zip(differential.iter()) {
+            let g = g.evaluate(&hash).unwrap().into_scalar();
+            let d = d.evaluate(&hash).unwrap().into_scalar();
+            assert!((g - d).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn it_works2() {
+        let x = new_variable_tensor("x".to_string(), vec![Size::Many]);
+        let s = new_variable("s".to_string());
+        let a = Expression::from(vec![1.0, 2.0, 3.0]);
+
+        // The sweep goes around the dot product, whose derivative is taken forward.
+        let expression = (a.dot(x, &[[0, 0]]) * s.clone()).sin() + s.clone().pow(3.0.into());
+
+        let mut hash = HashMap::new();
+        hash.insert("x", ConstantValue::Tensor(vec![0.5, -1.0, 2.0].into()));
+        hash.insert("s", ConstantValue::Scalar(0.4));
+
+        let g = expression.try_gradient(&["s"]).unwrap()[0]
+            .evaluate(&hash)
+            .unwrap();
+        let d = expression.differential(&["s"])[0].evaluate(&hash).unwrap();
+        assert!((g.elems()[0] - d.elems()[0]).abs() < 1e-12);
+    }
+
+    #[test]
+    fn it_works3() {
+        let x = new_variable_tensor("x".to_string(), vec![Size::Many]);
+        let a = new_variable_tensor("a".to_string(), vec![Size::Many, Size::Many]);
+        let b = Expression::from(vec![1.0, -0.5, 2.0]);
+
+        // The sweep goes through the dot products, the transpose, the inverse and the determinant.
+        let expression = x
+            .clone()
+            .dot(a.clone().inv(), &[[0, 0]])
+            .dot(b.clone(), &[[1, 0]])
+            * a.clone().det()
+            + x.clone()
+                .dot(a.clone().t(), &[[0, 0]])
+                .dot(b.clone(), &[[1, 0]])
+            + x.clone()
+                .dot(a.clone(), &[[0, 0]])
+                .dot(x.clone(), &[[1, 0]]);
+
+        let mut point = HashMap::new();
+        point.insert("x", ConstantValue::Tensor(vec![0.5, -1.0, 2.0].into()));
+        let mut elems = HashMap::new();
+        for (indices, v) in [
+            ([0, 0], 2.0),
+            ([0, 1], 0.5),
+            ([1, 1], 1.5),
+            ([1, 2], -0.3),
+            ([2, 0], 0.2),
+            ([2, 2], 1.0),
+        ] {
+            elems.insert(indices.to_vec(), v);
+        }
+        point.insert(
+            "a",
+            ConstantValue::Tensor(SparseTensor::from(vec![3, 3], elems).unwrap()),
+        );
+
+        let (_, expected) = expression.value_and_grad(&point, &["x", "a"]).unwrap();
+        let gradient = expression.gradient(&["x", "a"]);
+
+        for (g, expected) in gradient.iter().zip(expected.iter()) {
+            // The ranks of the variable follow the size-one ranks of the output.
+            let g = g.evaluate(&point).unwrap().to_tensor();
+            let sizes = expected.sizes();
+            let expected = expected.to_tensor();
+            for indices in indices_cartesian_product(&sizes) {
+                let mut g_indices = vec![0; sizes.len()];
+                g_indices.extend(indices.iter());
+                assert!((g[&g_indices] - expected[&indices]).abs() < 1e-10);
+            }
+        }
+    }
+}
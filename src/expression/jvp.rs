@@ -0,0 +1,430 @@
+use crate::{
+    expression::value_and_grad::derivatives, matrix_expression::operations::cholesky::lower_half,
+    ConstantValue, Expression, ExpressionRef, MatrixExpression, SymbolicError, TensorExpression,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A value together with its derivative along a direction.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Dual {
+    pub value: ConstantValue,
+    pub tangent: ConstantValue,
+}
+
+impl Dual {
+    fn constant(value: ConstantValue) -> Self {
+        let tangent = value.clone().map(|_| 0.0);
+        Self { value, tangent }
+    }
+}
+
+struct Pass<'a> {
+    point: &'a HashMap<&'a str, ConstantValue>,
+    direction: &'a HashMap<&'a str, ConstantValue>,
+    /// The dual of each node visited, or none for the nodes without a value of their own.
+    duals: HashMap<usize, Option<Dual>>,
+    nodes: Vec<ExpressionRef>,
+}
+
+impl<'a> Pass<'a> {
+    fn visit(&mut self, e: &Expression) -> Result<Option<Dual>, SymbolicError> {
+        let node = ExpressionRef::from(e.clone());
+        if let Some(dual) = self.duals.get(&node.id()) {
+            return Ok(dual.clone());
+        }
+
+        let children = e.children();
+        let operands = children
+            .iter()
+            .map(|c| self.visit(c))
+            .collect::<Result<Vec<_>, _>>()?;
+        let standalone = operands.iter().map(|o| o.is_some()).collect::<Vec<_>>();
+
+        let dual = if e.is_standalone(&standalone) {
+            Some(self.apply(e, children, operands)?)
+        } else {
+            None
+        };
+
+        self.duals.insert(node.id(), dual.clone());
+        self.nodes.push(node);
+
+        Ok(dual)
+    }
+
+    fn apply(
+        &self,
+        e: &Expression,
+        children: Vec<Expression>,
+        operands: Vec<Option<Dual>>,
+    ) -> Result<Dual, SymbolicError> {
+        let mut duals = operands.iter().flatten();
+        let mut next = || duals.next().unwrap();
+
+        Ok(match e {
+            Expression::Variable(id, _) => {
+                let value = Expression::evaluate_variable(id, self.point)?;
+                match self.direction.get(id.as_str()) {
+                    Some(tangent) => Dual {
+                        value,
+                        tangent: tangent.clone(),
+                    },
+                    None => Dual::constant(value),
+                }
+            }
+            Expression::Constant(v) => Dual::constant(v.clone()),
+            Expression::Add(_, _) => {
+                let (l, r) = (next(), next());
+                Dual {
+                    value: l.value.try_add(r.value.clone())?,
+                    tangent: l.tangent.try_add(r.tangent.clone())?,
+                }
+            }
+            Expression::Sub(_, _) => {
+                let (l, r) = (next(), next());
+                Dual {
+                    value: l.value.try_sub(r.value.clone())?,
+                    tangent: l.tangent.try_sub(r.tangent.clone())?,
+                }
+            }
+            Expression::Mul(_, _) => {
+                let (l, r) = (next(), next());
+                Dual {
+                    value: l.value.try_mul(r.value.clone())?,
+                    tangent: l
+                        .tangent
+                        .try_mul(r.value.clone())?
+                        .try_add(l.value.try_mul(r.tangent.clone())?)?,
+                }
+            }
+            Expression::Div(_, _) => {
+                let (l, r) = (next(), next());
+                let value = l.value.clone().try_div(&r.value)?;
+                let tangent = l
+                    .tangent
+                    .try_sub(value.try_mul(r.tangent.clone())?)?
+                    .try_div(&r.value)?;
+                Dual { value, tangent }
+            }
+            Expression::Neg(_) => {
+                let v = next();
+                Dual {
+                    value: v.value.clone().map(|v| -v),
+                    tangent: v.tangent.clone().map(|v| -v),
+                }
+            }
+            Expression::Matrix(v) => {
+                let v = v.as_ref();
+                let a = next();
+                match v {
+                    MatrixExpression::T(_) => Dual {
+                        value: a.value.t()?,
+                        tangent: a.tangent.t()?,
+                    },
+                    MatrixExpression::Inv(_) => {
+                        // d(A^-1) = -A^-1 dA A^-1
+                        let value = a.value.inv()?;
+                        let inv = value.to_matrix()?;
+                        let tangent = inv.dot(&a.tangent.to_matrix()?).dot(&inv);
+                        Dual {
+                            value,
+                            tangent: ConstantValue::Matrix(tangent).map(|v| -v),
+                        }
+                    }
+                    MatrixExpression::Det(_) => {
+                        // d(det A) = det A tr(A^-1 dA)
+                        let det = a.value.det()?.into_scalar();
+                        let inv = a.value.inv()?.to_matrix()?;
+                        let tr = inv.dot(&a.tangent.to_matrix()?).tr();
+                        Dual {
+                            value: ConstantValue::Scalar(det),
+                            tangent: ConstantValue::Scalar(det * tr),
+                        }
+                    }
+                    MatrixExpression::Cholesky(_) => {
+                        // dL = L Φ(L^-1 dA L^-T)
+                        let value = a.value.cholesky()?;
+                        let l = value.to_matrix()?;
+                        let l_inv = value.inv()?.to_matrix()?;
+                        let inner = l_inv.dot(&a.tangent.to_matrix()?).dot(&l_inv.t());
+                        Dual {
+                            value,
+                            tangent: ConstantValue::Matrix(l.dot(&lower_half(&inner))),
+                        }
+                    }
+                    MatrixExpression::Solve(_, _) => {
+                        // dX = A^-1 (dB - dA X)
+                        let b = next();
+                        let value = a.value.solve(&b.value)?;
+                        let moved = b.tangent.to_matrix()?
+                            - a.tangent.to_matrix()?.dot(&value.to_matrix()?);
+                        Dual {
+                            tangent: a
+                                .value
+                                .solve(&ConstantValue::matrix_like(moved, &b.value)?)?,
+                            value,
+                        }
+                    }
+                    MatrixExpression::LogDet(_) => {
+                        // d(ln|A|) = tr(A^-1 dA)
+                        let tr = a.value.solve(&a.tangent)?.to_matrix()?.tr();
+                        Dual {
+                            value: a.value.log_det()?,
+                            tangent: ConstantValue::Scalar(tr),
+                        }
+                    }
+                    MatrixExpression::LowerHalf(_) => Dual {
+                        value: a.value.lower_half()?,
+                        tangent: a.tangent.lower_half()?,
+                    },
+                }
+            }
+            Expression::PartialVariable(_) => {
+                // The elements are put in as they are, so the tangents are put in the same way.
+                Dual {
+                    value: Pass::evaluate_on(e, &children, &operands, |_, d| d.value.clone())?,
+                    tangent: Pass::evaluate_on(e, &children, &operands, |_, d| d.tangent.clone())?,
+                }
+            }
+            Expression::Transcendental(v) => {
+                let args = operands
+                    .iter()
+                    .flatten()
+                    .map(|d| d.value.clone())
+                    .collect::<Vec<_>>();
+                let value = Pass::evaluate_on(e, &children, &operands, |_, d| d.value.clone())?;
+                let mut tangent = value.clone().map(|_| 0.0);
+                for (d, dual) in derivatives(v, &args)?
+                    .into_iter()
+                    .zip(operands.iter().flatten())
+                {
+                    // The derivatives by constant operands, like that of a power by its exponent, may not be finite.
+                    if dual.tangent.elems().iter().all(|&t| t == 0.0) {
+                        continue;
+                    }
+                    tangent = tangent.try_add(d.try_mul(dual.tangent.clone())?)?;
+                }
+                Dual { value, tangent }
+            }
+            Expression::Tensor(v) => {
+                let value = Pass::evaluate_on(e, &children, &operands, |_, d| d.value.clone())?;
+                let tangent = match v.as_ref() {
+                    TensorExpression::DotProduct { .. } | TensorExpression::DirectProduct(_) => {
+                        // The products are linear in each term, so the tangent of each is contracted with the values of the others.
+                        let mut tangent = value.clone().map(|_| 0.0);
+                        for (k, o) in operands.iter().enumerate() {
+                            match o {
+                                Some(dual) if dual.tangent.elems().iter().any(|&t| t != 0.0) => {
+                                    let moved =
+                                        Pass::evaluate_on(e, &children, &operands, |j, d| {
+                                            if j == k {
+                                                d.tangent.clone()
+                                            } else {
+                                                d.value.clone()
+                                            }
+                                        })?;
+                                    tangent = tangent.try_add(moved)?;
+                                }
+                                _ => {}
+                            }
+                        }
+                        tangent
+                    }
+                    TensorExpression::LogSumExp { rank, .. } => {
+                        let a = next();
+                        TensorExpression::log_sum_exp_tangent(&a.value, &value, *rank, &a.tangent)?
+                    }
+                    // The Jacobian of the softmax is symmetric, so it moves the tangent as it moves the adjoint.
+                    TensorExpression::Softmax { rank, .. } => {
+                        TensorExpression::softmax_adjoint(&value, *rank, &next().tangent)?
+                    }
+                    TensorExpression::KroneckerDeltas(_)
+                    | TensorExpression::SizedKroneckerDeltas(_) => value.clone().map(|_| 0.0),
+                };
+                Dual { value, tangent }
+            }
+        })
+    }
+
+    /// Evaluates `e` on its operands put in as variables, each of them the value `pick` takes from its dual.
+    fn evaluate_on(
+        e: &Expression,
+        children: &[Expression],
+        operands: &[Option<Dual>],
+        pick: impl Fn(usize, &Dual) -> ConstantValue,
+    ) -> Result<ConstantValue, SymbolicError> {
+        let mut variables = HashMap::new();
+        let children = children
+            .iter()
+            .zip(operands.iter())
+            .enumerate()
+            .map(|(k, (c, o))| match o {
+                Some(dual) => {
+                    variables.insert(format!("${}", k), pick(k, dual));
+                    Expression::Variable(format!("${}", k), c.sizes())
+                }
+                None => c.clone(),
+            })
+            .collect();
+        let variables = variables
+            .iter()
+            .map(|(id, v)| (id.as_str(), v.clone()))
+            .collect::<HashMap<_, _>>();
+
+        e.with_children(children)._evaluate(&variables)
+    }
+}
+
+impl Expression {
+    /// The value at `point` and the derivative along `direction`, computed together in one forward pass.
+    /// The variables missing in `direction` are held fixed.
+    pub fn jvp(
+        &self,
+        point: &HashMap<&str, ConstantValue>,
+        direction: &HashMap<&str, ConstantValue>,
+    ) -> Result<Dual, SymbolicError> {
+        self.bind_dims(point)?;
+
+        let mut pass = Pass {
+            point,
+            direction,
+            duals: HashMap::new(),
+            nodes: vec![],
+        };
+        pass.visit(self)?.ok_or(SymbolicError::UndeterminedSize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use opensrdk_linear_algebra::Matrix;
+
+    use crate::{new_variable, new_variable_tensor, ConstantValue, Expression, Size};
+
+    fn central_difference(
+        expression: &Expression,
+        point: &HashMap<&str, ConstantValue>,
+        direction: &HashMap<&str, ConstantValue>,
+    ) -> Vec<f64> {
+        let h = 1e-6;
+        let moved = |s: f64| {
+            let mut moved = point.clone();
+            for (id, d) in direction.iter() {
+                // The elements of the tensors are stored in no fixed order, so they are moved by the operators.
+                let step = d.clone().map(|d| s * h * d);
+                moved.insert(id, point[id].try_add(step).unwrap());
+            }
+            expression.evaluate(&moved).unwrap().elems()
+        };
+
+        moved(1.0)
+            .iter()
+            .zip(moved(-1.0).iter())
+            .map(|(p, m)| (p - m) / (2.0 * h))
+            .collect()
+    }
+
+    #[test]
+    fn it_works() {
+        let x = new_variable("x".to_string());
+        let y = new_variable("y".to_string());
+        let expression = (x.clone() * y.clone()).sin().exp()
+            / (1.0 + x.clone().pow(2.0.into())).ln()
+            + y.clone().atan2(x.clone()).sigmoid();
+
+        let mut point = HashMap::new();
+        point.insert("x", ConstantValue::Scalar(0.7));
+        point.insert("y", ConstantValue::Scalar(-1.3));
+        let mut direction = HashMap::new();
+        direction.insert("x", ConstantValue::Scalar(0.4));
+        direction.insert("y", ConstantValue::Scalar(2.0));
+
+        let dual = expression.jvp(&point, &direction).unwrap();
+        assert_eq!(dual.value, expression.evaluate(&point).unwrap());
+
+        let expected = central_difference(&expression, &point, &direction)[0];
+        assert!((dual.tangent.into_scalar() - expected).abs() < 1e-6);
+
+        // The symbolic gradient contracted with the direction agrees.
+        let gradient = expression.gradient(&["x", "y"]);
+        let contracted = gradient[0].evaluate(&point).unwrap().into_scalar() * 0.4
+            + gradient[1].evaluate(&point).unwrap().into_scalar() * 2.0;
+        assert!((dual.tangent.into_scalar() - contracted).abs() < 1e-12);
+    }
+
+    #[test]
+    fn it_works2() {
+        let a = new_variable_tensor("a".to_string(), vec![Size::Many, Size::Many]);
+        let expression = a.clone().det() + a.clone().inv().t().det().ln();
+
+        let mut point = HashMap::new();
+        point.insert(
+            "a",
+            ConstantValue::Matrix(Matrix::from(2, vec![2.0, 0.5, 0.3, 1.5]).unwrap()),
+        );
+        let mut direction = HashMap::new();
+        direction.insert(
+            "a",
+            ConstantValue::Matrix(Matrix::from(2, vec![0.1, -0.2, 0.4, 0.3]).unwrap()),
+        );
+
+        let dual = expression.jvp(&point, &direction).unwrap();
+        let expected = central_difference(&expression, &point, &direction)[0];
+        assert!((dual.tangent.into_scalar() - expected).abs() < 1e-6);
+    }
+
+    #[test]
+    fn it_works3() {
+        let x = new_variable_tensor("x".to_string(), vec![Size::Many]);
+        let a = new_variable_tensor("a".to_string(), vec![Size::Many, Size::Many]);
+        let expression = x.clone().dot(a, &[[0, 0]]).dot(x, &[[1, 0]]);
+
+        let mut point = HashMap::new();
+        point.insert("x", ConstantValue::Tensor(vec![1.0, 2.0].into()));
+        point.insert(
+            "a",
+            ConstantValue::Matrix(Matrix::from(2, vec![1.0, 3.0, 2.0, 4.0]).unwrap()),
+        );
+        let mut direction = HashMap::new();
+        direction.insert("x", ConstantValue::Tensor(vec![0.5, -1.0].into()));
+
+        let dual = expression.jvp(&point, &direction).unwrap();
+        assert_eq!(dual.value.elems(), vec![27.0]);
+
+        // x^T (A + A^T) v
+        assert!((dual.tangent.elems()[0] - (-15.0)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn it_works4() {
+        let x = new_variable_tensor("x".to_string(), vec![Size::Many]);
+        let y = new_variable_tensor("y".to_string(), vec![Size::Many]);
+        let expressions = [
+            x.clone().direct(y.clone()).log_sum_exp(0),
+            x.clone().softmax(0).dot(y.clone(), &[[0, 0]]),
+            (x.clone() - 2.0)
+                .pow(2.0.into())
+                .dot(y.clone().exp(), &[[0, 0]]),
+        ];
+
+        let mut point = HashMap::new();
+        point.insert("x", ConstantValue::Tensor(vec![0.5, -1.0, 2.0].into()));
+        point.insert("y", ConstantValue::Tensor(vec![1.0, 0.3, -0.7].into()));
+        let mut direction = HashMap::new();
+        direction.insert("x", ConstantValue::Tensor(vec![0.2, 1.0, -0.4].into()));
+        direction.insert("y", ConstantValue::Tensor(vec![-1.0, 0.5, 0.6].into()));
+
+        for expression in expressions.iter() {
+            let dual = expression.jvp(&point, &direction).unwrap();
+            // The sparse elements are summed in no fixed order.
+            let value = expression.evaluate(&point).unwrap().elems()[0];
+            assert!((dual.value.elems()[0] - value).abs() < 1e-12);
+
+            let expected = central_difference(expression, &point, &direction)[0];
+            assert!((dual.tangent.elems()[0] - expected).abs() < 1e-6);
+        }
+    }
+}
@@ -1,14 +1,37 @@
-use crate::{ConstantValue, Expression, MatrixExpression};
+use crate::{ConstantValue, Expression, MatrixExpression, Size, SymbolicError};
 use std::collections::HashMap;
 
 impl MatrixExpression {
-    pub fn assign(self, variables: &HashMap<&str, ConstantValue>) -> Expression {
+    pub fn try_assign(
+        self,
+        variables: &HashMap<&str, ConstantValue>,
+    ) -> Result<Expression, SymbolicError> {
+        let dims = Size::bind_dims(self.variable_sizes(), variables)?;
+        self._assign(variables, &dims)
+    }
+
+    pub(crate) fn _assign(
+        self,
+        variables: &HashMap<&str, ConstantValue>,
+        dims: &HashMap<String, usize>,
+    ) -> Result<Expression, SymbolicError> {
         match self {
-            MatrixExpression::T(v) => v.assign(variables).t(),
-            MatrixExpression::Inv(v) => v.assign(variables).inv(),
-            MatrixExpression::Det(v) => v.assign(variables).det(),
+            MatrixExpression::T(v) => v._assign(variables, dims)?.try_t(),
+            MatrixExpression::Inv(v) => v._assign(variables, dims)?.try_inv(),
+            MatrixExpression::Det(v) => v._assign(variables, dims)?.try_det(),
+            MatrixExpression::Cholesky(v) => v._assign(variables, dims)?.try_cholesky(),
+            MatrixExpression::Solve(a, b) => a
+                ._assign(variables, dims)?
+                .try_solve(b._assign(variables, dims)?),
+            MatrixExpression::LogDet(v) => v._assign(variables, dims)?.try_log_det(),
+            MatrixExpression::LowerHalf(v) => v._assign(variables, dims)?.try_lower_half(),
         }
     }
+
+    pub fn assign(self, variables: &HashMap<&str, ConstantValue>) -> Expression {
+        self.try_assign(variables)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,45 @@
+use crate::{Expression, MatrixExpression, SymbolicError};
+
+impl MatrixExpression {
+    pub(crate) fn children(&self) -> Vec<Expression> {
+        match self {
+            MatrixExpression::T(v)
+            | MatrixExpression::Inv(v)
+            | MatrixExpression::Det(v)
+            | MatrixExpression::Cholesky(v)
+            | MatrixExpression::LogDet(v)
+            | MatrixExpression::LowerHalf(v) => vec![v.as_ref().clone()],
+            MatrixExpression::Solve(a, b) => vec![a.as_ref().clone(), b.as_ref().clone()],
+        }
+    }
+
+    pub(crate) fn with_children(&self, mut children: Vec<Expression>) -> Expression {
+        let v = children.remove(0).into();
+        match self {
+            MatrixExpression::T(_) => MatrixExpression::T(v),
+            MatrixExpression::Inv(_) => MatrixExpression::Inv(v),
+            MatrixExpression::Det(_) => MatrixExpression::Det(v),
+            MatrixExpression::Cholesky(_) => MatrixExpression::Cholesky(v),
+            MatrixExpression::Solve(_, _) => MatrixExpression::Solve(v, children.remove(0).into()),
+            MatrixExpression::LogDet(_) => MatrixExpression::LogDet(v),
+            MatrixExpression::LowerHalf(_) => MatrixExpression::LowerHalf(v),
+        }
+        .into()
+    }
+
+    pub(crate) fn rebuilt(
+        &self,
+        mut children: Vec<Expression>,
+    ) -> Result<Expression, SymbolicError> {
+        let v = children.remove(0);
+        match self {
+            MatrixExpression::T(_) => v.try_t(),
+            MatrixExpression::Inv(_) => v.try_inv(),
+            MatrixExpression::Det(_) => v.try_det(),
+            MatrixExpression::Cholesky(_) => v.try_cholesky(),
+            MatrixExpression::Solve(_, _) => v.try_solve(children.remove(0)),
+            MatrixExpression::LogDet(_) => v.try_log_det(),
+            MatrixExpression::LowerHalf(_) => v.try_lower_half(),
+        }
+    }
+}
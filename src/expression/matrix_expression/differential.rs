@@ -1,11 +1,31 @@
-use crate::{Expression, MatrixExpression};
+use crate::{Expression, MatrixExpression, Size};
 
 impl MatrixExpression {
     pub fn differential(&self, variable_ids: &[&str]) -> Vec<Expression> {
+        self._differential(variable_ids, Size::effective_rank(&self.sizes()))
+    }
+
+    pub(crate) fn _differential(
+        &self,
+        variable_ids: &[&str],
+        rank_offset: usize,
+    ) -> Vec<Expression> {
         match self {
-            MatrixExpression::T(v) => MatrixExpression::diff_t(v, variable_ids),
-            MatrixExpression::Inv(v) => MatrixExpression::diff_inv(v, variable_ids),
-            MatrixExpression::Det(v) => MatrixExpression::diff_det(v, variable_ids),
+            MatrixExpression::T(v) => MatrixExpression::diff_t(v, variable_ids, rank_offset),
+            MatrixExpression::Inv(v) => MatrixExpression::diff_inv(v, variable_ids, rank_offset),
+            MatrixExpression::Det(v) => MatrixExpression::diff_det(v, variable_ids, rank_offset),
+            MatrixExpression::Cholesky(v) => {
+                MatrixExpression::diff_cholesky(v, variable_ids, rank_offset)
+            }
+            MatrixExpression::Solve(a, b) => {
+                MatrixExpression::diff_solve(a, b, variable_ids, rank_offset)
+            }
+            MatrixExpression::LogDet(v) => {
+                MatrixExpression::diff_log_det(v, variable_ids, rank_offset)
+            }
+            MatrixExpression::LowerHalf(v) => {
+                MatrixExpression::diff_lower_half(v, variable_ids, rank_offset)
+            }
         }
     }
 }
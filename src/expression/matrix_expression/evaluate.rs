@@ -0,0 +1,62 @@
+use crate::{ConstantValue, MatrixExpression, Size, SymbolicError};
+use std::collections::HashMap;
+
+impl MatrixExpression {
+    pub fn evaluate(
+        &self,
+        variables: &HashMap<&str, ConstantValue>,
+    ) -> Result<ConstantValue, SymbolicError> {
+        Size::bind_dims(self.variable_sizes(), variables)?;
+        self._evaluate(variables)
+    }
+
+    pub(crate) fn _evaluate(
+        &self,
+        variables: &HashMap<&str, ConstantValue>,
+    ) -> Result<ConstantValue, SymbolicError> {
+        match self {
+            MatrixExpression::T(v) => v._evaluate(variables)?.t(),
+            MatrixExpression::Inv(v) => v._evaluate(variables)?.inv(),
+            MatrixExpression::Det(v) => v._evaluate(variables)?.det(),
+            MatrixExpression::Cholesky(v) => v._evaluate(variables)?.cholesky(),
+            MatrixExpression::Solve(a, b) => {
+                a._evaluate(variables)?.solve(&b._evaluate(variables)?)
+            }
+            MatrixExpression::LogDet(v) => v._evaluate(variables)?.log_det(),
+            MatrixExpression::LowerHalf(v) => v._evaluate(variables)?.lower_half(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use opensrdk_linear_algebra::Matrix;
+
+    use crate::{new_variable_tensor, ConstantValue, Size};
+
+    #[test]
+    fn it_works() {
+        let id = "x";
+        let ea = new_variable_tensor((id).to_string(), vec![Size::Many, Size::Many]);
+
+        let a = Matrix::from(2, vec![0.0, 1.0, 2.0, 3.0]).unwrap();
+        let mut hash = HashMap::new();
+        hash.insert(id, ConstantValue::Matrix(a.clone()));
+
+        let a_t = ea.clone().t().evaluate(&hash).unwrap();
+        assert_eq!(a_t, ConstantValue::Matrix(a.t()));
+
+        let a_det = ea.clone().det().evaluate(&hash).unwrap();
+        assert_eq!(a_det, ConstantValue::Scalar(-2.0));
+
+        let a_inv = ea.inv().evaluate(&hash).unwrap().into_matrix();
+        let expected = Matrix::from(2, vec![-1.5, 0.5, 1.0, 0.0]).unwrap();
+        a_inv
+            .elems()
+            .iter()
+            .zip(expected.elems().iter())
+            .for_each(|(l, r)| assert!((l - r).abs() < 1e-12));
+    }
+}
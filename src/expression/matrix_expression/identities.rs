@@ -0,0 +1,122 @@
+use crate::{Expression, Size, TensorExpression};
+
+/// The factors `A` and `B` of the matrix product `AB`.
+pub(crate) fn matrix_product(e: &Expression) -> Option<(Expression, Expression)> {
+    let (terms, rank_combinations) = match e {
+        Expression::Tensor(v) => match v.as_ref() {
+            TensorExpression::DotProduct {
+                terms,
+                rank_combinations,
+            } => (terms, rank_combinations),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    if terms.len() != 2 || terms.iter().any(|t| t.sizes().len() != 2) {
+        return None;
+    }
+
+    let (l, r) = (&rank_combinations[0], &rank_combinations[1]);
+    if l.len() != 1 || r.len() != 1 || l.get(&1)? != r.get(&0)? {
+        return None;
+    }
+
+    Some((terms[0].clone(), terms[1].clone()))
+}
+
+/// The scalar `c` and the matrix `A` of `cA`.
+pub(crate) fn scaled_matrix(e: &Expression) -> Option<(Expression, Expression)> {
+    match e {
+        Expression::Mul(l, r) => match (l.sizes().len(), r.sizes().len()) {
+            (0, 2) => Some((l.as_ref().clone(), r.as_ref().clone())),
+            (2, 0) => Some((r.as_ref().clone(), l.as_ref().clone())),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The number of rows of the square matrix, when it is known.
+pub(crate) fn fixed_dimension(v: &Expression) -> Option<usize> {
+    match v.sizes().first()? {
+        Size::One => Some(1),
+        Size::Fixed(n) => Some(*n),
+        _ => None,
+    }
+}
+
+pub(crate) fn is_square(v: &Expression) -> bool {
+    let sizes = v.sizes();
+    sizes.len() == 2 && sizes[0].is_compatible(&sizes[1])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use opensrdk_linear_algebra::Matrix;
+
+    use super::matrix_product;
+    use crate::{new_variable, new_variable_tensor, ConstantValue, Expression, Size};
+
+    #[test]
+    fn it_works() {
+        let a = new_variable_tensor("a".to_string(), vec![Size::Many, Size::Many]);
+        let b = new_variable_tensor("b".to_string(), vec![Size::Many, Size::Many]);
+        let x = new_variable("x".to_string());
+
+        assert_eq!(a.clone().t().t(), a);
+        assert_eq!(a.clone().inv().inv(), a);
+        assert_eq!(a.clone().t().inv(), a.clone().inv().t());
+        assert_eq!(a.clone().t().det(), a.clone().det());
+        assert_eq!(a.clone().inv().det(), 1.0 / a.clone().det());
+        assert_eq!(a.clone().t().inv().det(), 1.0 / a.clone().det());
+
+        let ab = a.clone().dot(b.clone(), &[[1, 0]]);
+        // The ids of the contracted ranks are generated, so the factors are compared.
+        assert_eq!(
+            matrix_product(&ab.clone().t()),
+            Some((b.clone().t(), a.clone().t()))
+        );
+        assert_eq!(ab.clone().det(), a.clone().det() * b.clone().det());
+
+        assert_eq!(Expression::from(-2.0).t(), Expression::from(-2.0));
+        assert_eq!(Expression::from(-4.0).inv(), Expression::from(-0.25));
+        assert_eq!(x.clone().t(), x);
+        assert_eq!(x.clone().inv(), 1.0 / x.clone());
+    }
+
+    #[test]
+    fn it_works2() {
+        let sizes = vec![Size::Fixed(2), Size::Fixed(2)];
+        let a = new_variable_tensor("a".to_string(), sizes.clone());
+        let b = new_variable_tensor("b".to_string(), sizes);
+        let s = new_variable("s".to_string());
+
+        let scaled = (s.clone() * a.clone()).det();
+        assert_eq!(scaled, s.clone().pow(2.0.into()) * a.clone().det());
+
+        let ma = Matrix::from(2, vec![2.0, 0.5, 0.3, 1.5]).unwrap();
+        let mb = Matrix::from(2, vec![1.0, -1.0, 2.0, 0.5]).unwrap();
+        let mut hash = HashMap::new();
+        hash.insert("a", ConstantValue::Matrix(ma.clone()));
+        hash.insert("b", ConstantValue::Matrix(mb.clone()));
+        hash.insert("s", ConstantValue::Scalar(-1.5));
+
+        let ab = a.clone().dot(b.clone(), &[[1, 0]]);
+        let expected = ma.dot(&mb).t();
+        let actual = ab.clone().t().evaluate(&hash).unwrap().to_matrix().unwrap();
+        for i in 0..2 {
+            for j in 0..2 {
+                assert!((actual[(i, j)] - expected[(i, j)]).abs() < 1e-12);
+            }
+        }
+
+        let det = |m: &Matrix| m[(0, 0)] * m[(1, 1)] - m[(0, 1)] * m[(1, 0)];
+        let actual = ab.det().evaluate(&hash).unwrap().into_scalar();
+        assert!((actual - det(&ma) * det(&mb)).abs() < 1e-12);
+
+        let actual = scaled.evaluate(&hash).unwrap().into_scalar();
+        assert!((actual - 1.5f64.powi(2) * det(&ma)).abs() < 1e-12);
+    }
+}
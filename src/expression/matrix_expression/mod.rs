@@ -1,6 +1,10 @@
 pub mod assign;
+pub mod children;
 pub mod differential;
+pub mod evaluate;
+pub mod identities;
 pub mod operations;
+pub mod simplify;
 pub mod size;
 pub mod tex_code;
 pub mod variable;
@@ -13,13 +17,21 @@ pub use size::*;
 pub use tex_code::*;
 pub use variable::*;
 
-use crate::Expression;
+use crate::{Expression, ExpressionRef};
 
-#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Hash, Serialize, Deserialize)]
 pub enum MatrixExpression {
-    T(Box<Expression>),
-    Inv(Box<Expression>),
-    Det(Box<Expression>),
+    T(ExpressionRef),
+    Inv(ExpressionRef),
+    Det(ExpressionRef),
+    /// The lower triangular `L` with `A = L L^T`, for a positive definite `A`.
+    Cholesky(ExpressionRef),
+    /// `A^-1 B` for a positive definite `A`, without inverting it.
+    Solve(ExpressionRef, ExpressionRef),
+    /// The logarithm of the determinant of a positive definite matrix.
+    LogDet(ExpressionRef),
+    /// The lower triangle in the ranks 0 and 1 with the halved diagonal, which the derivatives of the Cholesky factor take.
+    LowerHalf(ExpressionRef),
 }
 
 impl Expression {
@@ -83,9 +95,9 @@ mod tests {
 
         assert_eq!(Expression::from(a_inv), ea_inv);
 
-        let a_det = a.clone().getrf().unwrap().0.trdet();
+        // The matrix is lower triangular, while the LU decomposition interchanges its rows.
         let ea_det = ea.clone().det();
 
-        assert_eq!(Expression::from(a_det), ea_det);
+        assert_eq!(Expression::from(3.0), ea_det);
     }
 }
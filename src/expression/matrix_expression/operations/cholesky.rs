@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+
+use opensrdk_linear_algebra::Matrix;
+
+use crate::{BracketsLevel, Expression, MatrixExpression, SymbolicError};
+
+impl Expression {
+    pub fn try_cholesky(self) -> Result<Expression, SymbolicError> {
+        if let Expression::Constant(v) = &self {
+            return Ok(v.cholesky()?.into());
+        }
+        if self.sizes().is_empty() {
+            return self.try_pow(0.5.into());
+        }
+
+        MatrixExpression::check_square(&self, "decompose")?;
+
+        Ok(MatrixExpression::Cholesky(self.into()).into())
+    }
+
+    pub fn cholesky(self) -> Expression {
+        self.try_cholesky().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Takes the lower triangle in the ranks 0 and 1 and halves the diagonal, keeping the other ranks.
+    pub fn try_lower_half(self) -> Result<Expression, SymbolicError> {
+        if let Expression::Constant(v) = &self {
+            return Ok(v.lower_half()?.into());
+        }
+
+        Ok(MatrixExpression::LowerHalf(self.into()).into())
+    }
+
+    pub fn lower_half(self) -> Expression {
+        self.try_lower_half().unwrap_or_else(|e| panic!("{}", e))
+    }
+}
+
+/// The lower triangle of the matrix with the halved diagonal.
+pub(crate) fn lower_half(m: &Matrix) -> Matrix {
+    let mut m = m.clone();
+    for j in 0..m.cols() {
+        m[(j, j)] *= 0.5;
+        for i in 0..j.min(m.rows()) {
+            m[(i, j)] = 0.0;
+        }
+    }
+
+    m
+}
+
+impl MatrixExpression {
+    /// dL = L Φ(L^-1 dA L^-T), where Φ takes the lower triangle and halves the diagonal.
+    pub(crate) fn diff_cholesky(
+        v: &Expression,
+        symbols: &[&str],
+        rank_offset: usize,
+    ) -> Vec<Expression> {
+        v._differential(symbols, rank_offset)
+            .into_iter()
+            .map(|d_v_d_symbol| {
+                if d_v_d_symbol == 0.0.into() {
+                    return d_v_d_symbol;
+                }
+                let l = v.clone().cholesky();
+                let l_inv = l.clone().inv();
+                let inner = l_inv
+                    .clone()
+                    .dot(d_v_d_symbol, &[[1, 0]])
+                    .dot(l_inv.t(), &[[1, 0]]);
+
+                l.dot(inner.lower_half(), &[[1, 0]])
+            })
+            .collect()
+    }
+
+    /// Φ is linear, so it is taken of the derivatives.
+    pub(crate) fn diff_lower_half(
+        v: &Expression,
+        symbols: &[&str],
+        rank_offset: usize,
+    ) -> Vec<Expression> {
+        v._differential(symbols, rank_offset)
+            .into_iter()
+            .map(|d_v_d_symbol| d_v_d_symbol.lower_half())
+            .collect()
+    }
+
+    pub(crate) fn tex_code_lower_half(v: &Expression, symbols: &HashMap<&str, &str>) -> String {
+        format!(
+            r"\Phi\left({}\right)",
+            v._tex_code(symbols, BracketsLevel::None)
+        )
+    }
+
+    pub(crate) fn tex_code_cholesky(v: &Expression, symbols: &HashMap<&str, &str>) -> String {
+        format!(
+            r"\operatorname{{chol}}\left({}\right)",
+            v._tex_code(symbols, BracketsLevel::None)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use opensrdk_linear_algebra::Matrix;
+
+    use crate::{new_variable_tensor, ConstantValue, Expression, Size};
+
+    fn spd() -> Matrix {
+        Matrix::from(3, vec![4.0, 1.0, 0.5, 1.0, 3.0, 0.2, 0.5, 0.2, 2.0]).unwrap()
+    }
+
+    #[test]
+    fn it_works() {
+        let a = new_variable_tensor("a".to_string(), vec![Size::Many, Size::Many]);
+        let mut hash = HashMap::new();
+        hash.insert("a", ConstantValue::Matrix(spd()));
+
+        let l = a.clone().cholesky().evaluate(&hash).unwrap().into_matrix();
+        let llt = l.dot(&l.t());
+        for i in 0..3 {
+            for j in 0..3 {
+                assert!((llt[(i, j)] - spd()[(i, j)]).abs() < 1e-12);
+                if i < j {
+                    assert_eq!(l[(i, j)], 0.0);
+                }
+            }
+        }
+
+        assert_eq!(Expression::from(4.0).cholesky(), Expression::from(2.0));
+        let tex_symbols = vec![("a", "A")].into_iter().collect();
+        assert_eq!(
+            a.cholesky().tex_code(&tex_symbols),
+            r"\operatorname{chol}\left({A}\right)"
+        );
+    }
+
+    #[test]
+    fn it_works2() {
+        let a = new_variable_tensor("a".to_string(), vec![Size::Fixed(3), Size::Fixed(3)]);
+        let x = Expression::from(vec![1.0, -2.0, 0.5]);
+        let expression = x
+            .clone()
+            .dot(a.clone().cholesky(), &[[0, 0]])
+            .dot(x, &[[1, 0]]);
+
+        let mut point = HashMap::new();
+        point.insert("a", ConstantValue::Matrix(spd()));
+        let d = Matrix::from(3, vec![0.3, -0.1, 0.2, -0.1, 0.5, 0.4, 0.2, 0.4, -0.2]).unwrap();
+        let mut direction = HashMap::new();
+        direction.insert("a", ConstantValue::Matrix(d.clone()));
+
+        // The decomposition only reads the lower triangle, so the direction is symmetric.
+        let h = 1e-6;
+        let moved = |s: f64| {
+            let mut moved = point.clone();
+            moved.insert("a", ConstantValue::Matrix(spd() + d.clone() * (s * h)));
+            expression.evaluate(&moved).unwrap().to_matrix().unwrap()[(0, 0)]
+        };
+        let expected = (moved(1.0) - moved(-1.0)) / (2.0 * h);
+
+        let dual = expression.jvp(&point, &direction).unwrap();
+        assert!((dual.tangent.to_matrix().unwrap()[(0, 0)] - expected).abs() < 1e-6);
+
+        let symbolic = expression.differential(&["a"])[0]
+            .evaluate(&point)
+            .unwrap()
+            .to_tensor()
+            .reduce_1dimension_rank();
+        let (_, gradient) = expression.value_and_grad(&point, &["a"]).unwrap();
+        let gradient = gradient[0].to_matrix().unwrap();
+        let mut contracted = 0.0;
+        for i in 0..3 {
+            for j in 0..3 {
+                assert!((symbolic[&[i, j]] - gradient[(i, j)]).abs() < 1e-10);
+                contracted += gradient[(i, j)] * d[(i, j)];
+            }
+        }
+        assert!((contracted - expected).abs() < 1e-6);
+    }
+
+    #[test]
+    fn it_works3() {
+        // The derivative is built without knowing the size of the matrix.
+        let a = new_variable_tensor("a".to_string(), vec![Size::Many, Size::Many]);
+        let x = Expression::from(vec![1.0, -2.0, 0.5]);
+        let expression = x
+            .clone()
+            .dot(a.clone().cholesky(), &[[0, 0]])
+            .dot(x, &[[1, 0]]);
+        let derivative = expression.differential(&["a"])[0].clone();
+        let tex_symbols = vec![("a", "A")].into_iter().collect();
+        assert!(derivative.tex_code(&tex_symbols).contains(r"\Phi"));
+
+        let mut point = HashMap::new();
+        point.insert("a", ConstantValue::Matrix(spd()));
+        let symbolic = derivative
+            .evaluate(&point)
+            .unwrap()
+            .to_tensor()
+            .reduce_1dimension_rank();
+        let (_, gradient) = expression.value_and_grad(&point, &["a"]).unwrap();
+        let gradient = gradient[0].to_matrix().unwrap();
+        for i in 0..3 {
+            for j in 0..3 {
+                assert!((symbolic[&[i, j]] - gradient[(i, j)]).abs() < 1e-10);
+            }
+        }
+    }
+}
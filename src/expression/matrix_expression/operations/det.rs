@@ -1,27 +1,60 @@
 use std::collections::HashMap;
 
-use opensrdk_linear_algebra::Matrix;
-
-use crate::{BracketsLevel, ConstantValue, Expression, MatrixExpression};
+use crate::{
+    matrix_expression::identities::{fixed_dimension, is_square, matrix_product, scaled_matrix},
+    BracketsLevel, Expression, MatrixExpression, SymbolicError,
+};
 
 impl Expression {
-    pub fn det(self) -> Expression {
-        if let Expression::Constant(v) = self {
-            let det = |v: Matrix| v.getrf().unwrap().0.trdet().into();
-            return match v {
-                ConstantValue::Scalar(v) => v.abs().into(),
-                ConstantValue::Tensor(v) => det(v.reduce_1dimension_rank().to_mat()),
-                ConstantValue::Matrix(v) => return det(v),
-            };
+    pub fn try_det(self) -> Result<Expression, SymbolicError> {
+        if let Expression::Constant(v) = &self {
+            return Ok(Expression::Constant(v.det()?));
+        }
+        if self.sizes().is_empty() {
+            return Ok(self);
         }
 
-        MatrixExpression::Det(self.into()).into()
+        MatrixExpression::check_square(&self, "take the determinant of")?;
+
+        if let Expression::Matrix(v) = &self {
+            match v.as_ref() {
+                // |A^T| = |A|
+                MatrixExpression::T(v) => return v.as_ref().clone().try_det(),
+                // |A^-1| = 1 / |A|
+                MatrixExpression::Inv(v) => {
+                    return Expression::from(1.0).try_div(v.as_ref().clone().try_det()?)
+                }
+                _ => {}
+            }
+        }
+        // |AB| = |A| |B| for square A and B
+        if let Some((a, b)) = matrix_product(&self) {
+            if is_square(&a) && is_square(&b) {
+                return a.try_det()?.try_mul(b.try_det()?);
+            }
+        }
+        // |cA| = c^n |A| for A of n rows
+        if let Some((c, a)) = scaled_matrix(&self) {
+            if let Some(n) = fixed_dimension(&a) {
+                return c.try_pow((n as f64).into())?.try_mul(a.try_det()?);
+            }
+        }
+
+        Ok(MatrixExpression::Det(self.into()).into())
+    }
+
+    pub fn det(self) -> Expression {
+        self.try_det().unwrap_or_else(|e| panic!("{}", e))
     }
 }
 
 impl MatrixExpression {
-    pub(crate) fn diff_det(v: &Expression, symbols: &[&str]) -> Vec<Expression> {
-        v.differential(symbols)
+    pub(crate) fn diff_det(
+        v: &Expression,
+        symbols: &[&str],
+        rank_offset: usize,
+    ) -> Vec<Expression> {
+        v._differential(symbols, rank_offset)
             .into_iter()
             .map(|d_v_d_symbol| {
                 let v_det = v.clone().det();
@@ -40,3 +73,22 @@ impl MatrixExpression {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use opensrdk_linear_algebra::Matrix;
+
+    use crate::{new_variable, Expression};
+
+    #[test]
+    fn it_works() {
+        // The determinant of a scalar is itself, as it evaluates.
+        assert_eq!(Expression::from(-2.0).det(), Expression::from(-2.0));
+        let x = new_variable("x".to_string());
+        assert_eq!(x.clone().det(), x);
+
+        // A row interchange of the LU decomposition flips the sign.
+        let a = Expression::from(Matrix::from(2, vec![0.0, 1.0, 2.0, 0.0]).unwrap());
+        assert_eq!(a.det(), Expression::from(-2.0));
+    }
+}
@@ -2,32 +2,59 @@ use std::collections::HashMap;
 
 use opensrdk_linear_algebra::Matrix;
 
-use crate::{BracketsLevel, ConstantValue, Expression, MatrixExpression};
+use crate::{BracketsLevel, ConstantValue, Expression, MatrixExpression, SymbolicError};
 
 impl Expression {
-    pub fn inv(self) -> Expression {
+    pub fn try_inv(self) -> Result<Expression, SymbolicError> {
         if let Expression::Constant(v) = self {
-            let inv = |v: Matrix| v.getrf().unwrap().getri().unwrap().into();
+            let inv =
+                |v: Matrix| -> Result<Expression, SymbolicError> { Ok(v.getrf()?.getri()?.into()) };
             return match v {
-                ConstantValue::Scalar(v) => v.abs().into(),
-                ConstantValue::Tensor(v) => inv(v.reduce_1dimension_rank().to_mat()),
-                ConstantValue::Matrix(v) => return inv(v),
+                ConstantValue::Scalar(v) => Ok((1.0 / v).into()),
+                ConstantValue::Tensor(_) => inv(v.to_matrix()?),
+                ConstantValue::Matrix(v) => inv(v),
             };
         }
+        if self.sizes().is_empty() {
+            return Expression::from(1.0).try_div(self);
+        }
+
+        MatrixExpression::check_square(&self, "invert")?;
+
+        if let Expression::Matrix(v) = &self {
+            match v.as_ref() {
+                // (A^-1)^-1 = A
+                MatrixExpression::Inv(v) => return Ok(v.as_ref().clone()),
+                // (A^T)^-1 = (A^-1)^T
+                MatrixExpression::T(v) => return v.as_ref().clone().try_inv()?.try_t(),
+                _ => {}
+            }
+        }
 
-        MatrixExpression::Inv(self.into()).into()
+        Ok(MatrixExpression::Inv(self.into()).into())
+    }
+
+    pub fn inv(self) -> Expression {
+        self.try_inv().unwrap_or_else(|e| panic!("{}", e))
     }
 }
 
 impl MatrixExpression {
-    pub(crate) fn diff_inv(v: &Expression, symbols: &[&str]) -> Vec<Expression> {
-        v.differential(symbols)
+    pub(crate) fn diff_inv(
+        v: &Expression,
+        symbols: &[&str],
+        rank_offset: usize,
+    ) -> Vec<Expression> {
+        let v_inv = v.clone().inv();
+
+        // d(A^-1) = -A^-1 dA A^-1
+        v._differential(symbols, rank_offset)
             .into_iter()
             .map(|d_v_d_symbol| {
-                let v_inv = v.clone().inv();
-                let d_v_inv_d_v = -v_inv.clone().dot(v_inv, &[[1, 0]]);
-
-                d_v_inv_d_v.dot(d_v_d_symbol, &[[0, 0], [1, 1]])
+                -v_inv
+                    .clone()
+                    .dot(d_v_d_symbol, &[[1, 0]])
+                    .dot(v_inv.clone(), &[[1, 0]])
             })
             .collect()
     }
@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+
+use crate::{BracketsLevel, Expression, MatrixExpression, SymbolicError};
+
+impl Expression {
+    pub fn try_log_det(self) -> Result<Expression, SymbolicError> {
+        if let Expression::Constant(v) = &self {
+            return Ok(v.log_det()?.into());
+        }
+        if self.sizes().is_empty() {
+            return Ok(self.ln());
+        }
+
+        MatrixExpression::check_square(&self, "take the log determinant of")?;
+
+        if let Expression::Matrix(v) = &self {
+            match v.as_ref() {
+                // ln|A^T| = ln|A|
+                MatrixExpression::T(v) => return v.as_ref().clone().try_log_det(),
+                // ln|A^-1| = -ln|A|
+                MatrixExpression::Inv(v) => return Ok(-v.as_ref().clone().try_log_det()?),
+                _ => {}
+            }
+        }
+
+        Ok(MatrixExpression::LogDet(self.into()).into())
+    }
+
+    pub fn log_det(self) -> Expression {
+        self.try_log_det().unwrap_or_else(|e| panic!("{}", e))
+    }
+}
+
+impl MatrixExpression {
+    pub(crate) fn diff_log_det(
+        v: &Expression,
+        symbols: &[&str],
+        rank_offset: usize,
+    ) -> Vec<Expression> {
+        v._differential(symbols, rank_offset)
+            .into_iter()
+            .map(|d_v_d_symbol| {
+                // d ln|A| = tr(A^-1 dA)
+                let d_log_det_d_v = v.clone().inv().t();
+
+                d_log_det_d_v.dot(d_v_d_symbol, &[[0, 0], [1, 1]])
+            })
+            .collect()
+    }
+
+    pub(crate) fn tex_code_log_det(v: &Expression, symbols: &HashMap<&str, &str>) -> String {
+        format!(
+            r"\ln\left\|{}\right\|",
+            v._tex_code(symbols, BracketsLevel::None)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use opensrdk_linear_algebra::Matrix;
+
+    use crate::{log_det_rules, new_variable, new_variable_tensor, ConstantValue, RuleSet, Size};
+
+    fn spd() -> Matrix {
+        Matrix::from(2, vec![2.0, 0.5, 0.5, 1.5]).unwrap()
+    }
+
+    #[test]
+    fn it_works() {
+        let a = new_variable_tensor("a".to_string(), vec![Size::Many, Size::Many]);
+        let mut hash = HashMap::new();
+        hash.insert("a", ConstantValue::Matrix(spd()));
+
+        let actual = a.clone().log_det().evaluate(&hash).unwrap().into_scalar();
+        assert!((actual - 2.75f64.ln()).abs() < 1e-12);
+
+        assert_eq!(a.clone().t().log_det(), a.clone().log_det());
+        assert_eq!(a.clone().inv().log_det(), -a.clone().log_det());
+        let x = new_variable("x".to_string());
+        assert_eq!(x.clone().log_det(), x.ln());
+
+        let tex_symbols = vec![("a", "A")].into_iter().collect();
+        assert_eq!(a.log_det().tex_code(&tex_symbols), r"\ln\left\|{A}\right\|");
+    }
+
+    #[test]
+    fn it_works2() {
+        let a = new_variable_tensor("a".to_string(), vec![Size::Many, Size::Many]);
+        let mut point = HashMap::new();
+        point.insert("a", ConstantValue::Matrix(spd()));
+        let d = Matrix::from(2, vec![0.3, -0.1, -0.1, 0.5]).unwrap();
+        let mut direction = HashMap::new();
+        direction.insert("a", ConstantValue::Matrix(d.clone()));
+
+        // d ln|A| = tr(A^-1 dA)
+        let expression = a.clone().log_det();
+        let inv = spd().getrf().unwrap().getri().unwrap();
+        let dual = expression.jvp(&point, &direction).unwrap();
+        assert!((dual.tangent.into_scalar() - inv.dot(&d).tr()).abs() < 1e-12);
+
+        let symbolic = expression.differential(&["a"])[0]
+            .evaluate(&point)
+            .unwrap()
+            .to_tensor()
+            .reduce_1dimension_rank();
+        let (_, gradient) = expression.value_and_grad(&point, &["a"]).unwrap();
+        let gradient = gradient[0].to_matrix().unwrap();
+        for i in 0..2 {
+            for j in 0..2 {
+                assert!((symbolic[&[i, j]] - inv[(j, i)]).abs() < 1e-12);
+                assert!((gradient[(i, j)] - inv[(j, i)]).abs() < 1e-12);
+            }
+        }
+
+        // The log likelihood of a Gaussian, -1/2 ln|Σ| - q/2.
+        let sigma = new_variable_tensor("sigma".to_string(), vec![Size::Many, Size::Many]);
+        let q = new_variable("q".to_string());
+        let log_likelihood = -0.5 * sigma.clone().det().ln() + -0.5 * q.clone();
+        let rules = RuleSet::from(log_det_rules());
+        assert_eq!(
+            rules.apply(&log_likelihood),
+            -0.5 * sigma.log_det() + -0.5 * q
+        );
+    }
+}
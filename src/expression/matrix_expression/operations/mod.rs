@@ -1,4 +1,7 @@
+pub mod cholesky;
 pub mod det;
 pub mod inv;
+pub mod log_det;
+pub mod solve;
 pub mod t;
 pub mod tr;
@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+
+use crate::{BracketsLevel, Expression, MatrixExpression, SymbolicError};
+
+impl Expression {
+    /// `self^-1 b`, for a positive definite `self`.
+    pub fn try_solve(self, b: Expression) -> Result<Expression, SymbolicError> {
+        if let (Expression::Constant(a), Expression::Constant(b)) = (&self, &b) {
+            return Ok(a.solve(b)?.into());
+        }
+        if self.sizes().is_empty() {
+            return b.try_div(self);
+        }
+
+        MatrixExpression::check_square(&self, "solve with")?;
+        let (a_sizes, b_sizes) = (self.sizes(), b.sizes());
+        if a_sizes.len() != 2
+            || !b_sizes
+                .first()
+                .is_some_and(|s| s.is_compatible(&a_sizes[1]))
+        {
+            return Err(SymbolicError::SizeMismatch {
+                operation: "solve".to_owned(),
+                lhs: a_sizes,
+                rhs: b_sizes,
+            });
+        }
+        if b == 0.0.into() {
+            return Ok(b);
+        }
+
+        Ok(MatrixExpression::Solve(self.into(), b.into()).into())
+    }
+
+    pub fn solve(self, b: Expression) -> Expression {
+        self.try_solve(b).unwrap_or_else(|e| panic!("{}", e))
+    }
+}
+
+impl MatrixExpression {
+    pub(crate) fn diff_solve(
+        a: &Expression,
+        b: &Expression,
+        symbols: &[&str],
+        rank_offset: usize,
+    ) -> Vec<Expression> {
+        // dX = A^-1 (dB - dA X)
+        let a_inv = a.clone().inv();
+        let x = a.clone().solve(b.clone());
+
+        a._differential(symbols, rank_offset)
+            .into_iter()
+            .zip(b._differential(symbols, rank_offset))
+            .map(|(d_a_d_symbol, d_b_d_symbol)| {
+                let d_a = -a_inv
+                    .clone()
+                    .dot(d_a_d_symbol.dot(x.clone(), &[[1, 0]]), &[[1, 0]]);
+                let d_b = a_inv.clone().dot(d_b_d_symbol, &[[1, 0]]);
+
+                d_a + d_b
+            })
+            .collect()
+    }
+
+    pub(crate) fn tex_code_solve(
+        a: &Expression,
+        b: &Expression,
+        symbols: &HashMap<&str, &str>,
+    ) -> String {
+        format!(
+            r"{{{}^{{-1}}}}{}",
+            a._tex_code(symbols, BracketsLevel::ForOperation),
+            b._tex_code(symbols, BracketsLevel::ForMul)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use opensrdk_linear_algebra::Matrix;
+
+    use crate::{new_variable, new_variable_tensor, ConstantValue, Size, SymbolicError};
+
+    fn spd() -> Matrix {
+        Matrix::from(2, vec![2.0, 0.5, 0.5, 1.5]).unwrap()
+    }
+
+    #[test]
+    fn it_works() {
+        let a = new_variable_tensor("a".to_string(), vec![Size::Many, Size::Many]);
+        let b = new_variable_tensor("b".to_string(), vec![Size::Many]);
+        let solved = a.clone().solve(b.clone());
+        assert_eq!(solved.sizes(), b.sizes());
+
+        let mut hash = HashMap::new();
+        hash.insert("a", ConstantValue::Matrix(spd()));
+        hash.insert("b", ConstantValue::Tensor(vec![1.0, -1.0].into()));
+        let expected = spd()
+            .getrf()
+            .unwrap()
+            .getri()
+            .unwrap()
+            .dot(&Matrix::from(2, vec![1.0, -1.0]).unwrap());
+        let actual = solved.evaluate(&hash).unwrap().to_matrix().unwrap();
+        for i in 0..2 {
+            assert!((actual[(i, 0)] - expected[(i, 0)]).abs() < 1e-12);
+        }
+
+        let x = new_variable("x".to_string());
+        let y = new_variable("y".to_string());
+        assert_eq!(x.clone().solve(y.clone()), y / x);
+
+        let c = new_variable_tensor("c".to_string(), vec![Size::Fixed(2), Size::Fixed(2)]);
+        let v = new_variable_tensor("v".to_string(), vec![Size::Fixed(3)]);
+        assert!(matches!(
+            c.try_solve(v),
+            Err(SymbolicError::SizeMismatch { .. })
+        ));
+
+        let tex_symbols = vec![("a", "A"), ("b", "b")].into_iter().collect();
+        assert_eq!(solved.tex_code(&tex_symbols), "{{A}^{-1}}{b}");
+    }
+
+    #[test]
+    fn it_works2() {
+        let a = new_variable_tensor("a".to_string(), vec![Size::Many, Size::Many]);
+        let x = new_variable_tensor("x".to_string(), vec![Size::Many]);
+        // x^T A^-1 x
+        let expression = x.clone().dot(a.clone().solve(x.clone()), &[[0, 0]]);
+
+        let mut point = HashMap::new();
+        point.insert("a", ConstantValue::Matrix(spd()));
+        point.insert("x", ConstantValue::Tensor(vec![1.0, 2.0].into()));
+        let d = Matrix::from(2, vec![0.3, -0.1, -0.1, 0.5]).unwrap();
+        let mut direction = HashMap::new();
+        direction.insert("a", ConstantValue::Matrix(d.clone()));
+        direction.insert("x", ConstantValue::Tensor(vec![0.0, 0.0].into()));
+
+        let h = 1e-6;
+        let moved = |s: f64| {
+            let mut moved = point.clone();
+            moved.insert("a", ConstantValue::Matrix(spd() + d.clone() * (s * h)));
+            expression.evaluate(&moved).unwrap().to_matrix().unwrap()[(0, 0)]
+        };
+        let expected = (moved(1.0) - moved(-1.0)) / (2.0 * h);
+
+        let dual = expression.jvp(&point, &direction).unwrap();
+        assert!((dual.tangent.to_matrix().unwrap()[(0, 0)] - expected).abs() < 1e-6);
+
+        let symbolic = expression.differential(&["a", "x"]);
+        let (_, gradient) = expression.value_and_grad(&point, &["a", "x"]).unwrap();
+
+        let symbolic_a = symbolic[0]
+            .evaluate(&point)
+            .unwrap()
+            .to_tensor()
+            .reduce_1dimension_rank();
+        let gradient_a = gradient[0].to_matrix().unwrap();
+        let mut contracted = 0.0;
+        for i in 0..2 {
+            for j in 0..2 {
+                assert!((symbolic_a[&[i, j]] - gradient_a[(i, j)]).abs() < 1e-10);
+                contracted += gradient_a[(i, j)] * d[(i, j)];
+            }
+        }
+        assert!((contracted - expected).abs() < 1e-6);
+
+        // 2 A^-1 x
+        let symbolic_x = symbolic[1]
+            .evaluate(&point)
+            .unwrap()
+            .to_tensor()
+            .reduce_1dimension_rank();
+        let gradient_x = gradient[1].to_tensor();
+        let expected_x = spd()
+            .getrf()
+            .unwrap()
+            .getri()
+            .unwrap()
+            .dot(&Matrix::from(2, vec![2.0, 4.0]).unwrap());
+        for i in 0..2 {
+            assert!((symbolic_x[&[i]] - expected_x[(i, 0)]).abs() < 1e-10);
+            assert!((gradient_x[&[i]] - expected_x[(i, 0)]).abs() < 1e-10);
+        }
+    }
+}
@@ -2,32 +2,57 @@ use std::collections::HashMap;
 
 use opensrdk_linear_algebra::Matrix;
 
-use crate::{BracketsLevel, ConstantValue, Expression, MatrixExpression, TensorExpression};
+use crate::{
+    matrix_expression::identities::matrix_product, BracketsLevel, ConstantValue, Expression,
+    MatrixExpression, SymbolicError, TensorExpression,
+};
 
 impl Expression {
-    pub fn t(self) -> Expression {
+    pub fn try_t(self) -> Result<Expression, SymbolicError> {
         if let Expression::Constant(v) = &self {
             let t = |v: &Matrix| v.t().into();
-            return match v {
-                ConstantValue::Scalar(v) => v.abs().into(),
-                ConstantValue::Tensor(v) => t(&v.reduce_1dimension_rank().to_mat()),
-                ConstantValue::Matrix(v) => return t(v),
-            };
+            return Ok(match v {
+                ConstantValue::Scalar(v) => (*v).into(),
+                ConstantValue::Tensor(_) => t(&v.to_matrix()?),
+                ConstantValue::Matrix(v) => t(v),
+            });
+        }
+        if self.sizes().is_empty() {
+            return Ok(self);
+        }
+        // (A^T)^T = A
+        if let Expression::Matrix(v) = &self {
+            if let MatrixExpression::T(v) = v.as_ref() {
+                return Ok(v.as_ref().clone());
+            }
+        }
+        // (AB)^T = B^T A^T
+        if let Some((a, b)) = matrix_product(&self) {
+            return b.try_t()?.try_dot(a.try_t()?, &[[1, 0]]);
         }
 
-        MatrixExpression::T(self.into()).into()
+        Ok(MatrixExpression::T(self.into()).into())
+    }
+
+    pub fn t(self) -> Expression {
+        self.try_t().unwrap_or_else(|e| panic!("{}", e))
     }
 }
 
 impl MatrixExpression {
-    pub(crate) fn diff_t(v: &Expression, symbols: &[&str]) -> Vec<Expression> {
-        let delta_01: Expression = TensorExpression::KroneckerDeltas(vec![[0, 1]]).into();
-        let tensor = delta_01
-            .clone()
-            .dot(v.clone(), &[[0, 1]])
-            .dot(delta_01, &[[0, 1]]);
+    pub(crate) fn diff_t(v: &Expression, symbols: &[&str], rank_offset: usize) -> Vec<Expression> {
+        // The two ranks of the matrix are swapped, and the ranks of the variables after them are left as they are.
+        let ids = ["$0".to_owned(), "$1".to_owned()];
+        let swapped = [(1, ids[0].clone()), (0, ids[1].clone())];
 
-        tensor.differential(symbols)
+        v._differential(symbols, rank_offset)
+            .into_iter()
+            .map(|d| {
+                let rank_combination = ids.iter().cloned().enumerate().collect();
+                TensorExpression::try_dot_product_keeping(vec![d], vec![rank_combination], &swapped)
+                    .unwrap_or_else(|e| panic!("{}", e))
+            })
+            .collect()
     }
 
     pub(crate) fn tex_code_t(v: &Expression, symbols: &HashMap<&str, &str>) -> String {
@@ -53,7 +78,7 @@ mod tests {
         let ea_t = ea.clone().t();
 
         let id2 = "g";
-        let diff_ea_t = MatrixExpression::diff_t(&ea, &[id]);
+        let diff_ea_t = MatrixExpression::diff_t(&ea, &[id], 0);
         let tex_symbols = vec![("x", "y")].into_iter().collect();
         println!("{:?}", diff_ea_t);
         let tex_ea_t = ea_t.tex_code(&tex_symbols);
@@ -0,0 +1,15 @@
+use crate::{Expression, MatrixExpression};
+
+impl MatrixExpression {
+    pub fn simplify(&self) -> Expression {
+        match self {
+            MatrixExpression::T(v) => v.simplify().t(),
+            MatrixExpression::Inv(v) => v.simplify().inv(),
+            MatrixExpression::Det(v) => v.simplify().det(),
+            MatrixExpression::Cholesky(v) => v.simplify().cholesky(),
+            MatrixExpression::Solve(a, b) => a.simplify().solve(b.simplify()),
+            MatrixExpression::LogDet(v) => v.simplify().log_det(),
+            MatrixExpression::LowerHalf(v) => v.simplify().lower_half(),
+        }
+    }
+}
@@ -1,16 +1,32 @@
-use crate::{MatrixExpression, Size};
+use crate::{Expression, MatrixExpression, Size, SymbolicError};
 
 impl MatrixExpression {
     pub fn sizes(&self) -> Vec<Size> {
         match self {
             MatrixExpression::T(v) => {
                 let sizes = v.sizes();
-                vec![sizes[1], sizes[0]]
+                vec![sizes[1].clone(), sizes[0].clone()]
             }
-            MatrixExpression::Inv(v) => v.sizes(),
-            MatrixExpression::Det(_) => vec![Size::One, Size::One],
+            MatrixExpression::Inv(v)
+            | MatrixExpression::Cholesky(v)
+            | MatrixExpression::LowerHalf(v) => v.sizes(),
+            MatrixExpression::Solve(_, b) => b.sizes(),
+            MatrixExpression::Det(_) | MatrixExpression::LogDet(_) => vec![Size::One, Size::One],
         }
     }
+
+    pub(crate) fn check_square(v: &Expression, operation: &str) -> Result<(), SymbolicError> {
+        let sizes = v.sizes();
+        if sizes.len() == 2 && !sizes[0].is_compatible(&sizes[1]) {
+            return Err(SymbolicError::SizeMismatch {
+                operation: operation.to_owned(),
+                lhs: vec![sizes[0].clone()],
+                rhs: vec![sizes[1].clone()],
+            });
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -31,7 +47,7 @@ mod tests {
         let ea_t = ea.clone().t();
         let size_t = ea_t.sizes();
 
-        assert_eq!(vec![size[1], size[0]], size_t);
+        assert_eq!(vec![size[1].clone(), size[0].clone()], size_t);
 
         let ea_inv = ea.clone().inv();
         let size_inv = ea_inv.sizes();
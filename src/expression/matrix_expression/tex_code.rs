@@ -12,6 +12,10 @@ impl MatrixExpression {
             MatrixExpression::T(v) => MatrixExpression::tex_code_t(v, variables),
             MatrixExpression::Inv(v) => MatrixExpression::tex_code_inv(v, variables),
             MatrixExpression::Det(v) => MatrixExpression::tex_code_det(v, variables),
+            MatrixExpression::Cholesky(v) => MatrixExpression::tex_code_cholesky(v, variables),
+            MatrixExpression::Solve(a, b) => MatrixExpression::tex_code_solve(a, b, variables),
+            MatrixExpression::LogDet(v) => MatrixExpression::tex_code_log_det(v, variables),
+            MatrixExpression::LowerHalf(v) => MatrixExpression::tex_code_lower_half(v, variables),
         }
     }
 
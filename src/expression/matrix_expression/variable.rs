@@ -1,5 +1,5 @@
-use crate::MatrixExpression;
-use std::collections::HashSet;
+use crate::{MatrixExpression, Size};
+use std::collections::{HashMap, HashSet};
 
 impl MatrixExpression {
     pub fn variable_ids(&self) -> HashSet<&str> {
@@ -7,6 +7,28 @@ impl MatrixExpression {
             MatrixExpression::T(v) => v.variable_ids(),
             MatrixExpression::Inv(v) => v.variable_ids(),
             MatrixExpression::Det(v) => v.variable_ids(),
+            MatrixExpression::Cholesky(v) => v.variable_ids(),
+            MatrixExpression::Solve(a, b) => a
+                .variable_ids()
+                .into_iter()
+                .chain(b.variable_ids())
+                .collect(),
+            MatrixExpression::LogDet(v) | MatrixExpression::LowerHalf(v) => v.variable_ids(),
+        }
+    }
+
+    pub fn variable_sizes(&self) -> HashMap<&str, &[Size]> {
+        match self {
+            MatrixExpression::T(v) => v.variable_sizes(),
+            MatrixExpression::Inv(v) => v.variable_sizes(),
+            MatrixExpression::Det(v) => v.variable_sizes(),
+            MatrixExpression::Cholesky(v) => v.variable_sizes(),
+            MatrixExpression::Solve(a, b) => a
+                .variable_sizes()
+                .into_iter()
+                .chain(b.variable_sizes())
+                .collect(),
+            MatrixExpression::LogDet(v) | MatrixExpression::LowerHalf(v) => v.variable_sizes(),
         }
     }
 }
@@ -1,19 +1,38 @@
 pub mod assign;
+pub mod check_gradient;
+pub mod children;
+pub mod common_subexpression;
 pub mod differential;
+pub mod egraph;
+pub mod evaluate;
+pub mod evaluate_interval;
+pub mod expression_ref;
+pub mod gradient;
+pub mod jvp;
 pub mod matrix_expression;
 pub mod operators;
 pub mod partial_variable;
+pub mod rewrite;
+pub mod simplify;
 pub mod size;
 pub mod tensor_expression;
 pub mod tex_code;
 pub mod transcendental_expression;
+pub mod value_and_grad;
 pub mod variable;
 
 pub use assign::*;
+pub use check_gradient::*;
+pub use common_subexpression::*;
 pub use differential::*;
+pub use egraph::*;
+pub use evaluate_interval::*;
+pub use expression_ref::*;
+pub use jvp::*;
 pub use matrix_expression::*;
 use opensrdk_linear_algebra::{sparse::SparseTensor, Matrix};
 pub use partial_variable::*;
+pub use rewrite::*;
 pub use size::*;
 pub use tensor_expression::*;
 pub use tex_code::*;
@@ -24,16 +43,16 @@ use crate::{ConstantValue, ExpressionArray};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Hash, Serialize, Deserialize)]
 pub enum Expression {
     Variable(String, Vec<Size>),
     Constant(ConstantValue),
     PartialVariable(ExpressionArray),
-    Add(Box<Expression>, Box<Expression>),
-    Sub(Box<Expression>, Box<Expression>),
-    Mul(Box<Expression>, Box<Expression>),
-    Div(Box<Expression>, Box<Expression>),
-    Neg(Box<Expression>),
+    Add(ExpressionRef, ExpressionRef),
+    Sub(ExpressionRef, ExpressionRef),
+    Mul(ExpressionRef, ExpressionRef),
+    Div(ExpressionRef, ExpressionRef),
+    Neg(ExpressionRef),
     Transcendental(Box<TranscendentalExpression>),
     Tensor(Box<TensorExpression>),
     Matrix(Box<MatrixExpression>),
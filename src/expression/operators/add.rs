@@ -1,28 +1,32 @@
-use crate::{BracketsLevel, ConstantValue, Expression};
+use crate::{BracketsLevel, ConstantValue, Expression, ExpressionRef, SymbolicError};
 use std::{collections::HashMap, ops::Add};
 
-impl Add<Expression> for Expression {
-    type Output = Self;
-
-    fn add(self, rhs: Expression) -> Self::Output {
-        if !self.is_same_size(&rhs) {
-            panic!("Cannot add expressions of different sizes");
-        }
+impl Expression {
+    pub fn try_add(self, rhs: Expression) -> Result<Expression, SymbolicError> {
+        self.check_same_size(&rhs, "add")?;
         if let Expression::Constant(vl) = &self {
             if vl == &ConstantValue::Scalar(0.0) {
-                return rhs;
+                return Ok(rhs);
             }
             if let Expression::Constant(vr) = rhs {
-                return vl.add(vr).into();
+                return Ok(vl.try_add(vr)?.into());
             }
         }
         if let Expression::Constant(vr) = &rhs {
             if vr == &ConstantValue::Scalar(0.0) {
-                return self;
+                return Ok(self);
             }
         }
 
-        Expression::Add(self.into(), rhs.into())
+        Ok(Expression::Add(self.into(), rhs.into()))
+    }
+}
+
+impl Add<Expression> for Expression {
+    type Output = Self;
+
+    fn add(self, rhs: Expression) -> Self::Output {
+        self.try_add(rhs).unwrap_or_else(|e| panic!("{}", e))
     }
 }
 
@@ -44,20 +48,21 @@ impl Add<Expression> for f64 {
 
 impl Expression {
     pub(crate) fn diff_add(
-        l: &Box<Expression>,
-        r: &Box<Expression>,
+        l: &ExpressionRef,
+        r: &ExpressionRef,
         variable_ids: &[&str],
+        rank_offset: usize,
     ) -> Vec<Expression> {
-        l.differential(variable_ids)
+        l._differential(variable_ids, rank_offset)
             .into_iter()
-            .zip(r.differential(variable_ids).into_iter())
+            .zip(r._differential(variable_ids, rank_offset).into_iter())
             .map(|(li, ri)| li + ri)
             .collect()
     }
 
     pub(crate) fn tex_code_add(
-        l: &Box<Expression>,
-        r: &Box<Expression>,
+        l: &ExpressionRef,
+        r: &ExpressionRef,
         symbols: &HashMap<&str, &str>,
         brackets_level: BracketsLevel,
     ) -> String {
@@ -82,7 +87,7 @@ mod tests {
 
     use opensrdk_linear_algebra::sparse::SparseTensor;
 
-    use crate::Expression;
+    use crate::{new_variable_tensor, Expression, Size, SymbolicError};
 
     #[test]
     fn it_works() {
@@ -130,4 +135,16 @@ mod tests {
         assert_eq!(ec, c);
         //println!("{:?}", ec);
     }
+
+    #[test]
+    fn it_works1() {
+        let x = new_variable_tensor("x".to_string(), vec![Size::Fixed(2)]);
+        let y = new_variable_tensor("y".to_string(), vec![Size::Fixed(3)]);
+
+        let result = x.clone().try_add(y);
+        assert!(matches!(result, Err(SymbolicError::SizeMismatch { .. })));
+
+        let result = x.clone().try_add(2.0.into()).unwrap();
+        assert_eq!(result, x + 2.0);
+    }
 }
@@ -1,23 +1,32 @@
-use crate::{BracketsLevel, ConstantValue, Expression};
+use crate::{BracketsLevel, ConstantValue, Expression, ExpressionRef, SymbolicError};
 use std::{collections::HashMap, ops::Div};
 
-impl Div<Expression> for Expression {
-    type Output = Self;
-
-    fn div(self, rhs: Expression) -> Self::Output {
-        if !self.is_same_size(&rhs) {
-            panic!("Cannot add expressions of different sizes");
+impl Expression {
+    pub fn try_div(self, rhs: Expression) -> Result<Expression, SymbolicError> {
+        self.check_same_size(&rhs, "divide")?;
+        if let Expression::Constant(vl) = &self {
+            if vl == &ConstantValue::Scalar(0.0) {
+                return Ok(0.0.into());
+            }
         }
         if let Expression::Constant(vr) = &rhs {
             if vr == &ConstantValue::Scalar(1.0) {
-                return self;
+                return Ok(self);
             }
             if let Expression::Constant(vl) = self {
-                return vl.div(vr).into();
+                return Ok(vl.try_div(vr)?.into());
             }
         }
 
-        Expression::Div(self.into(), rhs.into())
+        Ok(Expression::Div(self.into(), rhs.into()))
+    }
+}
+
+impl Div<Expression> for Expression {
+    type Output = Self;
+
+    fn div(self, rhs: Expression) -> Self::Output {
+        self.try_div(rhs).unwrap_or_else(|e| panic!("{}", e))
     }
 }
 
@@ -39,23 +48,24 @@ impl Div<Expression> for f64 {
 
 impl Expression {
     pub(crate) fn diff_div(
-        l: &Box<Expression>,
-        r: &Box<Expression>,
+        l: &ExpressionRef,
+        r: &ExpressionRef,
         variable_ids: &[&str],
+        rank_offset: usize,
     ) -> Vec<Expression> {
-        l.differential(variable_ids)
+        l._differential(variable_ids, rank_offset)
             .into_iter()
-            .zip(r.differential(variable_ids).into_iter())
+            .zip(r._differential(variable_ids, rank_offset).into_iter())
             .map(|(li, ri)| {
-                (li * r.as_ref().clone()
-                    - l.as_ref().clone() * ri / r.as_ref().clone().pow(2.0.into()))
+                (li * r.as_ref().clone() - l.as_ref().clone() * ri)
+                    / r.as_ref().clone().pow(2.0.into())
             })
             .collect()
     }
 
     pub(crate) fn tex_code_div(
-        l: &Box<Expression>,
-        r: &Box<Expression>,
+        l: &ExpressionRef,
+        r: &ExpressionRef,
         symbols: &HashMap<&str, &str>,
         brackets_level: BracketsLevel,
     ) -> String {
@@ -1,31 +1,30 @@
-use crate::{BracketsLevel, ConstantValue, Expression, TranscendentalExpression};
+use crate::{
+    BracketsLevel, ConstantValue, Expression, ExpressionRef, SymbolicError,
+    TranscendentalExpression,
+};
 use std::{collections::HashMap, ops::Mul};
 
-impl Mul<Expression> for Expression {
-    type Output = Self;
-
-    fn mul(self, rhs: Expression) -> Self::Output {
-        if !self.is_same_size(&rhs) {
-            panic!("Cannot add expressions of different sizes");
-        }
+impl Expression {
+    pub fn try_mul(self, rhs: Expression) -> Result<Expression, SymbolicError> {
+        self.check_same_size(&rhs, "multiply")?;
         // Merge constant
         if let Expression::Constant(vl) = &self {
             if vl == &ConstantValue::Scalar(0.0) {
-                return 0.0.into();
+                return Ok(0.0.into());
             }
             if vl == &ConstantValue::Scalar(1.0) {
-                return rhs;
+                return Ok(rhs);
             }
             if let Expression::Constant(vr) = rhs {
-                return vl.mul(vr).into();
+                return Ok(vl.try_mul(vr)?.into());
             }
         }
         if let Expression::Constant(vr) = &rhs {
             if vr == &ConstantValue::Scalar(0.0) {
-                return 0.0.into();
+                return Ok(0.0.into());
             }
             if vr == &ConstantValue::Scalar(1.0) {
-                return self;
+                return Ok(self);
             }
         }
         // Merge pow
@@ -34,13 +33,16 @@ impl Mul<Expression> for Expression {
                 if let Expression::Transcendental(vr) = &rhs {
                     if let TranscendentalExpression::Pow(vr, er) = vr.as_ref() {
                         if vl.as_ref() == vr.as_ref() {
-                            return vl.clone().pow(*el.clone() + *er.clone());
+                            return Ok(vl
+                                .as_ref()
+                                .clone()
+                                .pow(el.as_ref().clone() + er.as_ref().clone()));
                         }
                     }
                 }
                 if vl.as_ref() == &rhs {
                     let one: Expression = 1.0.into();
-                    return vl.clone().pow(*el.clone() + one);
+                    return Ok(vl.as_ref().clone().pow(el.as_ref().clone() + one));
                 }
             }
         }
@@ -48,12 +50,20 @@ impl Mul<Expression> for Expression {
             if let TranscendentalExpression::Pow(vr, er) = vr.as_ref() {
                 if vr.as_ref() == &self {
                     let one: Expression = 1.0.into();
-                    return vr.clone().pow(*er.clone() + one);
+                    return Ok(vr.as_ref().clone().pow(er.as_ref().clone() + one));
                 }
             }
         }
 
-        Expression::Mul(self.into(), rhs.into())
+        Ok(Expression::Mul(self.into(), rhs.into()))
+    }
+}
+
+impl Mul<Expression> for Expression {
+    type Output = Self;
+
+    fn mul(self, rhs: Expression) -> Self::Output {
+        self.try_mul(rhs).unwrap_or_else(|e| panic!("{}", e))
     }
 }
 
@@ -75,20 +85,21 @@ impl Mul<f64> for Expression {
 
 impl Expression {
     pub(crate) fn diff_mul(
-        l: &Box<Expression>,
-        r: &Box<Expression>,
+        l: &ExpressionRef,
+        r: &ExpressionRef,
         variable_ids: &[&str],
+        rank_offset: usize,
     ) -> Vec<Expression> {
-        l.differential(variable_ids)
+        l._differential(variable_ids, rank_offset)
             .into_iter()
-            .zip(r.differential(variable_ids).into_iter())
+            .zip(r._differential(variable_ids, rank_offset).into_iter())
             .map(|(li, ri)| li * r.as_ref().clone() + l.as_ref().clone() * ri)
             .collect()
     }
 
     pub(crate) fn tex_code_mul(
-        l: &Box<Expression>,
-        r: &Box<Expression>,
+        l: &ExpressionRef,
+        r: &ExpressionRef,
         symbols: &HashMap<&str, &str>,
         brackets_level: BracketsLevel,
     ) -> String {
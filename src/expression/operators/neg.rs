@@ -1,4 +1,4 @@
-use crate::{BracketsLevel, Expression};
+use crate::{BracketsLevel, Expression, ExpressionRef};
 use std::{collections::HashMap, ops::Neg};
 
 impl Neg for Expression {
@@ -10,7 +10,7 @@ impl Neg for Expression {
             return v.into();
         }
         if let Expression::Neg(v) = self {
-            return *v;
+            return v.as_ref().clone();
         }
 
         Expression::Neg(self.into())
@@ -18,14 +18,18 @@ impl Neg for Expression {
 }
 
 impl Expression {
-    pub(crate) fn diff_neg(v: &Box<Expression>, variable_ids: &[&str]) -> Vec<Expression> {
-        v.differential(variable_ids)
+    pub(crate) fn diff_neg(
+        v: &ExpressionRef,
+        variable_ids: &[&str],
+        rank_offset: usize,
+    ) -> Vec<Expression> {
+        v._differential(variable_ids, rank_offset)
             .into_iter()
             .map(|e| -e)
             .collect()
     }
 
-    pub(crate) fn tex_code_neg(v: &Box<Expression>, symbols: &HashMap<&str, &str>) -> String {
+    pub(crate) fn tex_code_neg(v: &ExpressionRef, symbols: &HashMap<&str, &str>) -> String {
         format!("{{-{}}}", v._tex_code(symbols, BracketsLevel::ForOperation))
     }
 }
@@ -1,27 +1,31 @@
-use crate::{BracketsLevel, ConstantValue, Expression};
+use crate::{BracketsLevel, ConstantValue, Expression, ExpressionRef, SymbolicError};
 use std::{collections::HashMap, ops::Sub};
 
-impl Sub<Expression> for Expression {
-    type Output = Self;
-
-    fn sub(self, rhs: Expression) -> Self::Output {
-        if !self.is_same_size(&rhs) {
-            panic!("Cannot add expressions of different sizes");
-        }
+impl Expression {
+    pub fn try_sub(self, rhs: Expression) -> Result<Expression, SymbolicError> {
+        self.check_same_size(&rhs, "subtract")?;
         if let Expression::Constant(vl) = &self {
             if vl == &ConstantValue::Scalar(0.0) {
-                return rhs;
+                return Ok(-rhs);
             }
             if let Expression::Constant(vr) = rhs {
-                return vl.sub(vr).into();
+                return Ok(vl.try_sub(vr)?.into());
             }
         }
         if let Expression::Constant(vr) = &rhs {
             if vr == &ConstantValue::Scalar(0.0) {
-                return self;
+                return Ok(self);
             }
         }
-        Self::Sub(self.into(), rhs.into())
+        Ok(Self::Sub(self.into(), rhs.into()))
+    }
+}
+
+impl Sub<Expression> for Expression {
+    type Output = Self;
+
+    fn sub(self, rhs: Expression) -> Self::Output {
+        self.try_sub(rhs).unwrap_or_else(|e| panic!("{}", e))
     }
 }
 
@@ -43,20 +47,21 @@ impl Sub<Expression> for f64 {
 
 impl Expression {
     pub(crate) fn diff_sub(
-        l: &Box<Expression>,
-        r: &Box<Expression>,
+        l: &ExpressionRef,
+        r: &ExpressionRef,
         variable_ids: &[&str],
+        rank_offset: usize,
     ) -> Vec<Expression> {
-        l.differential(variable_ids)
+        l._differential(variable_ids, rank_offset)
             .into_iter()
-            .zip(r.differential(variable_ids).into_iter())
+            .zip(r._differential(variable_ids, rank_offset).into_iter())
             .map(|(li, ri)| li - ri)
             .collect()
     }
 
     pub(crate) fn tex_code_sub(
-        l: &Box<Expression>,
-        r: &Box<Expression>,
+        l: &ExpressionRef,
+        r: &ExpressionRef,
         symbols: &HashMap<&str, &str>,
         brackets_level: BracketsLevel,
     ) -> String {
@@ -1,4 +1,6 @@
-use crate::{Expression, ExpressionArray};
+use crate::{ConstantValue, Expression, ExpressionArray, SymbolicError};
+use opensrdk_linear_algebra::{indices_cartesian_product, sparse::SparseTensor};
+use rayon::prelude::*;
 use std::collections::HashMap;
 
 pub fn new_partial_variable(v: ExpressionArray) -> Expression {
@@ -9,15 +11,38 @@ impl Expression {
     pub(crate) fn diff_partial_variable(
         v: &ExpressionArray,
         variable_ids: &[&str],
+        rank_offset: usize,
     ) -> Vec<Expression> {
         variable_ids
             .iter()
             .map(|&variable_id| {
                 new_partial_variable(ExpressionArray::from_factory(
                     v.sizes().to_vec(),
-                    |indices| v[indices].differential(&[variable_id])[0].clone(),
+                    |indices| v[indices]._differential(&[variable_id], rank_offset)[0].clone(),
                 ))
             })
             .collect()
     }
+
+    pub(crate) fn evaluate_partial_variable(
+        v: &ExpressionArray,
+        variables: &HashMap<&str, ConstantValue>,
+    ) -> Result<ConstantValue, SymbolicError> {
+        // The elements are independent of each other, so they are evaluated in parallel.
+        let elems = indices_cartesian_product(v.sizes())
+            .into_par_iter()
+            .map(|indices| match v[&indices]._evaluate(variables)? {
+                ConstantValue::Scalar(e) => Ok((indices, e)),
+                _ => Err(SymbolicError::NonScalarElement(indices)),
+            })
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .filter(|(_, e)| *e != 0.0)
+            .collect::<HashMap<_, _>>();
+
+        Ok(ConstantValue::Tensor(SparseTensor::from(
+            v.sizes().to_vec(),
+            elems,
+        )?))
+    }
 }
@@ -0,0 +1,5 @@
+pub mod pattern;
+pub mod rule_set;
+
+pub use pattern::*;
+pub use rule_set::*;
@@ -0,0 +1,158 @@
+use crate::{Expression, Size};
+use std::collections::{HashMap, HashSet};
+
+/// The prefix of the ids of the variables which are wildcards in patterns.
+const WILDCARD_PREFIX: &str = "?";
+
+/// A wildcard binding any subexpression, to be put in the expression of a `Pattern`.
+/// Its sizes only serve building the pattern with the operators, and do not restrict what it binds.
+pub fn new_wildcard(name: String) -> Expression {
+    new_wildcard_tensor(name, vec![])
+}
+
+pub fn new_wildcard_tensor(name: String, sizes: Vec<Size>) -> Expression {
+    Expression::Variable(format!("{}{}", WILDCARD_PREFIX, name), sizes)
+}
+
+pub(crate) fn wildcard_name(e: &Expression) -> Option<&str> {
+    match e {
+        Expression::Variable(id, _) => id.strip_prefix(WILDCARD_PREFIX),
+        _ => None,
+    }
+}
+
+/// A condition on the subexpressions a wildcard binds.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Constraint {
+    Sizes(Vec<Size>),
+    Constant,
+    Nonconstant,
+}
+
+impl Constraint {
+    pub fn is_satisfied(&self, e: &Expression) -> bool {
+        match self {
+            Constraint::Sizes(sizes) => &e.sizes() == sizes,
+            Constraint::Constant => matches!(e, Expression::Constant(_)),
+            Constraint::Nonconstant => !matches!(e, Expression::Constant(_)),
+        }
+    }
+}
+
+/// An expression with wildcards, which matches the expressions of the same structure.
+/// A wildcard appearing more than once only matches identical subexpressions.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pattern {
+    expression: Expression,
+    constraints: HashMap<String, Vec<Constraint>>,
+}
+
+impl From<Expression> for Pattern {
+    fn from(expression: Expression) -> Self {
+        Self::new(expression)
+    }
+}
+
+impl Pattern {
+    pub fn new(expression: Expression) -> Self {
+        Self {
+            expression,
+            constraints: HashMap::new(),
+        }
+    }
+
+    pub fn constrain(mut self, wildcard: &str, constraint: Constraint) -> Self {
+        self.constraints
+            .entry(wildcard.to_owned())
+            .or_default()
+            .push(constraint);
+        self
+    }
+
+    pub fn expression(&self) -> &Expression {
+        &self.expression
+    }
+
+    pub fn wildcards(&self) -> HashSet<&str> {
+        self.expression
+            .variable_ids()
+            .into_iter()
+            .filter_map(|id| id.strip_prefix(WILDCARD_PREFIX))
+            .collect()
+    }
+
+    /// Whether `wildcard` may bind `e`.
+    pub(crate) fn allows(&self, wildcard: &str, e: &Expression) -> bool {
+        self.constraints
+            .get(wildcard)
+            .map_or(true, |c| c.iter().all(|c| c.is_satisfied(e)))
+    }
+
+    /// The subexpressions the wildcards bind when `e` matches.
+    pub fn matches(&self, e: &Expression) -> Option<HashMap<String, Expression>> {
+        let mut bindings = HashMap::new();
+        if self.bind(&self.expression, e, &mut bindings) {
+            Some(bindings)
+        } else {
+            None
+        }
+    }
+
+    fn bind(
+        &self,
+        pattern: &Expression,
+        e: &Expression,
+        bindings: &mut HashMap<String, Expression>,
+    ) -> bool {
+        if let Some(name) = wildcard_name(pattern) {
+            if let Some(bound) = bindings.get(name) {
+                return bound == e;
+            }
+            let satisfied = self.allows(name, e);
+            if satisfied {
+                bindings.insert(name.to_owned(), e.clone());
+            }
+            return satisfied;
+        }
+
+        let (pattern_children, children) = (pattern.children(), e.children());
+        // The same operation is the one which rebuilds `e` on its own operands.
+        if pattern_children.len() != children.len() || &pattern.with_children(children.clone()) != e
+        {
+            return false;
+        }
+
+        pattern_children
+            .iter()
+            .zip(children.iter())
+            .all(|(p, c)| self.bind(p, c, bindings))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{new_variable, new_wildcard, Constraint, Expression, Pattern};
+
+    #[test]
+    fn it_works() {
+        let (a, b) = (new_wildcard("a".to_string()), new_wildcard("b".to_string()));
+        let x = new_variable("x".to_string());
+        let y = new_variable("y".to_string());
+
+        let pattern = Pattern::new(a.clone().sin() * b.clone() + a.clone());
+        let expression = (x.clone() * y.clone()).sin() * y.clone().exp() + x.clone() * y.clone();
+
+        let bindings = pattern.matches(&expression).unwrap();
+        assert_eq!(bindings["a"], x.clone() * y.clone());
+        assert_eq!(bindings["b"], y.clone().exp());
+
+        // The repeated wildcard binds different subexpressions.
+        let expression = x.clone().sin() * y.clone() + y.clone();
+        assert!(pattern.matches(&expression).is_none());
+
+        let pattern = Pattern::new(a.clone() * b.clone()).constrain("a", Constraint::Constant);
+        assert!(pattern.matches(&(2.0 * x.clone())).is_some());
+        assert!(pattern.matches(&(y.clone() * x.clone())).is_none());
+        assert!(pattern.matches(&Expression::from(2.0)).is_none());
+    }
+}
@@ -0,0 +1,259 @@
+use super::pattern::wildcard_name;
+use crate::{Expression, ExpressionRef, Pattern, SymbolicError};
+use std::collections::HashMap;
+
+const DEFAULT_MAX_PASSES: usize = 100;
+
+/// Rewrites the expressions matching `pattern` into `replacement`, where the wildcards are substituted by what they bind.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rule {
+    pub name: String,
+    pattern: Pattern,
+    replacement: Expression,
+}
+
+impl Rule {
+    pub fn try_new(
+        name: String,
+        pattern: Pattern,
+        replacement: Expression,
+    ) -> Result<Self, SymbolicError> {
+        let bound = pattern.wildcards();
+        let replacement_pattern = Pattern::new(replacement.clone());
+        if let Some(unbound) = replacement_pattern
+            .wildcards()
+            .into_iter()
+            .find(|w| !bound.contains(w))
+        {
+            return Err(SymbolicError::UnboundWildcard(unbound.to_owned()));
+        }
+
+        Ok(Self {
+            name,
+            pattern,
+            replacement,
+        })
+    }
+
+    pub fn new(name: String, pattern: Pattern, replacement: Expression) -> Self {
+        Self::try_new(name, pattern, replacement).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn pattern(&self) -> &Pattern {
+        &self.pattern
+    }
+
+    pub fn replacement(&self) -> &Expression {
+        &self.replacement
+    }
+
+    /// The rewritten `e`, or none when it does not match.
+    pub fn try_rewrite(&self, e: &Expression) -> Result<Option<Expression>, SymbolicError> {
+        match self.pattern.matches(e) {
+            Some(bindings) => Ok(Some(substitute(&self.replacement, &bindings)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+fn substitute(
+    e: &Expression,
+    bindings: &HashMap<String, Expression>,
+) -> Result<Expression, SymbolicError> {
+    if let Some(name) = wildcard_name(e) {
+        return Ok(bindings[name].clone());
+    }
+
+    let children = e.children();
+    if children.is_empty() {
+        return Ok(e.clone());
+    }
+    let children = children
+        .iter()
+        .map(|c| substitute(c, bindings))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    e.rebuilt(children)
+}
+
+/// Rules applied to every node from the leaves up, until none of them rewrites any more.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RuleSet {
+    rules: Vec<Rule>,
+    max_passes: usize,
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<Vec<Rule>> for RuleSet {
+    fn from(rules: Vec<Rule>) -> Self {
+        Self {
+            rules,
+            max_passes: DEFAULT_MAX_PASSES,
+        }
+    }
+}
+
+impl RuleSet {
+    pub fn new() -> Self {
+        vec![].into()
+    }
+
+    /// The rules are tried in the order they are pushed, and the first one matching a node rewrites it.
+    pub fn push(&mut self, rule: Rule) -> &mut Self {
+        self.rules.push(rule);
+        self
+    }
+
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    /// Limits the passes over the expression, which rules rewriting each other back and forth never stop.
+    pub fn with_max_passes(mut self, max_passes: usize) -> Self {
+        self.max_passes = max_passes;
+        self
+    }
+
+    pub fn try_apply(&self, expression: &Expression) -> Result<Expression, SymbolicError> {
+        let mut expression = expression.clone();
+        for _ in 0..self.max_passes {
+            let mut memo = HashMap::new();
+            let rewritten = self.pass(&expression, &mut memo)?;
+            if rewritten == expression {
+                return Ok(rewritten);
+            }
+            expression = rewritten;
+        }
+
+        Err(SymbolicError::RewriteNotConverged(self.max_passes))
+    }
+
+    pub fn apply(&self, expression: &Expression) -> Expression {
+        self.try_apply(expression)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn pass(
+        &self,
+        e: &Expression,
+        memo: &mut HashMap<usize, (ExpressionRef, Expression)>,
+    ) -> Result<Expression, SymbolicError> {
+        let node = ExpressionRef::from(e.clone());
+        if let Some((_, rewritten)) = memo.get(&node.id()) {
+            return Ok(rewritten.clone());
+        }
+
+        let children = e.children();
+        let rewritten_children = children
+            .iter()
+            .map(|c| self.pass(c, memo))
+            .collect::<Result<Vec<_>, _>>()?;
+        let e = if rewritten_children == children {
+            e.clone()
+        } else {
+            e.rebuilt(rewritten_children)?
+        };
+
+        let mut rewritten = e.clone();
+        for rule in self.rules.iter() {
+            if let Some(v) = rule.try_rewrite(&e)? {
+                rewritten = v;
+                break;
+            }
+        }
+
+        memo.insert(node.id(), (node, rewritten.clone()));
+        Ok(rewritten)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{
+        new_variable, new_wildcard, ConstantValue, Constraint, Expression, Pattern, Rule, RuleSet,
+        SymbolicError,
+    };
+
+    #[test]
+    fn it_works() {
+        let a = new_wildcard("a".to_string());
+        let x = new_variable("x".to_string());
+        let y = new_variable("y".to_string());
+
+        let mut rules = RuleSet::new();
+        rules.push(Rule::new(
+            "pythagorean".to_string(),
+            Pattern::new(a.clone().sin().pow(2.0.into()) + a.clone().cos().pow(2.0.into())),
+            1.0.into(),
+        ));
+        rules.push(Rule::new(
+            "sinh".to_string(),
+            Pattern::new((a.clone().exp() - (-a.clone()).exp()) / 2.0),
+            a.clone().sinh(),
+        ));
+
+        let xy = x.clone() * y.clone();
+        let expression = ((xy.clone().sin().pow(2.0.into()) + xy.clone().cos().pow(2.0.into()))
+            * x.clone())
+        .ln()
+            + (y.clone().exp() - (-y.clone()).exp()) / 2.0;
+
+        // The replacements go through the operators, so the logarithm of 1 folds away after the rewrite.
+        let rewritten = rules.apply(&expression);
+        assert_eq!(rewritten, x.clone().ln() + y.clone().sinh());
+
+        let mut hash = HashMap::new();
+        hash.insert("x", ConstantValue::Scalar(1.3));
+        hash.insert("y", ConstantValue::Scalar(-0.4));
+        let expected = expression.evaluate(&hash).unwrap().into_scalar();
+        let actual = rewritten.evaluate(&hash).unwrap().into_scalar();
+        assert!((expected - actual).abs() < 1e-12);
+    }
+
+    #[test]
+    fn it_works2() {
+        let (a, b) = (new_wildcard("a".to_string()), new_wildcard("b".to_string()));
+        let (c, d) = (new_wildcard("c".to_string()), new_wildcard("d".to_string()));
+        let x = new_variable("x".to_string());
+        let y = new_variable("y".to_string());
+
+        // Collects like terms with constant coefficients, bottom-up until nothing matches.
+        let rules = RuleSet::from(vec![Rule::new(
+            "collect".to_string(),
+            Pattern::new(c.clone() * a.clone() + d.clone() * a.clone())
+                .constrain("c", Constraint::Constant)
+                .constrain("d", Constraint::Constant),
+            (c.clone() + d.clone()) * a.clone(),
+        )]);
+        let expression = 2.0 * x.clone() + 3.0 * x.clone();
+        assert_eq!(rules.apply(&expression), 5.0 * x.clone());
+
+        let expression = y.clone() * x.clone() + 3.0 * x.clone();
+        assert_eq!(rules.apply(&expression), expression);
+
+        // A rule rewriting what it produces never converges.
+        let rules = RuleSet::from(vec![Rule::new(
+            "commute".to_string(),
+            Pattern::new(a.clone() * b.clone()),
+            b.clone() * a.clone(),
+        )])
+        .with_max_passes(10);
+        assert!(matches!(
+            rules.try_apply(&(x.clone() * y.clone())),
+            Err(SymbolicError::RewriteNotConverged(10))
+        ));
+
+        assert!(matches!(
+            Rule::try_new("unbound".to_string(), Pattern::new(a.clone()), b.clone()),
+            Err(SymbolicError::UnboundWildcard(_))
+        ));
+        assert_eq!(rules.apply(&Expression::from(1.0)), Expression::from(1.0));
+    }
+}
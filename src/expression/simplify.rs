@@ -0,0 +1,483 @@
+use crate::{
+    ConstantValue, Expression, ExpressionArray, ExpressionRef, Size, TensorExpression,
+    TranscendentalExpression,
+};
+use std::{
+    cmp::Ordering,
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    mem::discriminant,
+};
+
+/// The canonical order of terms and factors, and the identity used to collect them.
+/// Identical terms are interned into the same node, so they are collected by its id, and ordered by the hash of their structure.
+#[derive(Clone, PartialEq)]
+struct Key {
+    hash: u64,
+    node: ExpressionRef,
+}
+
+impl Eq for Key {}
+
+impl PartialOrd for Key {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Key {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.hash, self.node.id()).cmp(&(other.hash, other.node.id()))
+    }
+}
+
+/// Unlike `Hash`, which looks at the addresses of the interned operands, this hashes the operands by their structure, so it is the same in every run.
+fn structural_hash(e: &Expression) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    discriminant(e).hash(&mut hasher);
+    match e {
+        Expression::Variable(id, sizes) => (id, sizes).hash(&mut hasher),
+        Expression::Constant(v) => v.hash(&mut hasher),
+        Expression::PartialVariable(v) => v.sizes().hash(&mut hasher),
+        Expression::Transcendental(v) => {
+            discriminant(v.as_ref()).hash(&mut hasher);
+            if let TranscendentalExpression::Polygamma(n, _) = v.as_ref() {
+                n.hash(&mut hasher);
+            }
+        }
+        Expression::Tensor(v) => {
+            discriminant(v.as_ref()).hash(&mut hasher);
+            match v.as_ref() {
+                TensorExpression::KroneckerDeltas(rank_pairs) => rank_pairs.hash(&mut hasher),
+                TensorExpression::SizedKroneckerDeltas(rank_pairs) => rank_pairs.hash(&mut hasher),
+                TensorExpression::DotProduct {
+                    rank_combinations, ..
+                } => {
+                    for rank_combination in rank_combinations.iter() {
+                        let mut ids = rank_combination.iter().collect::<Vec<_>>();
+                        ids.sort();
+                        ids.hash(&mut hasher);
+                    }
+                }
+                TensorExpression::LogSumExp { rank, .. }
+                | TensorExpression::Softmax { rank, .. } => rank.hash(&mut hasher),
+                TensorExpression::DirectProduct(_) => {}
+            }
+        }
+        Expression::Matrix(v) => discriminant(v.as_ref()).hash(&mut hasher),
+        _ => {}
+    }
+    for child in e.children() {
+        structural_hash(&child).hash(&mut hasher);
+    }
+
+    hasher.finish()
+}
+
+fn key(e: &Expression) -> Key {
+    Key {
+        hash: structural_hash(e),
+        node: e.clone().into(),
+    }
+}
+
+fn is_scalar(e: &Expression) -> bool {
+    Size::effective_rank(&e.sizes()) == 0
+}
+
+fn scaled(e: Expression, coefficient: f64) -> Expression {
+    if coefficient == 1.0 {
+        return e;
+    }
+    if coefficient == -1.0 {
+        return -e;
+    }
+
+    Expression::from(coefficient) * e
+}
+
+/// A flattened sum of terms with their coefficients.
+struct Sum {
+    terms: Vec<(Key, Expression, f64)>,
+    constant: ConstantValue,
+}
+
+impl Sum {
+    fn new() -> Self {
+        Self {
+            terms: vec![],
+            constant: ConstantValue::Scalar(0.0),
+        }
+    }
+
+    fn add(&mut self, e: Expression, coefficient: f64) {
+        match e {
+            Expression::Add(l, r) => {
+                self.add(l.as_ref().clone(), coefficient);
+                self.add(r.as_ref().clone(), coefficient);
+            }
+            Expression::Sub(l, r) => {
+                self.add(l.as_ref().clone(), coefficient);
+                self.add(r.as_ref().clone(), -coefficient);
+            }
+            Expression::Neg(v) => self.add(v.as_ref().clone(), -coefficient),
+            Expression::Constant(v) => {
+                self.constant = self.constant.add(v.map(|v| v * coefficient));
+            }
+            Expression::Mul(l, r) => match l.as_ref() {
+                &Expression::Constant(ConstantValue::Scalar(k)) => {
+                    self.add(r.as_ref().clone(), coefficient * k)
+                }
+                _ => self.push(Expression::Mul(l, r), coefficient),
+            },
+            e => self.push(e, coefficient),
+        }
+    }
+
+    fn push(&mut self, e: Expression, coefficient: f64) {
+        let key = key(&e);
+        match self.terms.iter_mut().find(|(k, _, _)| k == &key) {
+            Some(term) => term.2 += coefficient,
+            None => self.terms.push((key, e, coefficient)),
+        }
+    }
+
+    fn into_expression(mut self) -> Expression {
+        let cancelled = self
+            .terms
+            .iter()
+            .filter(|(_, _, c)| *c == 0.0)
+            .map(|(_, e, _)| e.clone())
+            .max_by_key(|e| Size::effective_rank(&e.sizes()));
+        self.terms.retain(|(_, _, c)| *c != 0.0);
+        // The positive terms come first, so that the sum starts without a negation when it can.
+        self.terms
+            .sort_by(|a, b| (a.2 < 0.0, &a.0).cmp(&(b.2 < 0.0, &b.0)));
+
+        let constant = match self.constant {
+            ConstantValue::Scalar(0.0) => None,
+            ConstantValue::Scalar(c) => Some((1.0.into(), c)),
+            v => Some((v.into(), 1.0)),
+        };
+
+        let sum = self
+            .terms
+            .into_iter()
+            .map(|(_, e, c)| (e, c))
+            .chain(constant)
+            .fold(None, |acc: Option<Expression>, (e, c)| {
+                Some(match acc {
+                    None => scaled(e, c),
+                    Some(acc) if c < 0.0 => acc - scaled(e, -c),
+                    Some(acc) => acc + scaled(e, c),
+                })
+            });
+
+        // The terms which cancel stay as a zero multiple when the others do not have their sizes.
+        match (sum, cancelled) {
+            (None, Some(c)) if !is_scalar(&c) => {
+                Expression::Mul(Expression::from(0.0).into(), c.into())
+            }
+            (Some(sum), Some(c))
+                if Size::effective_rank(&sum.sizes()) < Size::effective_rank(&c.sizes()) =>
+            {
+                sum + Expression::Mul(Expression::from(0.0).into(), c.into())
+            }
+            (sum, _) => sum.unwrap_or_else(|| 0.0.into()),
+        }
+    }
+}
+
+/// A flattened product of factors with their exponents.
+struct Product {
+    factors: Vec<(Key, Expression, Expression)>,
+    coefficient: f64,
+}
+
+impl Product {
+    fn new() -> Self {
+        Self {
+            factors: vec![],
+            coefficient: 1.0,
+        }
+    }
+
+    fn mul(&mut self, e: Expression, exponent: f64) {
+        match e {
+            Expression::Mul(l, r) => {
+                self.mul(l.as_ref().clone(), exponent);
+                self.mul(r.as_ref().clone(), exponent);
+            }
+            Expression::Div(l, r) => {
+                self.mul(l.as_ref().clone(), exponent);
+                self.mul(r.as_ref().clone(), -exponent);
+            }
+            Expression::Neg(v) => {
+                self.coefficient = -self.coefficient;
+                self.mul(v.as_ref().clone(), exponent);
+            }
+            Expression::Constant(ConstantValue::Scalar(v)) => {
+                self.coefficient *= v.powf(exponent);
+            }
+            Expression::Transcendental(v) => match *v {
+                TranscendentalExpression::Pow(base, e) => {
+                    let e = if exponent < 0.0 {
+                        -e.as_ref().clone()
+                    } else {
+                        e.as_ref().clone()
+                    };
+                    self.push(base.as_ref().clone(), e);
+                }
+                v => self.push(v.into(), exponent.into()),
+            },
+            e => self.push(e, exponent.into()),
+        }
+    }
+
+    fn push(&mut self, base: Expression, exponent: Expression) {
+        let key = key(&base);
+        match self.factors.iter_mut().find(|(k, _, _)| k == &key) {
+            Some(factor) => factor.2 = factor.2.clone() + exponent,
+            None => self.factors.push((key, base, exponent)),
+        }
+    }
+
+    fn into_expression(mut self) -> Expression {
+        if self.coefficient == 0.0 && self.factors.iter().all(|(_, base, _)| is_scalar(base)) {
+            return 0.0.into();
+        }
+        // The bases which cancel are kept as a quotient of themselves when they are not scalars, as 1 would lose their sizes.
+        let cancelled = self
+            .factors
+            .iter()
+            .filter(|(_, base, e)| {
+                e == &Expression::Constant(ConstantValue::Scalar(0.0)) && !is_scalar(base)
+            })
+            .map(|(_, base, _)| base.clone())
+            .collect::<Vec<_>>();
+        self.factors
+            .retain(|(_, _, e)| e != &Expression::Constant(ConstantValue::Scalar(0.0)));
+        self.factors.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut numerator = None::<Expression>;
+        let mut denominator = None::<Expression>;
+        for (_, base, exponent) in self.factors {
+            let (part, factor) = match exponent {
+                Expression::Constant(ConstantValue::Scalar(e)) if e < 0.0 => {
+                    (&mut denominator, base.pow((-e).into()))
+                }
+                e => (&mut numerator, base.pow(e)),
+            };
+            *part = Some(match part.take() {
+                Some(p) => p * factor,
+                None => factor,
+            });
+        }
+        for base in cancelled {
+            for part in [&mut numerator, &mut denominator] {
+                *part = Some(match part.take() {
+                    Some(p) => p * base.clone(),
+                    None => base.clone(),
+                });
+            }
+        }
+
+        let numerator = numerator.unwrap_or_else(|| 1.0.into());
+        let body = match denominator {
+            Some(denominator) => numerator / denominator,
+            None => numerator,
+        };
+
+        if self.coefficient == 0.0 {
+            // A zero multiple keeps the sizes of the factors.
+            return Expression::Mul(Expression::from(0.0).into(), body.into());
+        }
+
+        scaled(body, self.coefficient)
+    }
+}
+
+impl Expression {
+    /// Rewrites the expression into a canonical form.
+    /// Sums and products are flattened and ordered, like terms and powers of the same base are collected, and neutral elements are removed.
+    pub fn simplify(&self) -> Expression {
+        match self {
+            Expression::Variable(_, _) | Expression::Constant(_) => self.clone(),
+            Expression::PartialVariable(v) => Expression::PartialVariable(
+                ExpressionArray::from_factory(v.sizes().to_vec(), |indices| v[indices].simplify()),
+            ),
+            Expression::Add(l, r) => {
+                let mut sum = Sum::new();
+                sum.add(l.simplify(), 1.0);
+                sum.add(r.simplify(), 1.0);
+                sum.into_expression()
+            }
+            Expression::Sub(l, r) => {
+                let mut sum = Sum::new();
+                sum.add(l.simplify(), 1.0);
+                sum.add(r.simplify(), -1.0);
+                sum.into_expression()
+            }
+            Expression::Neg(v) => {
+                let mut sum = Sum::new();
+                sum.add(v.simplify(), -1.0);
+                sum.into_expression()
+            }
+            Expression::Mul(l, r) => {
+                let mut product = Product::new();
+                product.mul(l.simplify(), 1.0);
+                product.mul(r.simplify(), 1.0);
+                product.into_expression()
+            }
+            Expression::Div(l, r) => {
+                let mut product = Product::new();
+                product.mul(l.simplify(), 1.0);
+                product.mul(r.simplify(), -1.0);
+                product.into_expression()
+            }
+            Expression::Transcendental(v) => v.simplify(),
+            Expression::Tensor(v) => v.simplify(),
+            Expression::Matrix(v) => v.simplify(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{
+        new_partial_variable, new_variable, new_variable_tensor, ConstantValue, Expression,
+        ExpressionArray, ExpressionRef, Size,
+    };
+
+    #[test]
+    fn it_works() {
+        let x = new_variable("x".to_string());
+        let y = new_variable("y".to_string());
+
+        let expression = Expression::Add(
+            Expression::Mul(x.clone().into(), Expression::from(1.0).into()).into(),
+            Expression::from(0.0).into(),
+        );
+        assert_eq!(expression.simplify(), x);
+
+        let expression = Expression::Add(x.clone().into(), (-y.clone()).into());
+        assert_eq!(expression.simplify(), x.clone() - y.clone());
+
+        let expression = x.clone() + (y.clone() + x.clone()) + 2.0 * x.clone() - y.clone();
+        assert_eq!(expression.simplify(), 4.0 * x.clone());
+
+        let expression = x.clone() * y.clone() * x.clone() / y.clone();
+        assert_eq!(expression.simplify(), x.clone().pow(2.0.into()));
+
+        let expression = (x.clone() / x.clone()) + 1.0;
+        assert_eq!(expression.simplify(), Expression::from(2.0));
+    }
+
+    #[test]
+    fn it_works2() {
+        let x = new_variable("x".to_string());
+        let y = new_variable("y".to_string());
+
+        // Terms are ordered regardless of how they were written.
+        let a = (y.clone() * 3.0 + x.clone() * 2.0) * x.clone().sin();
+        let b = x.clone().sin() * (2.0 * x.clone() + 3.0 * y.clone());
+        assert_eq!(a.simplify(), b.simplify());
+
+        // Derivatives come out without neutral elements.
+        let diff = x.clone().pow(3.0.into()).differential(&["x"])[0].simplify();
+        assert_eq!(diff, 3.0 * x.clone().pow(2.0.into()));
+
+        let symbols = vec![("x", "x")].into_iter().collect();
+        assert_eq!(
+            diff.tex_code(&symbols),
+            r"{\text{const.} \times {x}^\text{const.}}"
+        );
+    }
+
+    #[test]
+    fn it_works3() {
+        let x = new_variable("x".to_string());
+        let y = new_variable("y".to_string());
+        let expression = (x.clone() - y.clone()).pow(2.0.into()) / (2.0 * y.clone().exp())
+            - (-(x.clone() * y.clone()) + y.clone() * x.clone() * 3.0)
+            + x.clone().ln() / x.clone();
+        let simplified = expression.simplify();
+
+        assert_eq!(simplified.simplify(), simplified);
+
+        let mut hash = HashMap::new();
+        hash.insert("x", ConstantValue::Scalar(1.5));
+        hash.insert("y", ConstantValue::Scalar(-0.5));
+
+        let expected = expression.evaluate(&hash).unwrap().into_scalar();
+        let result = simplified.evaluate(&hash).unwrap().into_scalar();
+        assert!((result - expected).abs() < 1e-12);
+    }
+
+    #[test]
+    fn it_works4() {
+        let x = new_variable("x".to_string());
+        let y = new_variable("y".to_string());
+        let array = || {
+            new_partial_variable(ExpressionArray::from_factory(vec![4, 4], |indices| {
+                (indices[0] * 4 + indices[1]) as f64 * x.clone() + y.clone()
+            }))
+        };
+
+        // The arrays are built apart, so their elements are stored in different orders.
+        let expression = array() + array();
+        assert_eq!(expression.simplify(), 2.0 * array().simplify());
+    }
+
+    #[test]
+    fn it_works5() {
+        let x = new_variable("x".to_string());
+        let term = |k: usize| (k as f64 * x.clone()).exp();
+        let expected = format!(
+            "{:?}",
+            (1..20)
+                .map(term)
+                .fold(Expression::from(0.0), |acc, t| acc + t)
+                .simplify()
+        );
+
+        // The interner forgets the terms once it has grown enough, so they are interned again at other addresses, which does not change their order in the sum.
+        let filler = (0..100000)
+            .map(|k| ExpressionRef::from(Expression::from(k as f64 + 0.5)))
+            .collect::<Vec<_>>();
+        let again = (1..20)
+            .map(term)
+            .fold(Expression::from(0.0), |acc, t| acc + t);
+        assert_eq!(format!("{:?}", again.simplify()), expected);
+        drop(filler);
+    }
+
+    #[test]
+    fn it_works6() {
+        let x = new_variable_tensor("x".to_string(), vec![Size::Many]);
+        let y = new_variable("y".to_string());
+
+        // Tensors which cancel keep their sizes, while scalars cancel to constants.
+        let difference = (x.clone() - x.clone()).simplify();
+        assert_eq!(difference.sizes(), vec![Size::Many]);
+        assert_eq!(difference.simplify(), difference);
+        let quotient = (x.clone() / x.clone()).simplify();
+        assert_eq!(quotient.sizes(), vec![Size::Many]);
+        assert_eq!(quotient.simplify(), quotient);
+        assert_eq!((y.clone() - y.clone()).simplify(), Expression::from(0.0));
+        assert_eq!((y.clone() / y.clone()).simplify(), Expression::from(1.0));
+        assert_eq!(
+            (y.clone() + x.clone() - x.clone()).simplify().sizes(),
+            vec![Size::Many]
+        );
+
+        let mut hash = HashMap::new();
+        hash.insert("x", ConstantValue::Tensor(vec![2.0, -3.0].into()));
+        let zeros = difference.evaluate(&hash).unwrap().to_tensor();
+        assert_eq!((zeros[&[0]], zeros[&[1]]), (0.0, 0.0));
+        let ones = quotient.evaluate(&hash).unwrap().to_tensor();
+        assert_eq!((ones[&[0]], ones[&[1]]), (1.0, 1.0));
+    }
+}
@@ -1,22 +1,209 @@
-use crate::Expression;
+use crate::{ConstantValue, Expression, ExpressionRef, SymbolicError};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
-#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Size {
     One,
     Many,
+    Fixed(usize),
+    Dim(String),
+}
+
+impl From<usize> for Size {
+    fn from(size: usize) -> Self {
+        if size == 1 {
+            Size::One
+        } else {
+            Size::Fixed(size)
+        }
+    }
+}
+
+impl From<&str> for Size {
+    fn from(dim: &str) -> Self {
+        Size::Dim(dim.to_owned())
+    }
+}
+
+impl Size {
+    pub fn is_one(&self) -> bool {
+        matches!(self, Size::One | Size::Fixed(1))
+    }
+
+    /// Length of the rank, if it is known.
+    pub fn fixed_len(&self) -> Option<usize> {
+        match self {
+            Size::One => Some(1),
+            Size::Fixed(len) => Some(*len),
+            _ => None,
+        }
+    }
+
+    pub fn is_compatible(&self, other: &Size) -> bool {
+        self.unify(other).is_some()
+    }
+
+    /// Returns the most specific size satisfying both `self` and `other`, or `None` if they conflict.
+    /// Ranks of size one are broadcast.
+    pub fn unify(&self, other: &Size) -> Option<Size> {
+        match (self, other) {
+            (l, r) if l.is_one() => Some(r.clone()),
+            (l, r) if r.is_one() => Some(l.clone()),
+            (Size::Dim(l), Size::Dim(r)) => (l == r).then_some(self.clone()),
+            (Size::Dim(_), Size::Many) => Some(self.clone()),
+            (Size::Many, Size::Dim(_)) => Some(other.clone()),
+            (Size::Dim(_), _) => Some(other.clone()),
+            (_, Size::Dim(_)) => Some(self.clone()),
+            (Size::Many, _) => Some(other.clone()),
+            (_, Size::Many) => Some(self.clone()),
+            (l, r) => (l == r).then_some(self.clone()),
+        }
+    }
+
+    /// Unifies the sizes as `unify` does, binding each `Dim` met with a fixed size to its length in `dims`.
+    /// A `Dim` already bound to another length conflicts.
+    pub fn unify_binding(
+        &self,
+        other: &Size,
+        dims: &mut HashMap<String, usize>,
+    ) -> Result<Option<Size>, SymbolicError> {
+        if self.is_one() || other.is_one() {
+            return Ok(self.unify(other));
+        }
+
+        match (self, other) {
+            (Size::Dim(_), Size::Fixed(len)) | (Size::Fixed(len), Size::Dim(_)) => {
+                self.bind(*len, dims)?;
+                other.bind(*len, dims)?;
+                Ok(Some(Size::Fixed(*len)))
+            }
+            _ => Ok(self.unify(other)),
+        }
+    }
+
+    pub fn unify_sizes(lhs: &[Size], rhs: &[Size]) -> Option<Vec<Size>> {
+        if lhs.is_empty() {
+            return Some(rhs.to_vec());
+        }
+        if rhs.is_empty() {
+            return Some(lhs.to_vec());
+        }
+
+        // Missing trailing ranks are treated as ranks of size one.
+        let one = Size::One;
+        (0..lhs.len().max(rhs.len()))
+            .map(|rank| {
+                lhs.get(rank)
+                    .unwrap_or(&one)
+                    .unify(rhs.get(rank).unwrap_or(&one))
+            })
+            .collect()
+    }
+
+    /// Number of ranks up to the last rank whose size is not one.
+    pub fn effective_rank(sizes: &[Size]) -> usize {
+        sizes.iter().rposition(|s| !s.is_one()).map_or(0, |r| r + 1)
+    }
+
+    /// Size of the rank obtained by the direct product of ranks of sizes `self` and `other`.
+    pub fn product(&self, other: &Size) -> Size {
+        match (self, other) {
+            (l, r) if l.is_one() => r.clone(),
+            (l, r) if r.is_one() => l.clone(),
+            (Size::Fixed(l), Size::Fixed(r)) => Size::Fixed(l * r),
+            _ => Size::Many,
+        }
+    }
+
+    /// Checks that a rank of length `len` satisfies `self`, binding `Dim` to `len` on first use.
+    pub fn bind(
+        &self,
+        len: usize,
+        dims: &mut HashMap<String, usize>,
+    ) -> Result<bool, SymbolicError> {
+        Ok(match self {
+            Size::One => len <= 1,
+            Size::Many => len > 1,
+            Size::Fixed(size) => len == *size,
+            Size::Dim(dim) => match dims.get(dim) {
+                Some(&bound) if bound != len => {
+                    return Err(SymbolicError::DimensionConflict {
+                        dim: dim.clone(),
+                        bound,
+                        actual: len,
+                    })
+                }
+                Some(_) => true,
+                None => {
+                    dims.insert(dim.clone(), len);
+                    true
+                }
+            },
+        })
+    }
+
+    pub(crate) fn bind_variable(
+        id: &str,
+        sizes: &[Size],
+        actual: &[usize],
+        dims: &mut HashMap<String, usize>,
+    ) -> Result<(), SymbolicError> {
+        let mut matched = sizes.len() == actual.len();
+        for (size, &len) in sizes.iter().zip(actual.iter()) {
+            matched &= size.bind(len, dims)?;
+        }
+
+        if !matched {
+            return Err(SymbolicError::VariableSizeMismatch {
+                id: id.to_owned(),
+                expected: sizes.to_vec(),
+                actual: actual.to_vec(),
+            });
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn bind_dims(
+        variable_sizes: HashMap<&str, &[Size]>,
+        variables: &HashMap<&str, ConstantValue>,
+    ) -> Result<HashMap<String, usize>, SymbolicError> {
+        let mut variable_sizes = variable_sizes.into_iter().collect::<Vec<_>>();
+        variable_sizes.sort_by(|a, b| a.0.cmp(b.0));
+
+        let mut dims = HashMap::new();
+        for (id, sizes) in variable_sizes {
+            if let Some(v) = variables.get(id) {
+                Size::bind_variable(id, sizes, &v.sizes(), &mut dims)?;
+            }
+        }
+
+        Ok(dims)
+    }
+
+    /// Replaces each `Dim` bound in `dims` with its length.
+    pub fn substitute_dims(sizes: &[Size], dims: &HashMap<String, usize>) -> Vec<Size> {
+        sizes
+            .iter()
+            .map(|size| match size {
+                Size::Dim(dim) => dims.get(dim).map_or_else(|| size.clone(), |&d| d.into()),
+                _ => size.clone(),
+            })
+            .collect()
+    }
 }
 
 impl Expression {
     pub fn sizes(&self) -> Vec<Size> {
         match self {
             Expression::Variable(_, sizes) => sizes.clone(),
-            Expression::Constant(v) => v.sizes().into_abstract_size(),
-            Expression::PartialVariable(v) => v.sizes().into_abstract_size(),
-            Expression::Add(l, _) => l.sizes(),
-            Expression::Sub(l, _) => l.sizes(),
-            Expression::Mul(l, _) => l.sizes(),
-            Expression::Div(l, _) => l.sizes(),
+            Expression::Constant(v) => v.sizes().into_fixed_size(),
+            Expression::PartialVariable(v) => v.sizes().into_fixed_size(),
+            Expression::Add(l, r) => Expression::size_elementwise(l, r),
+            Expression::Sub(l, r) => Expression::size_elementwise(l, r),
+            Expression::Mul(l, r) => Expression::size_elementwise(l, r),
+            Expression::Div(l, r) => Expression::size_elementwise(l, r),
             Expression::Neg(v) => v.sizes(),
             Expression::Transcendental(v) => v.sizes(),
             Expression::Tensor(v) => v.sizes(),
@@ -24,24 +211,49 @@ impl Expression {
         }
     }
 
+    pub(crate) fn size_elementwise(l: &ExpressionRef, r: &ExpressionRef) -> Vec<Size> {
+        let sl = l.sizes();
+        let sr = r.sizes();
+
+        Size::unify_sizes(&sl, &sr).unwrap_or(sl)
+    }
+
     pub fn is_same_size(&self, other: &Expression) -> bool {
-        let sl = self.sizes();
-        let sr = other.sizes();
+        Size::unify_sizes(&self.sizes(), &other.sizes()).is_some()
+    }
 
-        if sl.len() == 0 || sr.len() == 0 {
-            return true;
+    pub(crate) fn check_same_size(
+        &self,
+        other: &Expression,
+        operation: &str,
+    ) -> Result<(), SymbolicError> {
+        if self.is_same_size(other) {
+            return Ok(());
         }
 
-        sl == sr
+        Err(SymbolicError::SizeMismatch {
+            operation: operation.to_owned(),
+            lhs: self.sizes(),
+            rhs: other.sizes(),
+        })
     }
 
     pub fn not_1dimension_ranks(&self) -> usize {
-        self.sizes().iter().filter(|&d| *d != Size::One).count()
+        self.sizes().iter().filter(|&d| !d.is_one()).count()
+    }
+
+    /// Binds every `Dim` of the variables assigned in `variables`, checking that all variables agree.
+    pub fn bind_dims(
+        &self,
+        variables: &HashMap<&str, ConstantValue>,
+    ) -> Result<HashMap<String, usize>, SymbolicError> {
+        Size::bind_dims(self.variable_sizes(), variables)
     }
 }
 
 pub trait AbstractSize {
     fn into_abstract_size(&self) -> Vec<Size>;
+    fn into_fixed_size(&self) -> Vec<Size>;
 }
 
 impl AbstractSize for [usize] {
@@ -50,6 +262,10 @@ impl AbstractSize for [usize] {
             .map(|&size| if size > 1 { Size::Many } else { Size::One })
             .collect()
     }
+
+    fn into_fixed_size(&self) -> Vec<Size> {
+        self.iter().map(|&size| size.into()).collect()
+    }
 }
 
 #[cfg(test)]
@@ -61,7 +277,10 @@ mod tests {
 
     use opensrdk_linear_algebra::{sparse::SparseTensor, Matrix, Tensor};
 
-    use crate::{new_variable, AbstractSize, Expression, Size};
+    use crate::{
+        expression::tensor_expression::operations::dot::DotProduct, new_variable,
+        new_variable_tensor, AbstractSize, ConstantValue, Expression, Size, SymbolicError,
+    };
 
     #[test]
     fn it_works1() {
@@ -83,8 +302,9 @@ mod tests {
         let sc = ec.sizes();
 
         assert_eq!(vec![Size::Many; 0], sa);
-        assert_eq!(vec![Size::Many; 1], sb);
-        assert_eq!(vec![Size::Many; 8], sc);
+        assert_eq!(vec![Size::Fixed(8); 1], sb);
+        assert_eq!(vec![Size::Fixed(6); 8], sc);
+        assert_eq!(vec![Size::Many; 8], [6usize; 8].into_abstract_size());
     }
 
     #[test]
@@ -119,9 +339,11 @@ mod tests {
 
         let result1 = &c1.is_same_size(&c2);
         let result2 = &c1.is_same_size(&c3);
+        let result3 = &c1.is_same_size(&new_variable_tensor("x".to_string(), vec![Size::Many; 8]));
 
-        assert_eq!(result1, &true);
+        assert_eq!(result1, &false);
         assert_eq!(result2, &false);
+        assert_eq!(result3, &true);
     }
 
     #[test]
@@ -137,4 +359,93 @@ mod tests {
 
         assert_eq!(rank, 8usize);
     }
+
+    #[test]
+    fn it_works5() {
+        let a = new_variable_tensor("a".to_string(), vec![Size::Fixed(3), Size::Fixed(4)]);
+        let b = new_variable_tensor("b".to_string(), vec![5usize, 5]);
+
+        assert!(matches!(
+            a.clone().try_dot(b, &[[1, 0]]),
+            Err(SymbolicError::SizeMismatch { .. })
+        ));
+
+        let x = new_variable_tensor("x".to_string(), vec![4usize]);
+        assert_eq!(a.dot(x, &[[1, 0]]).sizes(), vec![Size::Fixed(3), Size::One]);
+    }
+
+    #[test]
+    fn it_works6() {
+        let x = new_variable_tensor("x".to_string(), vec!["n"]);
+        let a = new_variable_tensor("a".to_string(), vec!["n", "n"]);
+        let expression = x.clone().dot(a, &[[0, 0]]);
+
+        let mut hash = HashMap::new();
+        hash.insert("x", ConstantValue::Tensor(vec![1.0, 2.0].into()));
+
+        let assigned = expression.clone().try_assign(&hash).unwrap();
+        assert_eq!(
+            assigned.variable_sizes()["a"],
+            &[Size::Fixed(2), Size::Fixed(2)]
+        );
+
+        hash.insert(
+            "a",
+            ConstantValue::Matrix(Matrix::from(3, vec![1.0; 9]).unwrap()),
+        );
+        assert!(matches!(
+            expression.evaluate(&hash),
+            Err(SymbolicError::DimensionConflict { .. })
+        ));
+    }
+
+    #[test]
+    fn it_works7() {
+        let mut dims = HashMap::new();
+        assert_eq!(
+            Size::from("n")
+                .unify_binding(&Size::Fixed(3), &mut dims)
+                .unwrap(),
+            Some(Size::Fixed(3))
+        );
+        assert_eq!(dims["n"], 3);
+        assert!(matches!(
+            Size::Fixed(4).unify_binding(&"n".into(), &mut dims),
+            Err(SymbolicError::DimensionConflict { .. })
+        ));
+
+        // n is bound to 3 by the first contraction, and meets 4 in the second.
+        let x = new_variable_tensor("x".to_string(), vec!["n"]);
+        let y = new_variable_tensor("y".to_string(), vec!["n"]);
+        let combination = |id: &str| {
+            vec![(0, id.to_owned())]
+                .into_iter()
+                .collect::<HashMap<_, _>>()
+        };
+        let result = vec![
+            x,
+            Expression::from(vec![1.0; 3]),
+            y,
+            Expression::from(vec![1.0; 4]),
+        ]
+        .into_iter()
+        .try_dot_product(&[
+            combination("p"),
+            combination("p"),
+            combination("q"),
+            combination("q"),
+        ]);
+        assert!(matches!(
+            result,
+            Err(SymbolicError::DimensionConflict { .. })
+        ));
+
+        // The free ranks aligned at rank 0 differ.
+        let a = new_variable_tensor("a".to_string(), vec![3usize, 3]);
+        let b = new_variable_tensor("b".to_string(), vec![4usize, 3]);
+        assert!(matches!(
+            a.try_dot(b, &[[1, 1]]),
+            Err(SymbolicError::SizeMismatch { .. })
+        ));
+    }
 }
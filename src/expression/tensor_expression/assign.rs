@@ -1,22 +1,57 @@
 use super::operations::{DirectProduct, DotProduct};
-use crate::{ConstantValue, Expression, TensorExpression};
+use crate::{ConstantValue, Expression, Size, SymbolicError, TensorExpression};
 use std::collections::HashMap;
 
 impl TensorExpression {
-    pub fn assign(self, variables: &HashMap<&str, ConstantValue>) -> Expression {
+    pub fn try_assign(
+        self,
+        variables: &HashMap<&str, ConstantValue>,
+    ) -> Result<Expression, SymbolicError> {
+        let dims = Size::bind_dims(self.variable_sizes(), variables)?;
+        self._assign(variables, &dims)
+    }
+
+    pub(crate) fn _assign(
+        self,
+        variables: &HashMap<&str, ConstantValue>,
+        dims: &HashMap<String, usize>,
+    ) -> Result<Expression, SymbolicError> {
         match self {
-            TensorExpression::KroneckerDeltas(_) => self.into(),
+            TensorExpression::KroneckerDeltas(_) => Ok(self.into()),
+            TensorExpression::SizedKroneckerDeltas(rank_pairs) => {
+                let (pairs, sizes): (Vec<_>, Vec<_>) = rank_pairs.into_iter().unzip();
+                let sizes = Size::substitute_dims(&sizes, dims);
+                Ok(
+                    TensorExpression::kronecker_deltas(pairs.into_iter().zip(sizes).collect())
+                        .into(),
+                )
+            }
             TensorExpression::DotProduct {
                 terms,
                 rank_combinations,
             } => terms
                 .into_iter()
-                .map(|t| t.assign(variables))
-                .dot_product(&rank_combinations),
-            TensorExpression::DirectProduct(terms) => terms
+                .map(|t| t._assign(variables, dims))
+                .collect::<Result<Vec<_>, SymbolicError>>()?
+                .into_iter()
+                .try_dot_product(&rank_combinations),
+            TensorExpression::DirectProduct(terms) => Ok(terms
                 .into_iter()
-                .map(|t| t.assign(variables))
-                .direct_product(),
+                .map(|t| t._assign(variables, dims))
+                .collect::<Result<Vec<_>, SymbolicError>>()?
+                .into_iter()
+                .direct_product()),
+            TensorExpression::LogSumExp { arg, rank } => {
+                arg._assign(variables, dims)?.try_log_sum_exp(rank)
+            }
+            TensorExpression::Softmax { arg, rank } => {
+                arg._assign(variables, dims)?.try_softmax(rank)
+            }
         }
     }
+
+    pub fn assign(self, variables: &HashMap<&str, ConstantValue>) -> Expression {
+        self.try_assign(variables)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
 }
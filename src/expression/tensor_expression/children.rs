@@ -0,0 +1,59 @@
+use super::operations::{DirectProduct, DotProduct};
+use crate::{Expression, TensorExpression};
+
+impl TensorExpression {
+    pub(crate) fn children(&self) -> Vec<Expression> {
+        match self {
+            TensorExpression::KroneckerDeltas(_) | TensorExpression::SizedKroneckerDeltas(_) => {
+                vec![]
+            }
+            TensorExpression::DotProduct {
+                terms,
+                rank_combinations: _,
+            } => terms.clone(),
+            TensorExpression::DirectProduct(terms) => terms.clone(),
+            TensorExpression::LogSumExp { arg, rank: _ } => vec![arg.clone()],
+            TensorExpression::Softmax { arg, rank: _ } => vec![arg.clone()],
+        }
+    }
+
+    pub(crate) fn with_children(&self, mut children: Vec<Expression>) -> Expression {
+        match self {
+            TensorExpression::KroneckerDeltas(_) | TensorExpression::SizedKroneckerDeltas(_) => {
+                self.clone()
+            }
+            TensorExpression::DotProduct {
+                terms: _,
+                rank_combinations,
+            } => TensorExpression::DotProduct {
+                terms: children,
+                rank_combinations: rank_combinations.clone(),
+            },
+            TensorExpression::DirectProduct(_) => TensorExpression::DirectProduct(children),
+            TensorExpression::LogSumExp { arg: _, rank } => TensorExpression::LogSumExp {
+                arg: children.remove(0),
+                rank: *rank,
+            },
+            TensorExpression::Softmax { arg: _, rank } => TensorExpression::Softmax {
+                arg: children.remove(0),
+                rank: *rank,
+            },
+        }
+        .into()
+    }
+
+    pub(crate) fn rebuilt(&self, mut children: Vec<Expression>) -> Expression {
+        match self {
+            TensorExpression::KroneckerDeltas(_) | TensorExpression::SizedKroneckerDeltas(_) => {
+                self.clone().into()
+            }
+            TensorExpression::DotProduct {
+                terms: _,
+                rank_combinations,
+            } => children.into_iter().dot_product(rank_combinations),
+            TensorExpression::DirectProduct(_) => children.into_iter().direct_product(),
+            TensorExpression::LogSumExp { arg: _, rank } => children.remove(0).log_sum_exp(*rank),
+            TensorExpression::Softmax { arg: _, rank } => children.remove(0).softmax(*rank),
+        }
+    }
+}
@@ -1,17 +1,36 @@
-use crate::{Expression, TensorExpression};
+use crate::{Expression, Size, TensorExpression};
 
 impl TensorExpression {
     pub fn differential(&self, variable_ids: &[&str]) -> Vec<Expression> {
+        self._differential(variable_ids, Size::effective_rank(&self.sizes()))
+    }
+
+    pub(crate) fn _differential(
+        &self,
+        variable_ids: &[&str],
+        rank_offset: usize,
+    ) -> Vec<Expression> {
         match self {
-            TensorExpression::KroneckerDeltas(_) => {
+            TensorExpression::KroneckerDeltas(_) | TensorExpression::SizedKroneckerDeltas(_) => {
                 vec![0.0.into(); variable_ids.len()]
             }
             TensorExpression::DotProduct {
                 terms,
                 rank_combinations,
-            } => TensorExpression::diff_dot_product(terms, rank_combinations, variable_ids),
+            } => TensorExpression::diff_dot_product(
+                terms,
+                rank_combinations,
+                variable_ids,
+                rank_offset,
+            ),
             TensorExpression::DirectProduct(terms) => {
-                TensorExpression::diff_direct_product(terms, variable_ids)
+                TensorExpression::diff_direct_product(terms, variable_ids, rank_offset)
+            }
+            TensorExpression::LogSumExp { arg, rank } => {
+                TensorExpression::diff_log_sum_exp(arg, *rank, variable_ids, rank_offset)
+            }
+            TensorExpression::Softmax { arg, rank } => {
+                TensorExpression::diff_softmax(arg, *rank, variable_ids, rank_offset)
             }
         }
     }
@@ -0,0 +1,81 @@
+use crate::{ConstantValue, Size, SymbolicError, TensorExpression};
+use std::collections::HashMap;
+
+impl TensorExpression {
+    pub fn evaluate(
+        &self,
+        variables: &HashMap<&str, ConstantValue>,
+    ) -> Result<ConstantValue, SymbolicError> {
+        Size::bind_dims(self.variable_sizes(), variables)?;
+        self._evaluate(variables)
+    }
+
+    pub(crate) fn _evaluate(
+        &self,
+        variables: &HashMap<&str, ConstantValue>,
+    ) -> Result<ConstantValue, SymbolicError> {
+        match self {
+            TensorExpression::KroneckerDeltas(_) | TensorExpression::SizedKroneckerDeltas(_) => {
+                Err(SymbolicError::UndeterminedSize)
+            }
+            TensorExpression::DotProduct {
+                terms,
+                rank_combinations,
+            } => TensorExpression::evaluate_dot_product(terms, rank_combinations, variables),
+            TensorExpression::DirectProduct(terms) => {
+                TensorExpression::evaluate_direct_product(terms, variables)
+            }
+            TensorExpression::LogSumExp { arg, rank } => {
+                TensorExpression::evaluate_log_sum_exp(arg, *rank, variables)
+            }
+            TensorExpression::Softmax { arg, rank } => {
+                TensorExpression::evaluate_softmax(arg, *rank, variables)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use opensrdk_linear_algebra::{sparse::SparseTensor, Matrix};
+
+    use crate::{new_variable_tensor, ConstantValue, Expression, Size};
+
+    #[test]
+    fn it_works1() {
+        let x = new_variable_tensor("x".to_string(), vec![Size::Many]);
+        let a = Expression::from(vec![1.0, 2.0, 3.0]);
+        let expression = a.dot(x, &[[0, 0]]);
+        let diff = expression.differential(&["x"])[0].clone();
+
+        let mut hash = HashMap::new();
+        hash.insert("x", ConstantValue::Tensor(vec![4.0, 5.0, 6.0].into()));
+
+        let value = expression.evaluate(&hash).unwrap();
+        assert_eq!(value, ConstantValue::Tensor(vec![32.0].into()));
+
+        let mut elems = HashMap::new();
+        elems.insert(vec![0, 0], 1.0);
+        elems.insert(vec![0, 1], 2.0);
+        elems.insert(vec![0, 2], 3.0);
+        let gradient = diff.evaluate(&hash).unwrap();
+        assert_eq!(
+            gradient,
+            ConstantValue::Tensor(SparseTensor::from(vec![1, 3], elems).unwrap())
+        );
+    }
+
+    #[test]
+    fn it_works2() {
+        let a = Expression::from(Matrix::from(2, vec![1.0, 0.0, 0.0, 2.0]).unwrap());
+        let b = Expression::from(vec![1.0, 3.0]);
+        let expression = a.direct(b);
+
+        let result = expression.evaluate(&HashMap::new()).unwrap();
+
+        assert_eq!(result.sizes(), vec![4, 2]);
+        assert_eq!(result.elems().iter().sum::<f64>(), 12.0);
+    }
+}
@@ -1,6 +1,9 @@
 pub mod assign;
+pub mod children;
 pub mod differential;
+pub mod evaluate;
 pub mod operations;
+pub mod simplify;
 pub mod size;
 pub mod tex_code;
 pub mod variable;
@@ -12,8 +15,12 @@ pub use size::*;
 pub use tex_code::*;
 pub use variable::*;
 
-use crate::Expression;
-use std::collections::{HashMap, HashSet};
+use crate::{Expression, Size};
+use opensrdk_linear_algebra::RankIndex;
+use std::{
+    collections::{HashMap, HashSet},
+    hash::{Hash, Hasher},
+};
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum TensorExpression {
@@ -23,6 +30,47 @@ pub enum TensorExpression {
         rank_combinations: Vec<HashMap<usize, String>>,
     },
     DirectProduct(Vec<Expression>),
+    LogSumExp {
+        arg: Expression,
+        rank: RankIndex,
+    },
+    Softmax {
+        arg: Expression,
+        rank: RankIndex,
+    },
+    /// Kronecker deltas which also know the size of the ranks each pair connects, so that a delta traced
+    /// with itself contracts to its dimension.
+    SizedKroneckerDeltas(Vec<([usize; 2], Size)>),
+}
+
+impl Hash for TensorExpression {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            TensorExpression::KroneckerDeltas(rank_pairs) => rank_pairs.hash(state),
+            TensorExpression::DotProduct {
+                terms,
+                rank_combinations,
+            } => {
+                terms.hash(state);
+                for rank_combination in rank_combinations.iter() {
+                    let mut ids = rank_combination.iter().collect::<Vec<_>>();
+                    ids.sort();
+                    ids.hash(state);
+                }
+            }
+            TensorExpression::DirectProduct(terms) => terms.hash(state),
+            TensorExpression::LogSumExp { arg, rank } => {
+                arg.hash(state);
+                rank.hash(state);
+            }
+            TensorExpression::Softmax { arg, rank } => {
+                arg.hash(state);
+                rank.hash(state);
+            }
+            TensorExpression::SizedKroneckerDeltas(rank_pairs) => rank_pairs.hash(state),
+        }
+    }
 }
 
 impl Expression {
@@ -1,6 +1,6 @@
-use opensrdk_linear_algebra::sparse::SparseTensor;
+use opensrdk_linear_algebra::{indices_cartesian_product, sparse::SparseTensor, Tensor};
 
-use crate::{BracketsLevel, Expression, Size, TensorExpression};
+use crate::{BracketsLevel, ConstantValue, Expression, Size, SymbolicError, TensorExpression};
 use std::{collections::HashMap, iter::once};
 
 pub trait DirectProduct {
@@ -12,7 +12,13 @@ where
     I: Iterator<Item = Expression>,
 {
     fn direct_product(self) -> Expression {
-        TensorExpression::DirectProduct(self.collect()).into()
+        let terms = self.collect::<Vec<_>>();
+
+        if terms.iter().any(|t| t == &0.0.into()) {
+            return 0.0.into();
+        }
+
+        TensorExpression::DirectProduct(terms).into()
     }
 }
 
@@ -26,6 +32,7 @@ impl TensorExpression {
     pub(crate) fn diff_direct_product(
         terms: &Vec<Expression>,
         symbols: &[&str],
+        rank_offset: usize,
     ) -> Vec<Expression> {
         let terms_len = terms.len();
         let symbols_len = symbols.len();
@@ -36,7 +43,7 @@ impl TensorExpression {
                 let elems_right = (i + 1..terms_len)
                     .map(|k| terms[k].clone())
                     .direct_product();
-                let elem_diff = terms[i].differential(symbols);
+                let elem_diff = terms[i]._differential(symbols, rank_offset);
 
                 let elems = (0..symbols_len)
                     .map(|l| {
@@ -81,23 +88,113 @@ impl TensorExpression {
             .into_iter()
             .map(|t| t.sizes())
             .fold(vec![], |mut acc, next| {
+                for i in 0..acc.len().min(next.len()) {
+                    acc[i] = acc[i].product(&next[i]);
+                }
                 if acc.len() < next.len() {
-                    for i in 0..acc.len() {
-                        if next[i] == Size::Many {
-                            acc[i] = next[i];
-                        }
-                    }
-                    acc.extend(next[acc.len()..].iter().copied());
-                } else {
-                    for i in 0..next.len() {
-                        if next[i] == Size::Many {
-                            acc[i] = next[i];
+                    acc.extend(next[acc.len()..].iter().cloned());
+                }
+                acc
+            })
+    }
+
+    pub(crate) fn evaluate_direct_product(
+        terms: &[Expression],
+        variables: &HashMap<&str, ConstantValue>,
+    ) -> Result<ConstantValue, SymbolicError> {
+        let values = terms
+            .iter()
+            .map(|t| t._evaluate(variables))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(TensorExpression::direct_product_value(&values))
+    }
+
+    /// The direct product of the values of the terms.
+    pub(crate) fn direct_product_value(values: &[ConstantValue]) -> ConstantValue {
+        match split_product(values) {
+            (scalar, Some(tensor)) => ConstantValue::Tensor(tensor * scalar),
+            (scalar, None) => ConstantValue::Scalar(scalar),
+        }
+    }
+
+    /// The adjoints of the terms of a direct product, given their values and the adjoint of the product.
+    pub(crate) fn direct_product_adjoints(
+        values: &[ConstantValue],
+        adjoint: &ConstantValue,
+    ) -> Result<Vec<ConstantValue>, SymbolicError> {
+        let g = adjoint.to_tensor();
+        let unit = || SparseTensor::from(vec![], once((vec![], 1.0)).collect()).unwrap();
+        let size = |t: &SparseTensor, rank: usize| if rank < t.rank() { t.size(rank) } else { 1 };
+        let index = |indices: &[usize], rank: usize| indices.get(rank).copied().unwrap_or(0);
+
+        (0..values.len())
+            .map(|k| {
+                let (scalar_left, left) = split_product(&values[..k]);
+                let (scalar_right, right) = split_product(&values[k + 1..]);
+                let scalar = scalar_left * scalar_right;
+
+                if let ConstantValue::Scalar(_) = values[k] {
+                    let sum = match (left, right) {
+                        (Some(l), Some(r)) => dot_elems(&l.direct(&r), &g),
+                        (Some(t), None) | (None, Some(t)) => dot_elems(&t, &g),
+                        (None, None) => adjoint.elems().iter().sum(),
+                    };
+                    return Ok(ConstantValue::Scalar(sum * scalar));
+                }
+
+                // The product is left ⊗ term ⊗ right, so each element of it is indexed by one element of each of them.
+                let term = values[k].to_tensor();
+                let sizes = values[k].sizes();
+                let (left, right) = (left.unwrap_or_else(unit), right.unwrap_or_else(unit));
+                let mut elems = HashMap::<Vec<usize>, f64>::new();
+                for (l_indices, l) in left.elems().iter() {
+                    for (r_indices, r) in right.elems().iter() {
+                        for indices in indices_cartesian_product(&sizes) {
+                            let product_indices = (0..g.rank())
+                                .map(|rank| {
+                                    (index(l_indices, rank) * size(&term, rank)
+                                        + index(&indices, rank))
+                                        * size(&right, rank)
+                                        + index(r_indices, rank)
+                                })
+                                .collect::<Vec<_>>();
+                            *elems.entry(indices).or_insert(0.0) +=
+                                g[&product_indices] * l * r * scalar;
                         }
                     }
                 }
-                acc
+                elems.retain(|_, v| *v != 0.0);
+
+                Ok(ConstantValue::Tensor(SparseTensor::from(sizes, elems)?))
             })
+            .collect()
+    }
+}
+
+/// The product of the scalars and the direct product of the others among `values`.
+fn split_product(values: &[ConstantValue]) -> (f64, Option<SparseTensor>) {
+    let mut scalar = 1.0;
+    let mut tensor = None::<SparseTensor>;
+
+    for v in values.iter() {
+        match v {
+            ConstantValue::Scalar(v) => scalar *= v,
+            v => {
+                let v = v.to_tensor();
+                tensor = Some(match tensor {
+                    Some(acc) => acc.direct(&v),
+                    None => v,
+                });
+            }
+        }
     }
+
+    (scalar, tensor)
+}
+
+fn dot_elems(t: &SparseTensor, g: &SparseTensor) -> f64 {
+    t.elems().iter().map(|(indices, v)| v * g[indices]).sum()
 }
 
 #[cfg(test)]
@@ -159,7 +256,7 @@ mod tests {
 
         let ids = &["x", "y"];
 
-        let diff_dp = TensorExpression::diff_direct_product(&vec![ec.clone(), ea, eb, ec], ids);
+        let diff_dp = TensorExpression::diff_direct_product(&vec![ec.clone(), ea, eb, ec], ids, 0);
         println!("{:?}", diff_dp);
 
         let tex_symbols = vec![("x", "y")].into_iter().collect();
@@ -1,26 +1,681 @@
-use crate::{BracketsLevel, Expression, Size, TensorExpression};
-use opensrdk_linear_algebra::{generate_rank_combinations, RankIndex};
-use std::{collections::HashMap, iter::once};
+use crate::{BracketsLevel, ConstantValue, Expression, Size, SymbolicError, TensorExpression};
+use opensrdk_linear_algebra::{
+    generate_rank_combinations, sparse::SparseTensor, RankIndex, Tensor,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    iter::once,
+};
 
 type TermIndex = usize;
+type Delta = ([RankIndex; 2], Size);
+type CombinedDeltas<'a> = (Vec<Delta>, &'a HashMap<RankIndex, String>);
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum IndexLabel {
+    Combined(String),
+    Free(RankIndex),
+}
+
+#[derive(Clone)]
+pub(crate) enum ContractionTerm {
+    Value(SparseTensor),
+    Deltas(Vec<Delta>),
+}
+
+struct IndexedTerm<'a> {
+    labels: Vec<(RankIndex, IndexLabel)>,
+    key_labels: Vec<(RankIndex, IndexLabel)>,
+    groups: HashMap<Vec<usize>, Vec<(&'a Vec<usize>, f64)>>,
+}
+
+fn index_label(rank_combination: &HashMap<RankIndex, String>, rank: RankIndex) -> IndexLabel {
+    match rank_combination.get(&rank) {
+        Some(id) => IndexLabel::Combined(id.to_owned()),
+        None => IndexLabel::Free(rank),
+    }
+}
+
+/// Sums the products of the terms over the ranks sharing a combination id.
+/// The ranks which are not combined are aligned by their rank index, and those of size 1 are broadcast.
+/// The ids in `kept` are not summed up but placed at the given ranks of the result.
+pub(crate) fn contract(
+    terms: &[ContractionTerm],
+    rank_combinations: &[HashMap<RankIndex, String>],
+    kept: &HashMap<RankIndex, String>,
+) -> Result<ConstantValue, SymbolicError> {
+    let mut dims = HashMap::<IndexLabel, usize>::new();
+    let mut max_rank = 0;
+    let mut delta_pairs = vec![];
+
+    for (i, t) in terms.iter().enumerate() {
+        match t {
+            ContractionTerm::Value(v) => {
+                max_rank = max_rank.max(v.rank());
+                for rank in 0..v.rank() {
+                    let label = index_label(&rank_combinations[i], rank);
+                    let dim = v.size(rank);
+                    if dim == 1 && matches!(label, IndexLabel::Free(_)) {
+                        continue;
+                    }
+                    if *dims.entry(label).or_insert(dim) != dim {
+                        return Err(SymbolicError::DimensionMismatch);
+                    }
+                }
+            }
+            ContractionTerm::Deltas(rank_pairs) => {
+                for ([a, b], size) in rank_pairs.iter() {
+                    for &rank in [a, b] {
+                        if !rank_combinations[i].contains_key(&rank) {
+                            max_rank = max_rank.max(rank + 1);
+                        }
+                    }
+                    let pair = (
+                        index_label(&rank_combinations[i], *a),
+                        index_label(&rank_combinations[i], *b),
+                    );
+                    if let Some(dim) = size.fixed_len() {
+                        for label in [&pair.0, &pair.1] {
+                            if dim == 1 && matches!(label, IndexLabel::Free(_)) {
+                                continue;
+                            }
+                            if *dims.entry(label.clone()).or_insert(dim) != dim {
+                                return Err(SymbolicError::DimensionMismatch);
+                            }
+                        }
+                    }
+                    delta_pairs.push(pair);
+                }
+            }
+        }
+    }
+
+    // The dimensions of the deltas are inherited from the ranks they connect.
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (a, b) in delta_pairs.iter() {
+            match (dims.get(a).copied(), dims.get(b).copied()) {
+                (Some(da), Some(db)) if da != db => return Err(SymbolicError::DimensionMismatch),
+                (Some(da), None) => {
+                    dims.insert(b.clone(), da);
+                    changed = true;
+                }
+                (None, Some(db)) => {
+                    dims.insert(a.clone(), db);
+                    changed = true;
+                }
+                _ => {}
+            }
+        }
+    }
+    if delta_pairs
+        .iter()
+        .any(|(a, b)| !dims.contains_key(a) || !dims.contains_key(b))
+    {
+        return Err(SymbolicError::UndeterminedSize);
+    }
+
+    let mut bound = Vec::<IndexLabel>::new();
+    let indexed_terms = terms
+        .iter()
+        .enumerate()
+        .filter_map(|(i, t)| match t {
+            ContractionTerm::Value(v) => Some((i, v)),
+            ContractionTerm::Deltas(_) => None,
+        })
+        .map(|(i, v)| {
+            let labels = (0..v.rank())
+                .map(|rank| (rank, index_label(&rank_combinations[i], rank)))
+                .filter(|(rank, label)| v.size(*rank) != 1 || !matches!(label, IndexLabel::Free(_)))
+                .collect::<Vec<_>>();
+            let key_labels = labels
+                .iter()
+                .filter(|(_, label)| bound.contains(label))
+                .cloned()
+                .collect::<Vec<_>>();
+            bound.extend(labels.iter().map(|(_, label)| label.clone()));
+
+            let mut groups = HashMap::<Vec<usize>, Vec<_>>::new();
+            for (indices, &value) in v.elems().iter() {
+                let key = key_labels.iter().map(|(rank, _)| indices[*rank]).collect();
+                groups.entry(key).or_default().push((indices, value));
+            }
+
+            IndexedTerm {
+                labels,
+                key_labels,
+                groups,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let max_rank = kept.keys().map(|&rank| rank + 1).fold(max_rank, usize::max);
+    let output_labels = (0..max_rank)
+        .map(|rank| match kept.get(&rank) {
+            Some(id) => IndexLabel::Combined(id.to_owned()),
+            None => IndexLabel::Free(rank),
+        })
+        .collect::<Vec<_>>();
+
+    let mut result = HashMap::<Vec<usize>, f64>::new();
+    join_terms(
+        &indexed_terms,
+        &delta_pairs,
+        &dims,
+        &output_labels,
+        &mut HashMap::new(),
+        1.0,
+        &mut result,
+    );
+
+    if max_rank == 0 {
+        return Ok(ConstantValue::Scalar(
+            result.get(&vec![]).copied().unwrap_or(0.0),
+        ));
+    }
+
+    let sizes = output_labels
+        .iter()
+        .map(|label| dims.get(label).copied().unwrap_or(1))
+        .collect();
+    let elems = result.into_iter().filter(|(_, v)| *v != 0.0).collect();
+
+    Ok(ConstantValue::Tensor(SparseTensor::from(sizes, elems)?))
+}
+
+fn join_terms(
+    terms: &[IndexedTerm],
+    delta_pairs: &[(IndexLabel, IndexLabel)],
+    dims: &HashMap<IndexLabel, usize>,
+    output_labels: &[IndexLabel],
+    bindings: &mut HashMap<IndexLabel, usize>,
+    product: f64,
+    result: &mut HashMap<Vec<usize>, f64>,
+) {
+    if let Some((term, rest)) = terms.split_first() {
+        let key = term
+            .key_labels
+            .iter()
+            .map(|(_, label)| bindings[label])
+            .collect::<Vec<_>>();
+        let entries = match term.groups.get(&key) {
+            Some(entries) => entries,
+            None => return,
+        };
+
+        for (indices, value) in entries.iter() {
+            let mut newly_bound = vec![];
+            let mut consistent = true;
+            for (rank, label) in term.labels.iter() {
+                match bindings.get(label) {
+                    Some(&index) => {
+                        if index != indices[*rank] {
+                            consistent = false;
+                            break;
+                        }
+                    }
+                    None => {
+                        bindings.insert(label.clone(), indices[*rank]);
+                        newly_bound.push(label.clone());
+                    }
+                }
+            }
+            if consistent {
+                join_terms(
+                    rest,
+                    delta_pairs,
+                    dims,
+                    output_labels,
+                    bindings,
+                    product * value,
+                    result,
+                );
+            }
+            newly_bound.iter().for_each(|label| {
+                bindings.remove(label);
+            });
+        }
+
+        return;
+    }
+
+    if let Some(((a, b), rest)) = delta_pairs.split_first() {
+        match (bindings.get(a).copied(), bindings.get(b).copied()) {
+            (Some(ia), Some(ib)) => {
+                if ia == ib {
+                    join_terms(terms, rest, dims, output_labels, bindings, product, result);
+                }
+            }
+            (Some(index), None) | (None, Some(index)) => {
+                let unbound = if bindings.contains_key(a) { b } else { a };
+                bindings.insert(unbound.clone(), index);
+                join_terms(terms, rest, dims, output_labels, bindings, product, result);
+                bindings.remove(unbound);
+            }
+            (None, None) => {
+                for index in 0..dims[a] {
+                    bindings.insert(a.clone(), index);
+                    bindings.insert(b.clone(), index);
+                    join_terms(terms, rest, dims, output_labels, bindings, product, result);
+                    bindings.remove(a);
+                    bindings.remove(b);
+                }
+            }
+        }
+
+        return;
+    }
+
+    let indices = output_labels
+        .iter()
+        .map(|label| bindings.get(label).copied().unwrap_or(0))
+        .collect();
+    *result.entry(indices).or_insert(0.0) += product;
+}
+
+/// Contracts the constant terms into one constant term.
+/// The deltas connected to the constant terms are folded into it by relabeling its ranks.
+fn merge_constants(
+    terms: &mut Vec<Expression>,
+    rank_combinations: &mut Vec<HashMap<RankIndex, String>>,
+    deltas: &mut Vec<Delta>,
+    deltas_combination: &mut HashMap<RankIndex, String>,
+) -> Result<(), SymbolicError> {
+    let constants = terms
+        .iter()
+        .enumerate()
+        .filter_map(|(i, t)| match t {
+            Expression::Constant(v) => Some((i, v.to_tensor())),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if constants.is_empty() {
+        return Ok(());
+    }
+
+    let mut dims = HashMap::<IndexLabel, usize>::new();
+    for (i, v) in constants.iter() {
+        for rank in 0..v.rank() {
+            let label = index_label(&rank_combinations[*i], rank);
+            if v.size(rank) != 1 || !matches!(label, IndexLabel::Free(_)) {
+                dims.insert(label, v.size(rank));
+            }
+        }
+    }
+
+    // A delta can be folded if the dimension of its ranks is determined by the constants.
+    let mut folded = vec![false; deltas.len()];
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (k, ([a, b], _)) in deltas.iter().enumerate() {
+            if folded[k] {
+                continue;
+            }
+            let (la, lb) = (
+                index_label(deltas_combination, *a),
+                index_label(deltas_combination, *b),
+            );
+            let dim = match (dims.get(&la), dims.get(&lb)) {
+                (Some(&d), _) | (None, Some(&d)) => d,
+                (None, None) => continue,
+            };
+            dims.entry(la).or_insert(dim);
+            dims.entry(lb).or_insert(dim);
+            folded[k] = true;
+            changed = true;
+        }
+    }
+
+    // The ids referred by the other terms must stay open in the merged constant.
+    let open_ids = terms
+        .iter()
+        .zip(rank_combinations.iter())
+        .filter(|(t, _)| !matches!(t, Expression::Constant(_)))
+        .flat_map(|(_, r)| r.values())
+        .chain(
+            deltas
+                .iter()
+                .zip(folded.iter())
+                .filter(|(_, &f)| !f)
+                .flat_map(|((pair, _), _)| pair.iter())
+                .filter_map(|rank| deltas_combination.get(rank)),
+        )
+        .cloned()
+        .collect::<HashSet<_>>();
+    let merged_ids = constants
+        .iter()
+        .flat_map(|(i, _)| {
+            let mut sorted = rank_combinations[*i].iter().collect::<Vec<_>>();
+            sorted.sort_by(|a, b| a.0.cmp(b.0));
+            sorted.into_iter().map(|(_, id)| id)
+        })
+        .chain(
+            deltas
+                .iter()
+                .zip(folded.iter())
+                .filter(|(_, &f)| f)
+                .flat_map(|((pair, _), _)| pair.iter())
+                .filter_map(|rank| deltas_combination.get(rank)),
+        )
+        .collect::<Vec<_>>();
+
+    let has_internal_ids = merged_ids.iter().any(|&id| !open_ids.contains(id));
+    if constants.len() < 2 && !folded.contains(&true) && !has_internal_ids {
+        return Ok(());
+    }
+
+    // The open ids are placed on the ranks which are not left uncombined in the merged constant.
+    let mut kept = HashMap::<RankIndex, String>::new();
+    let mut rank = 0;
+    for &id in merged_ids.iter() {
+        if !open_ids.contains(id) || kept.values().any(|k| k == id) {
+            continue;
+        }
+        while dims.contains_key(&IndexLabel::Free(rank)) || kept.contains_key(&rank) {
+            rank += 1;
+        }
+        kept.insert(rank, id.to_owned());
+    }
+
+    let contraction_terms = constants
+        .iter()
+        .map(|(_, v)| ContractionTerm::Value(v.clone()))
+        .chain(
+            deltas
+                .iter()
+                .zip(folded.iter())
+                .filter(|(_, &f)| f)
+                .map(|(delta, _)| ContractionTerm::Deltas(vec![delta.clone()])),
+        )
+        .collect::<Vec<_>>();
+    let contraction_rank_combinations = constants
+        .iter()
+        .map(|(i, _)| rank_combinations[*i].clone())
+        .chain(
+            folded
+                .iter()
+                .filter(|&&f| f)
+                .map(|_| deltas_combination.clone()),
+        )
+        .collect::<Vec<_>>();
+
+    let merged = contract(&contraction_terms, &contraction_rank_combinations, &kept)?;
+
+    for (i, _) in constants.iter().rev() {
+        terms.remove(*i);
+        rank_combinations.remove(*i);
+    }
+    let mut folded = folded.into_iter();
+    deltas.retain(|_| !folded.next().unwrap());
+    let delta_ranks = deltas
+        .iter()
+        .flat_map(|(pair, _)| pair)
+        .copied()
+        .collect::<HashSet<_>>();
+    deltas_combination.retain(|rank, _| delta_ranks.contains(rank));
+
+    terms.push(merged.into());
+    rank_combinations.push(kept);
+
+    Ok(())
+}
+
+/// Makes the rank of the term combined with `id` free at `rank`, transposing the term if it is a matrix with `id` on its other rank.
+fn move_to_free_rank(
+    terms: &mut [Expression],
+    rank_combinations: &mut [HashMap<RankIndex, String>],
+    id: &str,
+    rank: RankIndex,
+) -> bool {
+    let occurrences = rank_combinations
+        .iter()
+        .enumerate()
+        .flat_map(|(i, r)| {
+            r.iter()
+                .filter(|(_, v)| v.as_str() == id)
+                .map(move |(&combined, _)| (i, combined))
+        })
+        .collect::<Vec<_>>();
+    // Two ranks cannot be free at the same rank.
+    let (i, combined) = match occurrences[..] {
+        [occurrence] => occurrence,
+        _ => return false,
+    };
+    let conflicts = terms
+        .iter()
+        .zip(rank_combinations.iter())
+        .enumerate()
+        .any(|(j, (t, r))| {
+            j != i
+                && !r.contains_key(&rank)
+                && t.sizes().get(rank).is_some_and(|size| !size.is_one())
+        });
+    if conflicts {
+        return false;
+    }
+
+    if combined == rank {
+        rank_combinations[i].remove(&combined);
+        return true;
+    }
+
+    let sizes = terms[i].sizes();
+    if sizes.len() != 2 || combined + rank != 1 {
+        return false;
+    }
+    let other = rank_combinations[i].get(&rank).cloned();
+    if other.is_none() && !sizes[rank].is_one() {
+        return false;
+    }
+    match terms[i].clone().try_t() {
+        Ok(t) => terms[i] = t,
+        Err(_) => return false,
+    }
+    rank_combinations[i] = other.into_iter().map(|id| (combined, id)).collect();
+
+    true
+}
+
+/// Removes the deltas by renaming the labels they connect.
+/// A delta between two ids merges them, and a delta between an id and a free rank moves what is combined with the id to that rank.
+/// A delta traced with itself is the identity for the other factors summed over its id.
+/// When there are none, it counts the dimension of its ranks, so the product of the known dimensions is returned, and the others stay.
+fn eliminate_deltas(
+    terms: &mut [Expression],
+    rank_combinations: &mut [HashMap<RankIndex, String>],
+    deltas: &mut Vec<Delta>,
+    deltas_combination: &mut HashMap<RankIndex, String>,
+) -> usize {
+    let mut traced = 1;
+    let mut k = 0;
+    while k < deltas.len() {
+        let ([a, b], size) = deltas.remove(k);
+        let in_deltas = |id: &str| {
+            deltas
+                .iter()
+                .flat_map(|(pair, _)| pair)
+                .any(|rank| deltas_combination.get(rank).is_some_and(|v| v == id))
+        };
+        let in_terms = |id: &str| {
+            rank_combinations
+                .iter()
+                .any(|r| r.values().any(|v| v == id))
+        };
+
+        let eliminated = match (
+            index_label(deltas_combination, a),
+            index_label(deltas_combination, b),
+        ) {
+            (IndexLabel::Combined(x), IndexLabel::Combined(y)) if x == y => {
+                if in_deltas(&x) || in_terms(&x) {
+                    true
+                } else if let Some(dim) = size.fixed_len() {
+                    traced *= dim;
+                    true
+                } else {
+                    false
+                }
+            }
+            (IndexLabel::Combined(x), IndexLabel::Combined(y)) => {
+                let rename = |id: &mut String| {
+                    if id == &y {
+                        *id = x.clone();
+                    }
+                };
+                rank_combinations
+                    .iter_mut()
+                    .flat_map(|r| r.values_mut())
+                    .for_each(rename);
+                deltas_combination.values_mut().for_each(rename);
+                true
+            }
+            (IndexLabel::Combined(x), IndexLabel::Free(rank))
+            | (IndexLabel::Free(rank), IndexLabel::Combined(x)) => {
+                let moved = if in_terms(&x) {
+                    move_to_free_rank(terms, rank_combinations, &x, rank)
+                } else {
+                    in_deltas(&x)
+                };
+                if moved {
+                    // The ranks of the deltas only stand for their labels, so they are renumbered.
+                    let renumbered = deltas_combination
+                        .iter()
+                        .filter(|(_, id)| id == &&x)
+                        .map(|(&r, _)| r)
+                        .collect::<HashSet<_>>();
+                    for (pair, _) in deltas.iter_mut() {
+                        for r in pair.iter_mut() {
+                            if renumbered.contains(r) {
+                                *r = rank;
+                            }
+                        }
+                    }
+                    deltas_combination.retain(|r, _| !renumbered.contains(r));
+                    deltas.retain(|([c, d], _)| c != d || deltas_combination.contains_key(c));
+                }
+                moved
+            }
+            (IndexLabel::Free(_), IndexLabel::Free(_)) => false,
+        };
+
+        if eliminated {
+            let delta_ranks = deltas
+                .iter()
+                .flat_map(|(pair, _)| pair)
+                .copied()
+                .collect::<HashSet<_>>();
+            deltas_combination.retain(|rank, _| delta_ranks.contains(rank));
+            k = 0;
+        } else {
+            deltas.insert(k, ([a, b], size));
+            k += 1;
+        }
+    }
+
+    traced
+}
+
+fn free_delta_term(
+    terms: &[Expression],
+    rank_combinations: &[HashMap<RankIndex, String>],
+    rank: RankIndex,
+) -> Option<TermIndex> {
+    terms.iter().enumerate().position(|(i, t)| match t {
+        Expression::Tensor(t) => match t.as_ref() {
+            TensorExpression::KroneckerDeltas(rank_pairs) => {
+                !rank_combinations[i].contains_key(&rank)
+                    && rank_pairs.iter().any(|pair| pair.contains(&rank))
+            }
+            TensorExpression::SizedKroneckerDeltas(rank_pairs) => {
+                !rank_combinations[i].contains_key(&rank)
+                    && rank_pairs.iter().any(|(pair, _)| pair.contains(&rank))
+            }
+            _ => false,
+        },
+        _ => false,
+    })
+}
+
+/// Merges the deltas of several terms into one term.
+/// Combined ranks only stand for their combination id, so they are renumbered when they clash with a rank used by another delta.
+fn merge_deltas(deltas: &[CombinedDeltas]) -> (Vec<Delta>, HashMap<RankIndex, String>) {
+    let free_ranks = deltas
+        .iter()
+        .flat_map(|(rank_pairs, rank_combination)| {
+            rank_pairs
+                .iter()
+                .flat_map(|(pair, _)| pair)
+                .filter(move |rank| !rank_combination.contains_key(rank))
+        })
+        .copied()
+        .collect::<HashSet<_>>();
+    let mut next_rank = deltas
+        .iter()
+        .flat_map(|(rank_pairs, _)| rank_pairs.iter().flat_map(|(pair, _)| pair))
+        .map(|&rank| rank + 1)
+        .max()
+        .unwrap_or(0);
+
+    let mut merged = vec![];
+    let mut merged_combination = HashMap::<RankIndex, String>::new();
+    let mut used = HashSet::new();
+
+    for (rank_pairs, rank_combination) in deltas.iter() {
+        let mut renumbered = HashMap::new();
+        for (&rank, id) in rank_combination.iter() {
+            let clashes = free_ranks.contains(&rank)
+                || (used.contains(&rank) && merged_combination.get(&rank) != Some(id));
+            if clashes {
+                renumbered.insert(rank, next_rank);
+                next_rank += 1;
+            }
+        }
+        let renumber = |rank: RankIndex| renumbered.get(&rank).copied().unwrap_or(rank);
+
+        for ([a, b], size) in rank_pairs.iter() {
+            let (a, b) = (renumber(*a), renumber(*b));
+            merged.push(([a, b], size.clone()));
+            used.insert(a);
+            used.insert(b);
+        }
+        for (&rank, id) in rank_combination.iter() {
+            merged_combination.insert(renumber(rank), id.to_owned());
+        }
+    }
+
+    (merged, merged_combination)
+}
 
 fn next_char(c: char, count: usize) -> char {
     std::char::from_u32(c as u32 + count as u32).unwrap_or(c)
 }
 
-pub trait DotProduct {
-    fn dot_product(self, rank_combinations: &[HashMap<RankIndex, String>]) -> Expression;
+pub trait DotProduct: Sized {
+    fn try_dot_product(
+        self,
+        rank_combinations: &[HashMap<RankIndex, String>],
+    ) -> Result<Expression, SymbolicError>;
+
+    fn dot_product(self, rank_combinations: &[HashMap<RankIndex, String>]) -> Expression {
+        self.try_dot_product(rank_combinations)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
 }
 
 impl<I> DotProduct for I
 where
     I: Iterator<Item = Expression>,
 {
-    fn dot_product(self, rank_combinations: &[HashMap<RankIndex, String>]) -> Expression {
+    fn try_dot_product(
+        self,
+        rank_combinations: &[HashMap<RankIndex, String>],
+    ) -> Result<Expression, SymbolicError> {
         // Flatten InnerProd
         let terms = self
             .zip(rank_combinations.iter())
-            .flat_map(|(t, rank_combination)| {
+            .map(|(t, rank_combination)| {
                 if let Expression::Tensor(t) = &t {
                     if let TensorExpression::DotProduct {
                         terms: t,
@@ -30,30 +685,41 @@ where
                         let t = t.clone();
                         let mut rank_combinations = rank_combinations.clone();
                         let not_1dimension_ranks =
-                            TensorExpression::not_1dimension_ranks_in_dot_product(
+                            TensorExpression::try_not_1dimension_ranks_in_dot_product(
                                 &t,
                                 &rank_combinations,
-                            );
+                            )?;
 
                         for (&rank, id) in rank_combination.iter() {
                             if let Some(&term_index) = not_1dimension_ranks.get(&rank) {
                                 rank_combinations[term_index].insert(rank, id.to_owned());
+                                continue;
+                            }
+                            // The rank may be carried by a free rank of the deltas.
+                            if let Some(term_index) = free_delta_term(&t, &rank_combinations, rank)
+                            {
+                                rank_combinations[term_index].insert(rank, id.to_owned());
                             }
                         }
 
-                        return t
+                        return Ok(t
                             .into_iter()
                             .zip(rank_combinations.into_iter())
-                            .collect::<Vec<_>>();
+                            .collect::<Vec<_>>());
                     }
                 }
 
-                vec![(t, rank_combination.clone())]
+                Ok(vec![(t, rank_combination.clone())])
             })
+            .collect::<Result<Vec<_>, SymbolicError>>()?
+            .into_iter()
+            .flatten()
             .collect::<Vec<_>>();
 
+        TensorExpression::check_combined_sizes(&terms)?;
+
         if terms.iter().find(|&t| &t.0 == &0.0.into()).is_some() {
-            return 0.0.into();
+            return Ok(0.0.into());
         }
 
         // Merge KroneckerDeltas
@@ -61,8 +727,8 @@ where
             .iter()
             .filter_map(|(t, r)| {
                 if let Expression::Tensor(t) = t {
-                    if let TensorExpression::KroneckerDeltas(rank_pairs) = t.as_ref() {
-                        return Some((rank_pairs.clone(), r));
+                    if let Some(rank_pairs) = t.delta_pairs() {
+                        return Some((rank_pairs, r));
                     }
                 }
 
@@ -73,7 +739,7 @@ where
             .iter()
             .filter(|(t, _)| {
                 if let Expression::Tensor(t) = t {
-                    if let &TensorExpression::KroneckerDeltas(_) = t.as_ref() {
+                    if t.delta_pairs().is_some() {
                         return false;
                     }
                 }
@@ -82,17 +748,7 @@ where
             })
             .collect::<Vec<_>>();
 
-        let flatten_deltas = deltas
-            .iter()
-            .map(|(t, _)| t)
-            .flatten()
-            .cloned()
-            .collect::<Vec<_>>();
-        let flatten_deltas_combination = deltas
-            .iter()
-            .flat_map(|(_, r)| r.iter())
-            .map(|(&rank, id)| (rank, id.to_owned()))
-            .collect::<HashMap<_, _>>();
+        let (mut flatten_deltas, mut flatten_deltas_combination) = merge_deltas(&deltas);
 
         let mut new_terms = not_deltas
             .iter()
@@ -103,28 +759,65 @@ where
             .map(|&(_, r)| r.clone())
             .collect::<Vec<_>>();
 
-        // TODO: Merge constants
+        // Merge constants
+        merge_constants(
+            &mut new_terms,
+            &mut new_rank_combinations,
+            &mut flatten_deltas,
+            &mut flatten_deltas_combination,
+        )?;
+
+        // Eliminate KroneckerDeltas
+        let traced = eliminate_deltas(
+            &mut new_terms,
+            &mut new_rank_combinations,
+            &mut flatten_deltas,
+            &mut flatten_deltas_combination,
+        );
+        if traced != 1 {
+            match new_terms.first_mut() {
+                Some(t) => *t = (traced as f64) * t.clone(),
+                None => {
+                    new_terms.push((traced as f64).into());
+                    new_rank_combinations.push(HashMap::new());
+                }
+            }
+        }
+
+        if flatten_deltas.is_empty() && new_terms.len() == 1 && new_rank_combinations[0].is_empty()
+        {
+            return Ok(new_terms.remove(0));
+        }
 
         if flatten_deltas.len() > 0 {
-            let merged_deltas = TensorExpression::KroneckerDeltas(flatten_deltas);
+            let merged_deltas = TensorExpression::kronecker_deltas(flatten_deltas);
 
             new_terms.insert(0, merged_deltas.into());
             new_rank_combinations.insert(0, flatten_deltas_combination);
         }
 
-        TensorExpression::DotProduct {
+        Ok(TensorExpression::DotProduct {
             terms: new_terms,
             rank_combinations: new_rank_combinations,
         }
-        .into()
+        .into())
     }
 }
 
 impl Expression {
-    pub fn dot(self, rhs: Expression, rank_pairs: &[[RankIndex; 2]]) -> Expression {
+    pub fn try_dot(
+        self,
+        rhs: Expression,
+        rank_pairs: &[[RankIndex; 2]],
+    ) -> Result<Expression, SymbolicError> {
         vec![self, rhs]
             .into_iter()
-            .dot_product(&generate_rank_combinations(rank_pairs))
+            .try_dot_product(&generate_rank_combinations(rank_pairs))
+    }
+
+    pub fn dot(self, rhs: Expression, rank_pairs: &[[RankIndex; 2]]) -> Expression {
+        self.try_dot(rhs, rank_pairs)
+            .unwrap_or_else(|e| panic!("{}", e))
     }
 }
 
@@ -133,9 +826,10 @@ impl TensorExpression {
         terms: &Vec<Expression>,
         rank_combinations: &Vec<HashMap<RankIndex, String>>,
         symbols: &[&str],
+        rank_offset: usize,
     ) -> Vec<Expression> {
         let mut result = terms[0]
-            .differential(symbols)
+            ._differential(symbols, rank_offset)
             .into_iter()
             .map(|d| {
                 once(d)
@@ -147,7 +841,7 @@ impl TensorExpression {
         for i in 1..terms.len() {
             result
                 .iter_mut()
-                .zip(terms[i].differential(symbols).into_iter())
+                .zip(terms[i]._differential(symbols, rank_offset).into_iter())
                 .for_each(|(r, d)| {
                     *r = r.clone()
                         + terms[0..i]
@@ -210,30 +904,112 @@ impl TensorExpression {
         terms: &Vec<Expression>,
         rank_combinations: &Vec<HashMap<RankIndex, String>>,
     ) -> Vec<Size> {
-        let max_rank = terms.iter().map(|vi| vi.sizes().len()).max().unwrap();
-        let mut sizes = vec![Size::One; max_rank];
+        let mut combined_sizes = HashMap::<&str, Size>::new();
+        let mut free_sizes = HashMap::<RankIndex, Size>::new();
+        let mut delta_pairs = vec![];
 
         for i in 0..terms.len() {
+            if let Expression::Tensor(t) = &terms[i] {
+                if let Some(rank_pairs) = t.delta_pairs() {
+                    delta_pairs.extend(rank_pairs.into_iter().map(|pair| (pair, i)));
+                    continue;
+                }
+            }
+
             let term_sizes = terms[i].sizes();
 
-            for (rank, size) in term_sizes.iter().enumerate() {
-                if sizes[rank] == Size::Many {
+            for (rank, size) in term_sizes.into_iter().enumerate() {
+                if size.is_one() {
                     continue;
                 }
-                if let Some(_) = rank_combinations[i].get(&rank) {
+                if let Some(id) = rank_combinations[i].get(&rank) {
+                    combined_sizes.insert(id.as_str(), size);
+                    continue;
+                }
+                // The free sizes are checked to unify when the product is built.
+                let unified = match free_sizes.get(&rank) {
+                    Some(free_size) => free_size.unify(&size).unwrap_or(size),
+                    None => size,
+                };
+                free_sizes.insert(rank, unified);
+            }
+        }
+
+        // A free rank of the deltas has the size of the rank it is paired with.
+        for (([a, b], size), i) in delta_pairs {
+            for (free, paired) in [(a, b), (b, a)] {
+                if rank_combinations[i].contains_key(&free) {
                     continue;
                 }
-                sizes.insert(rank, size.clone());
+                let size = rank_combinations[i]
+                    .get(&paired)
+                    .and_then(|id| combined_sizes.get(id.as_str()))
+                    .cloned()
+                    .unwrap_or_else(|| size.clone());
+                free_sizes.entry(free).or_insert(size);
             }
         }
 
-        sizes
+        let max_rank = terms
+            .iter()
+            .map(|vi| vi.sizes().len())
+            .chain(free_sizes.keys().map(|&rank| rank + 1))
+            .max()
+            .unwrap_or(0);
+
+        (0..max_rank)
+            .map(|rank| free_sizes.remove(&rank).unwrap_or(Size::One))
+            .collect()
     }
 
-    pub fn not_1dimension_ranks_in_dot_product(
+    /// Checks that the ranks combined with the same id, and the free ranks aligned at the same rank, have sizes which unify.
+    /// A `Dim` met with fixed sizes is bound to the first one, so that it conflicts with the others.
+    fn check_combined_sizes(
+        terms: &[(Expression, HashMap<RankIndex, String>)],
+    ) -> Result<(), SymbolicError> {
+        let mut combined_sizes = HashMap::<&str, Size>::new();
+        let mut free_sizes = HashMap::<RankIndex, Size>::new();
+        let mut dims = HashMap::new();
+
+        for (t, rank_combination) in terms.iter() {
+            if let Expression::Tensor(v) = t {
+                if v.delta_pairs().is_some() {
+                    continue;
+                }
+            }
+
+            for (rank, size) in t.sizes().into_iter().enumerate() {
+                if size.is_one() {
+                    continue;
+                }
+                let (operation, unified) = match rank_combination.get(&rank) {
+                    Some(id) => ("contract", combined_sizes.get(id.as_str())),
+                    None => ("align", free_sizes.get(&rank)),
+                };
+                let unified = match unified {
+                    Some(unified) => unified.unify_binding(&size, &mut dims)?.ok_or_else(|| {
+                        SymbolicError::SizeMismatch {
+                            operation: operation.to_owned(),
+                            lhs: vec![unified.clone()],
+                            rhs: vec![size.clone()],
+                        }
+                    })?,
+                    None => size,
+                };
+                match rank_combination.get(&rank) {
+                    Some(id) => combined_sizes.insert(id.as_str(), unified),
+                    None => free_sizes.insert(rank, unified),
+                };
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn try_not_1dimension_ranks_in_dot_product(
         terms: &Vec<Expression>,
         rank_combinations: &Vec<HashMap<RankIndex, String>>,
-    ) -> HashMap<RankIndex, TermIndex> {
+    ) -> Result<HashMap<RankIndex, TermIndex>, SymbolicError> {
         let mut not_1dimension_ranks = HashMap::new();
 
         for i in 0..terms.len() {
@@ -243,20 +1019,501 @@ impl TensorExpression {
                     continue;
                 }
 
-                if *size != Size::One {
-                    if not_1dimension_ranks.contains_key(&rank) {
-                        panic!(
-                            "Rank {} is not 1-dimension in terms[{}] and terms[{}]",
+                if !size.is_one() {
+                    if let Some(&j) = not_1dimension_ranks.get(&rank) {
+                        return Err(SymbolicError::RankConflict {
                             rank,
-                            not_1dimension_ranks.get(&rank).unwrap(),
-                            i
-                        );
+                            terms: [j, i],
+                        });
                     }
                     not_1dimension_ranks.insert(rank, i);
                 }
             }
         }
 
-        not_1dimension_ranks
+        Ok(not_1dimension_ranks)
+    }
+
+    pub fn not_1dimension_ranks_in_dot_product(
+        terms: &Vec<Expression>,
+        rank_combinations: &Vec<HashMap<RankIndex, String>>,
+    ) -> HashMap<RankIndex, TermIndex> {
+        TensorExpression::try_not_1dimension_ranks_in_dot_product(terms, rank_combinations)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub(crate) fn evaluate_dot_product(
+        terms: &[Expression],
+        rank_combinations: &[HashMap<RankIndex, String>],
+        variables: &HashMap<&str, ConstantValue>,
+    ) -> Result<ConstantValue, SymbolicError> {
+        let terms = terms
+            .iter()
+            .map(|t| {
+                if let Expression::Tensor(v) = t {
+                    if let Some(rank_pairs) = v.delta_pairs() {
+                        return Ok(ContractionTerm::Deltas(rank_pairs));
+                    }
+                }
+
+                Ok(ContractionTerm::Value(t._evaluate(variables)?.to_tensor()))
+            })
+            .collect::<Result<Vec<_>, SymbolicError>>()?;
+
+        contract(&terms, rank_combinations, &HashMap::new())
+    }
+
+    /// The dot product of `terms`, leaving the ids of `kept` at the ranks paired with them, where Kronecker deltas carry them.
+    pub(crate) fn try_dot_product_keeping(
+        mut terms: Vec<Expression>,
+        mut rank_combinations: Vec<HashMap<RankIndex, String>>,
+        kept: &[(RankIndex, String)],
+    ) -> Result<Expression, SymbolicError> {
+        if let Some(base) = kept.iter().map(|(rank, _)| rank + 1).max() {
+            let size = |id: &str| {
+                terms
+                    .iter()
+                    .zip(rank_combinations.iter())
+                    .find_map(|(t, r)| {
+                        let (&rank, _) = r.iter().find(|(_, v)| v.as_str() == id)?;
+                        t.sizes().get(rank).cloned()
+                    })
+                    .unwrap_or(Size::Many)
+            };
+            let rank_pairs = kept
+                .iter()
+                .enumerate()
+                .map(|(j, (rank, id))| ([base + j, *rank], size(id)))
+                .collect();
+            terms.push(TensorExpression::kronecker_deltas(rank_pairs).into());
+            rank_combinations.push(
+                kept.iter()
+                    .enumerate()
+                    .map(|(j, (_, id))| (base + j, id.to_owned()))
+                    .collect(),
+            );
+        }
+
+        terms.into_iter().try_dot_product(&rank_combinations)
+    }
+
+    /// The adjoints of the terms of a dot product built as expressions from the adjoint of the product, as `dot_product_adjoints` computes them.
+    /// The constants and the Kronecker deltas get none.
+    pub(crate) fn dot_product_adjoint_expressions(
+        terms: &[Expression],
+        rank_combinations: &[HashMap<RankIndex, String>],
+        adjoint: &Expression,
+    ) -> Result<Vec<Option<Expression>>, SymbolicError> {
+        let open = |rank: RankIndex| format!("${}", rank);
+        let not_one = |sizes: Vec<Size>| {
+            sizes
+                .into_iter()
+                .enumerate()
+                .filter(|(_, size)| !size.is_one())
+                .map(|(rank, _)| rank)
+                .collect::<Vec<_>>()
+        };
+
+        let opened = terms
+            .iter()
+            .zip(rank_combinations.iter())
+            .map(|(t, rank_combination)| {
+                let ranks = match t {
+                    Expression::Tensor(v) => match v.as_ref() {
+                        TensorExpression::KroneckerDeltas(rank_pairs) => {
+                            rank_pairs.iter().flatten().copied().collect()
+                        }
+                        TensorExpression::SizedKroneckerDeltas(rank_pairs) => rank_pairs
+                            .iter()
+                            .flat_map(|(pair, _)| pair)
+                            .copied()
+                            .collect(),
+                        _ => not_one(t.sizes()),
+                    },
+                    _ => not_one(t.sizes()),
+                };
+                let mut rank_combination = rank_combination.clone();
+                for rank in ranks {
+                    rank_combination.entry(rank).or_insert_with(|| open(rank));
+                }
+                rank_combination
+            })
+            .collect::<Vec<_>>();
+        let adjoint_combination = not_one(adjoint.sizes())
+            .into_iter()
+            .map(|rank| (rank, open(rank)))
+            .collect::<HashMap<_, _>>();
+
+        terms
+            .iter()
+            .enumerate()
+            .map(|(i, t)| {
+                match t {
+                    Expression::Constant(_) => return Ok(None),
+                    Expression::Tensor(v) if v.delta_pairs().is_some() => return Ok(None),
+                    _ => {}
+                }
+
+                let (others, others_combinations): (Vec<_>, Vec<_>) = terms
+                    .iter()
+                    .zip(opened.iter())
+                    .enumerate()
+                    .filter(|&(j, _)| j != i)
+                    .map(|(_, (t, r))| (t.clone(), r.clone()))
+                    .chain(once((adjoint.clone(), adjoint_combination.clone())))
+                    .unzip();
+                let mut kept = opened[i]
+                    .iter()
+                    .map(|(&rank, id)| (rank, id.to_owned()))
+                    .collect::<Vec<_>>();
+                kept.sort();
+
+                TensorExpression::try_dot_product_keeping(others, others_combinations, &kept)
+                    .map(Some)
+            })
+            .collect()
+    }
+
+    /// The adjoints of the terms of a dot product, given their values and the adjoint of the product.
+    /// Each term gets the contraction of the adjoint with the other terms, so the Kronecker deltas, which have no value, get none.
+    pub(crate) fn dot_product_adjoints(
+        terms: &[Expression],
+        rank_combinations: &[HashMap<RankIndex, String>],
+        values: &[Option<ConstantValue>],
+        adjoint: &ConstantValue,
+    ) -> Result<Vec<Option<ConstantValue>>, SymbolicError> {
+        let open = |rank: RankIndex| format!("${}", rank);
+
+        let contraction_terms = terms
+            .iter()
+            .zip(values.iter())
+            .map(|(t, v)| match (t, v) {
+                (_, Some(v)) => Ok(ContractionTerm::Value(v.to_tensor())),
+                (Expression::Tensor(t), None) => match t.delta_pairs() {
+                    Some(rank_pairs) => Ok(ContractionTerm::Deltas(rank_pairs)),
+                    None => Err(SymbolicError::UndeterminedSize),
+                },
+                _ => Err(SymbolicError::UndeterminedSize),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // The ranks left uncombined in the product are combined with the same ranks of the adjoint.
+        let opened = contraction_terms
+            .iter()
+            .zip(rank_combinations.iter())
+            .map(|(t, rank_combination)| {
+                let ranks = match t {
+                    ContractionTerm::Value(v) => {
+                        (0..v.rank()).filter(|&rank| v.size(rank) != 1).collect()
+                    }
+                    ContractionTerm::Deltas(rank_pairs) => rank_pairs
+                        .iter()
+                        .flat_map(|(pair, _)| pair)
+                        .copied()
+                        .collect::<Vec<_>>(),
+                };
+                let mut rank_combination = rank_combination.clone();
+                for rank in ranks {
+                    rank_combination.entry(rank).or_insert_with(|| open(rank));
+                }
+                rank_combination
+            })
+            .collect::<Vec<_>>();
+
+        let adjoint = adjoint.to_tensor();
+        let adjoint_combination = (0..adjoint.rank())
+            .filter(|&rank| adjoint.size(rank) != 1)
+            .map(|rank| (rank, open(rank)))
+            .collect::<HashMap<_, _>>();
+
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, v)| {
+                if v.is_none() {
+                    return Ok(None);
+                }
+
+                let (others, others_combinations): (Vec<_>, Vec<_>) = contraction_terms
+                    .iter()
+                    .zip(opened.iter())
+                    .enumerate()
+                    .filter(|&(j, _)| j != i)
+                    .map(|(_, (t, r))| (t.clone(), r.clone()))
+                    .chain(once((
+                        ContractionTerm::Value(adjoint.clone()),
+                        adjoint_combination.clone(),
+                    )))
+                    .unzip();
+
+                contract(&others, &others_combinations, &opened[i]).map(Some)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use opensrdk_linear_algebra::{sparse::SparseTensor, Matrix};
+
+    use super::DotProduct;
+    use crate::{
+        new_variable_tensor, ConstantValue, Expression, Size, SymbolicError, TensorExpression,
+    };
+
+    #[test]
+    fn it_works1() {
+        let a = Expression::from(vec![1.0, 2.0, 3.0]);
+        let b = Expression::from(vec![4.0, 5.0, 6.0]);
+
+        let result = a.dot(b, &[[0, 0]]);
+
+        assert_eq!(result, Expression::from(vec![32.0]));
+    }
+
+    #[test]
+    fn it_works2() {
+        let a = Matrix::from(2, vec![1.0, 3.0, 2.0, 4.0]).unwrap();
+        let b = Matrix::from(2, vec![0.0, 1.0, 1.0, 0.0]).unwrap();
+        let x = new_variable_tensor("x".to_string(), vec![Size::Many]);
+
+        let expression = Expression::from(a)
+            .dot(Expression::from(b), &[[1, 0]])
+            .dot(x, &[[1, 0]]);
+
+        if let Expression::Tensor(t) = &expression {
+            if let TensorExpression::DotProduct { terms, .. } = t.as_ref() {
+                assert_eq!(terms.len(), 2);
+            } else {
+                panic!("{:?}", expression);
+            }
+        }
+
+        let mut hash = HashMap::new();
+        hash.insert("x", ConstantValue::Tensor(vec![1.0, 2.0].into()));
+
+        // [[1 2] [3 4]] [[0 1] [1 0]] [1 2]^T = [4 10]^T
+        let result = expression.evaluate(&hash).unwrap();
+        let mut elems = HashMap::new();
+        elems.insert(vec![0, 0], 4.0);
+        elems.insert(vec![1, 0], 10.0);
+        assert_eq!(
+            result,
+            ConstantValue::Tensor(SparseTensor::from(vec![2, 1], elems).unwrap())
+        );
+    }
+
+    #[test]
+    fn it_works3() {
+        let a = Expression::from(vec![1.0, 2.0, 3.0]);
+        let x = new_variable_tensor("x".to_string(), vec![Size::Many]);
+
+        let diff = a.dot(x, &[[0, 0]]).differential(&["x"])[0].clone();
+
+        let mut elems = HashMap::new();
+        elems.insert(vec![0, 0], 1.0);
+        elems.insert(vec![0, 1], 2.0);
+        elems.insert(vec![0, 2], 3.0);
+        assert_eq!(
+            diff,
+            Expression::from(SparseTensor::from(vec![1, 3], elems).unwrap())
+        );
+    }
+
+    #[test]
+    fn it_works4() {
+        let a = Matrix::from(2, vec![1.0, 3.0, 2.0, 4.0]).unwrap();
+
+        let result = Expression::from(a).tr();
+
+        assert_eq!(
+            result,
+            Expression::from(
+                SparseTensor::from(vec![1, 1], vec![(vec![0, 0], 5.0)].into_iter().collect())
+                    .unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn it_works5() {
+        let x = new_variable_tensor("x".to_string(), vec![Size::Many]);
+        let a = new_variable_tensor("a".to_string(), vec![Size::Many, Size::Many]);
+        let expression = x.clone().dot(a, &[[0, 0]]).dot(x, &[[1, 0]]);
+
+        let mut hash = HashMap::new();
+        hash.insert("x", ConstantValue::Tensor(vec![1.0, 2.0].into()));
+        hash.insert(
+            "a",
+            ConstantValue::Matrix(Matrix::from(2, vec![1.0, 3.0, 2.0, 4.0]).unwrap()),
+        );
+
+        let result = expression.assign(&hash);
+
+        assert_eq!(
+            result,
+            Expression::from(
+                SparseTensor::from(vec![1, 1], vec![(vec![0, 0], 27.0)].into_iter().collect())
+                    .unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn it_works6() {
+        let x = new_variable_tensor("x".to_string(), vec![Size::Many]);
+        let y = new_variable_tensor("y".to_string(), vec![Size::Many]);
+
+        let result = TensorExpression::try_not_1dimension_ranks_in_dot_product(
+            &vec![x.clone(), y.clone()],
+            &vec![HashMap::new(), HashMap::new()],
+        );
+        assert!(matches!(
+            result,
+            Err(SymbolicError::RankConflict {
+                rank: 0,
+                terms: [0, 1]
+            })
+        ));
+        assert!(x.try_dot(y, &[[0, 0]]).is_ok());
+    }
+
+    #[test]
+    fn it_works7() {
+        let x = new_variable_tensor("x".to_string(), vec![Size::Many]);
+        let a = new_variable_tensor("a".to_string(), vec![Size::Many, Size::Many]);
+
+        // d tr(A) / dA = I
+        let diff = a.clone().tr().differential(&["a"])[0].clone();
+        let delta_23 = Expression::from(TensorExpression::KroneckerDeltas(vec![[2, 3]]));
+        assert_eq!(
+            diff,
+            vec![delta_23].into_iter().dot_product(&[HashMap::new()])
+        );
+
+        // d x^T A x / dx = A^T x + A x
+        let expression = x
+            .clone()
+            .dot(a.clone(), &[[0, 0]])
+            .dot(x.clone(), &[[1, 0]]);
+        let diff = expression.differential(&["x"])[0].clone();
+        let symbols = vec![("x", "x"), ("a", "A")].into_iter().collect();
+        assert!(!diff.tex_code(&symbols).contains(r"\delta"));
+
+        let mut hash = HashMap::new();
+        hash.insert("x", ConstantValue::Tensor(vec![1.0, 2.0].into()));
+        hash.insert(
+            "a",
+            ConstantValue::Matrix(Matrix::from(2, vec![1.0, 3.0, 2.0, 4.0]).unwrap()),
+        );
+        // [[1 2] [3 4]] + [[1 3] [2 4]] = [[2 5] [5 8]]
+        let result = diff.evaluate(&hash).unwrap().to_tensor();
+        assert_eq!(result[&[0, 0]], 12.0);
+        assert_eq!(result[&[0, 1]], 21.0);
+    }
+
+    #[test]
+    fn it_works8() {
+        let a = new_variable_tensor("a".to_string(), vec![Size::Many, Size::Many]);
+        let b = new_variable_tensor("b".to_string(), vec![Size::Many]);
+        let combination = |pairs: &[(usize, &str)]| {
+            pairs
+                .iter()
+                .map(|&(rank, id)| (rank, id.to_owned()))
+                .collect::<HashMap<_, _>>()
+        };
+        let delta_01 = Expression::from(TensorExpression::KroneckerDeltas(vec![[0, 1]]));
+
+        // sum_p sum_q delta_p,j a_p,q b_q = sum_q a^T_q,j b_q
+        let expression = vec![delta_01.clone(), a.clone(), b.clone()]
+            .into_iter()
+            .dot_product(&[
+                combination(&[(0, "p")]),
+                combination(&[(0, "p"), (1, "q")]),
+                combination(&[(0, "q")]),
+            ]);
+        let expected = vec![a.clone().t(), b.clone()]
+            .into_iter()
+            .dot_product(&[combination(&[(0, "q")]), combination(&[(0, "q")])]);
+        assert_eq!(expression, expected);
+
+        // sum_p sum_q delta_p,q a_p,q = tr(a)
+        let expression = vec![delta_01.clone(), a.clone()].into_iter().dot_product(&[
+            combination(&[(0, "p"), (1, "q")]),
+            combination(&[(0, "p"), (1, "q")]),
+        ]);
+        let expected = vec![a.clone()]
+            .into_iter()
+            .dot_product(&[combination(&[(0, "p"), (1, "p")])]);
+        assert_eq!(expression, expected);
+
+        // sum_p delta_p,p = n
+        let delta_3 = Expression::from(TensorExpression::SizedKroneckerDeltas(vec![(
+            [0, 1],
+            Size::Fixed(3),
+        )]));
+        let expression = vec![delta_3]
+            .into_iter()
+            .dot_product(&[combination(&[(0, "p"), (1, "p")])]);
+        assert_eq!(expression, 3.0.into());
+        assert_eq!(
+            expression.evaluate(&HashMap::new()).unwrap(),
+            ConstantValue::Scalar(3.0)
+        );
+
+        // The dimension of the delta is known once the variable sharing it is assigned.
+        let c = new_variable_tensor("c".to_string(), vec![Size::from("n")]);
+        let delta_n = Expression::from(TensorExpression::SizedKroneckerDeltas(vec![(
+            [0, 1],
+            Size::from("n"),
+        )]));
+        let expression = vec![delta_n, c.clone()]
+            .into_iter()
+            .dot_product(&[combination(&[(0, "p"), (1, "p")]), HashMap::new()]);
+        let mut hash = HashMap::new();
+        hash.insert("c", ConstantValue::Tensor(vec![1.0, 2.0, 3.0, 4.0].into()));
+        assert_eq!(
+            expression.assign(&hash),
+            (4.0 * Expression::from(vec![1.0, 2.0, 3.0, 4.0]))
+        );
+
+        // The delta traced with itself stays while its dimension is unknown.
+        let expression = vec![delta_01.clone()]
+            .into_iter()
+            .dot_product(&[combination(&[(0, "p"), (1, "p")])]);
+        assert!(matches!(
+            expression.evaluate(&HashMap::new()),
+            Err(SymbolicError::UndeterminedSize)
+        ));
+        let expression = vec![delta_01, b.clone()]
+            .into_iter()
+            .dot_product(&[combination(&[(0, "p"), (1, "p")]), combination(&[(0, "p")])]);
+        assert_eq!(
+            expression,
+            vec![b].into_iter().dot_product(&[combination(&[(0, "p")])])
+        );
+    }
+
+    #[test]
+    fn it_works9() {
+        let u = Expression::from(vec![1.0, 2.0, 3.0]);
+        let v = Expression::from(vec![4.0, 5.0]);
+        let delta_01 = Expression::from(TensorExpression::KroneckerDeltas(vec![[0, 1]]));
+        let combination = |pairs: &[(usize, &str)]| {
+            pairs
+                .iter()
+                .map(|&(rank, id)| (rank, id.to_owned()))
+                .collect::<HashMap<_, _>>()
+        };
+
+        // The delta equates the ranks of the constants, which differ in size.
+        let result = vec![delta_01, u, v].into_iter().try_dot_product(&[
+            combination(&[(0, "p"), (1, "q")]),
+            combination(&[(0, "p")]),
+            combination(&[(0, "q")]),
+        ]);
+        assert!(matches!(result, Err(SymbolicError::DimensionMismatch)));
     }
 }
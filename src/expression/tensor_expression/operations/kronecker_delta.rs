@@ -1,7 +1,29 @@
-use crate::{BracketsLevel, TensorExpression};
+use crate::{BracketsLevel, Size, TensorExpression};
 use opensrdk_linear_algebra::RankIndex;
 
 impl TensorExpression {
+    /// Kronecker deltas between the pairs of ranks, kept as `KroneckerDeltas` when none of the sizes is known.
+    pub fn kronecker_deltas(rank_pairs: Vec<([RankIndex; 2], Size)>) -> TensorExpression {
+        if rank_pairs.iter().all(|(_, size)| size == &Size::Many) {
+            return TensorExpression::KroneckerDeltas(
+                rank_pairs.into_iter().map(|(pair, _)| pair).collect(),
+            );
+        }
+
+        TensorExpression::SizedKroneckerDeltas(rank_pairs)
+    }
+
+    /// The pairs of ranks of Kronecker deltas with their sizes, which are `Many` when unknown.
+    pub fn delta_pairs(&self) -> Option<Vec<([RankIndex; 2], Size)>> {
+        match self {
+            TensorExpression::KroneckerDeltas(rank_pairs) => {
+                Some(rank_pairs.iter().map(|&pair| (pair, Size::Many)).collect())
+            }
+            TensorExpression::SizedKroneckerDeltas(rank_pairs) => Some(rank_pairs.clone()),
+            _ => None,
+        }
+    }
+
     pub(crate) fn tex_code_kronecker_deltas(
         rank_pairs: &[[RankIndex; 2]],
         brackets_level: BracketsLevel,
@@ -20,3 +42,33 @@ impl TensorExpression {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{Size, TensorExpression};
+
+    #[test]
+    fn it_works() {
+        let unsized_deltas = TensorExpression::kronecker_deltas(vec![([0, 1], Size::Many)]);
+        assert_eq!(
+            unsized_deltas,
+            TensorExpression::KroneckerDeltas(vec![[0, 1]])
+        );
+        assert_eq!(
+            unsized_deltas.delta_pairs(),
+            Some(vec![([0, 1], Size::Many)])
+        );
+
+        let sized_deltas = TensorExpression::kronecker_deltas(vec![
+            ([0, 1], Size::Many),
+            ([2, 3], Size::Fixed(3)),
+        ]);
+        assert_eq!(
+            sized_deltas,
+            TensorExpression::SizedKroneckerDeltas(vec![
+                ([0, 1], Size::Many),
+                ([2, 3], Size::Fixed(3))
+            ])
+        );
+    }
+}
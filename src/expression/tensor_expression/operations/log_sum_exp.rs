@@ -0,0 +1,172 @@
+use crate::{BracketsLevel, ConstantValue, Expression, Size, SymbolicError, TensorExpression};
+use opensrdk_linear_algebra::{indices_cartesian_product, sparse::SparseTensor, RankIndex};
+use std::collections::HashMap;
+
+pub(crate) fn log_sum_exp(fiber: &[f64]) -> Vec<f64> {
+    // Shifting by the maximum keeps `exp` from overflowing.
+    let max = fiber.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    if max.is_infinite() {
+        return vec![max];
+    }
+
+    vec![max + fiber.iter().map(|v| (v - max).exp()).sum::<f64>().ln()]
+}
+
+impl Expression {
+    /// `ln(sum(exp(self)))` over `rank`, which is left with size 1.
+    pub fn try_log_sum_exp(self, rank: RankIndex) -> Result<Expression, SymbolicError> {
+        if let Expression::Constant(v) = &self {
+            return Ok(v.map_rank(rank, log_sum_exp)?.into());
+        }
+        if self.sizes().get(rank).unwrap_or(&Size::One).is_one() {
+            return Ok(self);
+        }
+
+        Ok(TensorExpression::LogSumExp { arg: self, rank }.into())
+    }
+
+    pub fn log_sum_exp(self, rank: RankIndex) -> Expression {
+        self.try_log_sum_exp(rank)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+}
+
+impl TensorExpression {
+    pub(crate) fn diff_log_sum_exp(
+        arg: &Expression,
+        rank: RankIndex,
+        symbols: &[&str],
+        rank_offset: usize,
+    ) -> Vec<Expression> {
+        let softmax = arg.clone().softmax(rank);
+        arg._differential(symbols, rank_offset.max(Size::effective_rank(&arg.sizes())))
+            .into_iter()
+            .map(|d| softmax.clone().dot(d, &[[rank, rank]]))
+            .collect()
+    }
+
+    pub(crate) fn size_log_sum_exp(arg: &Expression, rank: RankIndex) -> Vec<Size> {
+        let mut sizes = arg.sizes();
+        if rank < sizes.len() {
+            sizes[rank] = Size::One;
+        }
+        sizes
+    }
+
+    pub(crate) fn evaluate_log_sum_exp(
+        arg: &Expression,
+        rank: RankIndex,
+        variables: &HashMap<&str, ConstantValue>,
+    ) -> Result<ConstantValue, SymbolicError> {
+        arg._evaluate(variables)?.map_rank(rank, log_sum_exp)
+    }
+
+    /// The adjoint of the argument of a log-sum-exp valued `value`, which is the adjoint spread over `rank` by the softmax.
+    pub(crate) fn log_sum_exp_adjoint(
+        arg: &ConstantValue,
+        value: &ConstantValue,
+        rank: RankIndex,
+        adjoint: &ConstantValue,
+    ) -> Result<ConstantValue, SymbolicError> {
+        let sizes = arg.sizes();
+        if rank >= sizes.len() {
+            return Ok(adjoint.clone());
+        }
+
+        let (a, v, g) = (arg.to_tensor(), value.to_tensor(), adjoint.to_tensor());
+        let elems = indices_cartesian_product(&sizes)
+            .into_iter()
+            .filter_map(|indices| {
+                let mut reduced = indices.clone();
+                reduced[rank] = 0;
+                let e = g[&reduced] * (a[&indices] - v[&reduced]).exp();
+                (e != 0.0).then_some((indices, e))
+            })
+            .collect();
+
+        Ok(ConstantValue::Tensor(SparseTensor::from(sizes, elems)?))
+    }
+
+    /// The derivative of the log-sum-exp along `tangent` of its argument, which is the tangent averaged by the softmax along the rank.
+    pub(crate) fn log_sum_exp_tangent(
+        arg: &ConstantValue,
+        value: &ConstantValue,
+        rank: RankIndex,
+        tangent: &ConstantValue,
+    ) -> Result<ConstantValue, SymbolicError> {
+        let sizes = arg.sizes();
+        if rank >= sizes.len() {
+            return Ok(tangent.clone());
+        }
+
+        let (a, v, t) = (arg.to_tensor(), value.to_tensor(), tangent.to_tensor());
+        let mut elems = HashMap::<Vec<usize>, f64>::new();
+        for indices in indices_cartesian_product(&sizes) {
+            let mut reduced = indices.clone();
+            reduced[rank] = 0;
+            let e = t[&indices] * (a[&indices] - v[&reduced]).exp();
+            if e != 0.0 {
+                *elems.entry(reduced).or_insert(0.0) += e;
+            }
+        }
+
+        Ok(ConstantValue::Tensor(SparseTensor::from(
+            value.sizes(),
+            elems,
+        )?))
+    }
+
+    pub(crate) fn tex_code_log_sum_exp(
+        arg: &Expression,
+        rank: RankIndex,
+        symbols: &HashMap<&str, &str>,
+    ) -> String {
+        format!(
+            r"\operatorname{{logsumexp}}_{{[{}]}}\left({}\right)",
+            rank,
+            arg._tex_code(symbols, BracketsLevel::None)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{new_variable_tensor, ConstantValue, Expression, Size};
+
+    #[test]
+    fn it_works() {
+        let x = new_variable_tensor("x".to_string(), vec![Size::Many]);
+        let expression = x.log_sum_exp(0);
+        assert_eq!(expression.sizes(), vec![Size::One]);
+
+        let mut hash = HashMap::new();
+        hash.insert("x", ConstantValue::Tensor(vec![1000.0, 1000.0].into()));
+
+        let result = expression.evaluate(&hash).unwrap();
+        assert_eq!(result.elems(), vec![1000.0 + 2f64.ln()]);
+
+        let gradient = expression.differential(&["x"])[0].evaluate(&hash).unwrap();
+        assert_eq!(gradient.sizes(), vec![1, 2]);
+        assert_eq!(gradient.elems().iter().sum::<f64>(), 1.0);
+        assert!(gradient.elems().iter().all(|&e| (e - 0.5).abs() < 1e-12));
+    }
+
+    #[test]
+    fn it_works2() {
+        let a = Expression::from(vec![1.0, 2.0, 3.0]);
+        let expected = (1f64.exp() + 2f64.exp() + 3f64.exp()).ln();
+
+        let result = a.log_sum_exp(0).evaluate(&HashMap::new()).unwrap();
+        assert!((result.elems()[0] - expected).abs() < 1e-12);
+
+        let x = new_variable_tensor("x".to_string(), vec![Size::One, Size::Many]);
+        assert_eq!(x.clone().log_sum_exp(0), x);
+        assert_eq!(x.clone().try_log_sum_exp(2).unwrap(), x);
+
+        let a = Expression::from(vec![f64::NEG_INFINITY, f64::NEG_INFINITY]);
+        let result = a.log_sum_exp(0).evaluate(&HashMap::new()).unwrap();
+        assert_eq!(result.elems(), vec![f64::NEG_INFINITY]);
+    }
+}
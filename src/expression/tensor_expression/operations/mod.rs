@@ -1,6 +1,8 @@
 pub mod direct;
 pub mod dot;
 pub mod kronecker_delta;
+pub mod log_sum_exp;
+pub mod softmax;
 
 pub use direct::*;
 pub use dot::*;
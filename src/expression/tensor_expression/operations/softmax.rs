@@ -0,0 +1,195 @@
+use crate::{BracketsLevel, ConstantValue, Expression, Size, SymbolicError, TensorExpression};
+use opensrdk_linear_algebra::{indices_cartesian_product, sparse::SparseTensor, RankIndex};
+use std::collections::HashMap;
+
+use super::DotProduct;
+
+pub(crate) fn softmax(fiber: &[f64]) -> Vec<f64> {
+    // Shifting by the maximum keeps `exp` from overflowing.
+    let max = fiber.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    if max.is_infinite() {
+        // The shift gives NaN, so the mass is shared by the values equal to the maximum, which are all of them for -inf.
+        let count = fiber.iter().filter(|&&v| v == max).count() as f64;
+        return fiber
+            .iter()
+            .map(|&v| if v == max { 1.0 / count } else { 0.0 })
+            .collect();
+    }
+    let exp = fiber.iter().map(|v| (v - max).exp()).collect::<Vec<_>>();
+    let sum = exp.iter().sum::<f64>();
+
+    exp.into_iter().map(|e| e / sum).collect()
+}
+
+impl Expression {
+    /// `exp(self) / sum(exp(self))` normalized over `rank`.
+    pub fn try_softmax(self, rank: RankIndex) -> Result<Expression, SymbolicError> {
+        if let Expression::Constant(v) = &self {
+            return Ok(v.map_rank(rank, softmax)?.into());
+        }
+        let sizes = self.sizes();
+        if sizes.get(rank).unwrap_or(&Size::One).is_one() {
+            // The fibers of length 1 are ones, which keep the sizes when they are known.
+            let lens = sizes
+                .iter()
+                .map(|size| match size {
+                    Size::One => Some(1),
+                    Size::Fixed(len) => Some(*len),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>();
+            if let Some(lens) = lens {
+                if lens.is_empty() {
+                    return Ok(1.0.into());
+                }
+                let elems = indices_cartesian_product(&lens)
+                    .into_iter()
+                    .map(|indices| (indices, 1.0))
+                    .collect();
+                return Ok(SparseTensor::from(lens, elems)?.into());
+            }
+        }
+
+        Ok(TensorExpression::Softmax { arg: self, rank }.into())
+    }
+
+    pub fn softmax(self, rank: RankIndex) -> Expression {
+        self.try_softmax(rank).unwrap_or_else(|e| panic!("{}", e))
+    }
+}
+
+impl TensorExpression {
+    pub(crate) fn diff_softmax(
+        arg: &Expression,
+        rank: RankIndex,
+        symbols: &[&str],
+        rank_offset: usize,
+    ) -> Vec<Expression> {
+        // d softmax(x)_i = softmax(x)_i (dx_i - sum_j softmax(x)_j dx_j)
+        let softmax = arg.clone().softmax(rank);
+        let elementwise = vec![HashMap::new(), HashMap::new()];
+
+        arg._differential(symbols, rank_offset)
+            .into_iter()
+            .map(|d| {
+                let expectation = softmax.clone().dot(d.clone(), &[[rank, rank]]);
+
+                vec![softmax.clone(), d]
+                    .into_iter()
+                    .dot_product(&elementwise)
+                    - vec![softmax.clone(), expectation]
+                        .into_iter()
+                        .dot_product(&elementwise)
+            })
+            .collect()
+    }
+
+    pub(crate) fn evaluate_softmax(
+        arg: &Expression,
+        rank: RankIndex,
+        variables: &HashMap<&str, ConstantValue>,
+    ) -> Result<ConstantValue, SymbolicError> {
+        arg._evaluate(variables)?.map_rank(rank, softmax)
+    }
+
+    /// The adjoint of the argument of a softmax valued `value`.
+    pub(crate) fn softmax_adjoint(
+        value: &ConstantValue,
+        rank: RankIndex,
+        adjoint: &ConstantValue,
+    ) -> Result<ConstantValue, SymbolicError> {
+        let mut sizes = value.sizes();
+        if rank >= sizes.len() {
+            return Ok(value.clone().map(|_| 0.0));
+        }
+
+        let (s, g) = (value.to_tensor(), adjoint.to_tensor());
+        let len = sizes[rank];
+        sizes[rank] = 1;
+        let mut elems = HashMap::new();
+        for mut indices in indices_cartesian_product(&sizes) {
+            let expectation = (0..len)
+                .map(|j| {
+                    indices[rank] = j;
+                    s[&indices] * g[&indices]
+                })
+                .sum::<f64>();
+            for j in 0..len {
+                indices[rank] = j;
+                let e = s[&indices] * (g[&indices] - expectation);
+                if e != 0.0 {
+                    elems.insert(indices.clone(), e);
+                }
+            }
+        }
+        sizes[rank] = len;
+
+        Ok(ConstantValue::Tensor(SparseTensor::from(sizes, elems)?))
+    }
+
+    pub(crate) fn tex_code_softmax(
+        arg: &Expression,
+        rank: RankIndex,
+        symbols: &HashMap<&str, &str>,
+    ) -> String {
+        format!(
+            r"\operatorname{{softmax}}_{{[{}]}}\left({}\right)",
+            rank,
+            arg._tex_code(symbols, BracketsLevel::None)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{new_variable_tensor, ConstantValue, Expression, Size};
+
+    #[test]
+    fn it_works() {
+        let x = new_variable_tensor("x".to_string(), vec![Size::Many]);
+        let expression = x.softmax(0);
+
+        let mut hash = HashMap::new();
+        hash.insert("x", ConstantValue::Tensor(vec![1.0, 2.0].into()));
+
+        let e = [1f64.exp(), 2f64.exp()];
+        let s = [e[0] / (e[0] + e[1]), e[1] / (e[0] + e[1])];
+
+        let result = expression.evaluate(&hash).unwrap().to_tensor();
+        assert!((result[&[0]] - s[0]).abs() < 1e-12);
+        assert!((result[&[1]] - s[1]).abs() < 1e-12);
+
+        // The jacobian is diag(s) - s s^T.
+        let jacobian = expression.differential(&["x"])[0]
+            .evaluate(&hash)
+            .unwrap()
+            .to_tensor();
+        for i in 0..2 {
+            for j in 0..2 {
+                let expected = if i == j { s[i] } else { 0.0 } - s[i] * s[j];
+                assert!((jacobian[&[i, j]] - expected).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn it_works2() {
+        let a = Expression::from(vec![f64::NEG_INFINITY, f64::NEG_INFINITY]);
+        let result = a.softmax(0).evaluate(&HashMap::new()).unwrap();
+        assert_eq!(result.elems(), vec![0.5, 0.5]);
+
+        let a = Expression::from(vec![f64::INFINITY, 1.0]);
+        let result = a.softmax(0).evaluate(&HashMap::new()).unwrap().to_tensor();
+        assert_eq!((result[&[0]], result[&[1]]), (1.0, 0.0));
+
+        let x = new_variable_tensor("x".to_string(), vec![Size::Fixed(3), Size::One]);
+        let ones = x.try_softmax(1).unwrap();
+        assert_eq!(ones.sizes(), vec![Size::Fixed(3), Size::One]);
+        assert_eq!(
+            ones.evaluate(&HashMap::new()).unwrap().elems(),
+            vec![1.0; 3]
+        );
+    }
+}
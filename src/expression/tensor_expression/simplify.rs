@@ -0,0 +1,24 @@
+use super::operations::{DirectProduct, DotProduct};
+use crate::{Expression, TensorExpression};
+
+impl TensorExpression {
+    pub fn simplify(&self) -> Expression {
+        match self {
+            TensorExpression::KroneckerDeltas(_) | TensorExpression::SizedKroneckerDeltas(_) => {
+                self.clone().into()
+            }
+            TensorExpression::DotProduct {
+                terms,
+                rank_combinations,
+            } => terms
+                .iter()
+                .map(|t| t.simplify())
+                .dot_product(rank_combinations),
+            TensorExpression::DirectProduct(terms) => {
+                terms.iter().map(|t| t.simplify()).direct_product()
+            }
+            TensorExpression::LogSumExp { arg, rank } => arg.simplify().log_sum_exp(*rank),
+            TensorExpression::Softmax { arg, rank } => arg.simplify().softmax(*rank),
+        }
+    }
+}
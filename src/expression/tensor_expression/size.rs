@@ -3,12 +3,18 @@ use crate::{Size, TensorExpression};
 impl TensorExpression {
     pub fn sizes(&self) -> Vec<Size> {
         match self {
-            TensorExpression::KroneckerDeltas(_) => vec![],
+            TensorExpression::KroneckerDeltas(_) | TensorExpression::SizedKroneckerDeltas(_) => {
+                vec![]
+            }
             TensorExpression::DotProduct {
                 terms,
                 rank_combinations,
             } => TensorExpression::size_dot_product(terms, rank_combinations),
             TensorExpression::DirectProduct(terms) => TensorExpression::size_direct_product(terms),
+            TensorExpression::LogSumExp { arg, rank } => {
+                TensorExpression::size_log_sum_exp(arg, *rank)
+            }
+            TensorExpression::Softmax { arg, rank: _ } => arg.sizes(),
         }
     }
 }
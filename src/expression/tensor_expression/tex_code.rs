@@ -11,6 +11,12 @@ impl TensorExpression {
             TensorExpression::KroneckerDeltas(rank_pairs) => {
                 TensorExpression::tex_code_kronecker_deltas(rank_pairs, brackets_level)
             }
+            TensorExpression::SizedKroneckerDeltas(rank_pairs) => {
+                TensorExpression::tex_code_kronecker_deltas(
+                    &rank_pairs.iter().map(|(pair, _)| *pair).collect::<Vec<_>>(),
+                    brackets_level,
+                )
+            }
             TensorExpression::DotProduct {
                 terms,
                 rank_combinations,
@@ -18,6 +24,12 @@ impl TensorExpression {
             TensorExpression::DirectProduct(terms) => {
                 TensorExpression::tex_code_direct_product(terms, symbols, brackets_level)
             }
+            TensorExpression::LogSumExp { arg, rank } => {
+                TensorExpression::tex_code_log_sum_exp(arg, *rank, symbols)
+            }
+            TensorExpression::Softmax { arg, rank } => {
+                TensorExpression::tex_code_softmax(arg, *rank, symbols)
+            }
         }
     }
 
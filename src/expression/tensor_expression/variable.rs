@@ -1,14 +1,16 @@
 use crate::{Expression, Size, TensorExpression};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
-pub fn new_variable_tensor(id: String, sizes: Vec<Size>) -> Expression {
-    Expression::Variable(id, sizes)
+pub fn new_variable_tensor<S: Into<Size>>(id: String, sizes: Vec<S>) -> Expression {
+    Expression::Variable(id, sizes.into_iter().map(|s| s.into()).collect())
 }
 
 impl TensorExpression {
     pub fn variable_ids(&self) -> HashSet<&str> {
         match self {
-            TensorExpression::KroneckerDeltas(_) => HashSet::new(),
+            TensorExpression::KroneckerDeltas(_) | TensorExpression::SizedKroneckerDeltas(_) => {
+                HashSet::new()
+            }
             TensorExpression::DotProduct {
                 terms,
                 rank_combinations: _,
@@ -16,6 +18,25 @@ impl TensorExpression {
             TensorExpression::DirectProduct(terms) => {
                 terms.iter().map(|t| t.variable_ids()).flatten().collect()
             }
+            TensorExpression::LogSumExp { arg, rank: _ } => arg.variable_ids(),
+            TensorExpression::Softmax { arg, rank: _ } => arg.variable_ids(),
+        }
+    }
+
+    pub fn variable_sizes(&self) -> HashMap<&str, &[Size]> {
+        match self {
+            TensorExpression::KroneckerDeltas(_) | TensorExpression::SizedKroneckerDeltas(_) => {
+                HashMap::new()
+            }
+            TensorExpression::DotProduct {
+                terms,
+                rank_combinations: _,
+            } => terms.iter().flat_map(|t| t.variable_sizes()).collect(),
+            TensorExpression::DirectProduct(terms) => {
+                terms.iter().flat_map(|t| t.variable_sizes()).collect()
+            }
+            TensorExpression::LogSumExp { arg, rank: _ } => arg.variable_sizes(),
+            TensorExpression::Softmax { arg, rank: _ } => arg.variable_sizes(),
         }
     }
 }
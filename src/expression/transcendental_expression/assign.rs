@@ -1,21 +1,61 @@
-use crate::{ConstantValue, Expression, TranscendentalExpression};
+use crate::{ConstantValue, Expression, Size, SymbolicError, TranscendentalExpression};
 use std::collections::HashMap;
 
 impl TranscendentalExpression {
-    pub fn assign(self, variables: &HashMap<&str, ConstantValue>) -> Expression {
-        match self {
-            TranscendentalExpression::Abs(arg) => arg.assign(variables).abs(),
-            TranscendentalExpression::Pow(base, exponent) => {
-                base.assign(variables).pow(exponent.assign(variables))
-            }
-            TranscendentalExpression::Exp(arg) => arg.assign(variables).exp(),
-            TranscendentalExpression::Log(base, antilogarithm) => {
-                base.assign(variables).log(antilogarithm.assign(variables))
+    pub fn try_assign(
+        self,
+        variables: &HashMap<&str, ConstantValue>,
+    ) -> Result<Expression, SymbolicError> {
+        let dims = Size::bind_dims(self.variable_sizes(), variables)?;
+        self._assign(variables, &dims)
+    }
+
+    pub(crate) fn _assign(
+        self,
+        variables: &HashMap<&str, ConstantValue>,
+        dims: &HashMap<String, usize>,
+    ) -> Result<Expression, SymbolicError> {
+        Ok(match self {
+            TranscendentalExpression::Abs(arg) => arg._assign(variables, dims)?.abs(),
+            TranscendentalExpression::Pow(base, exponent) => base
+                ._assign(variables, dims)?
+                .try_pow(exponent._assign(variables, dims)?)?,
+            TranscendentalExpression::Exp(arg) => arg._assign(variables, dims)?.exp(),
+            TranscendentalExpression::Log(base, antilogarithm) => base
+                ._assign(variables, dims)?
+                .try_log(antilogarithm._assign(variables, dims)?)?,
+            TranscendentalExpression::Ln(arg) => arg._assign(variables, dims)?.ln(),
+            TranscendentalExpression::Sin(arg) => arg._assign(variables, dims)?.sin(),
+            TranscendentalExpression::Cos(arg) => arg._assign(variables, dims)?.cos(),
+            TranscendentalExpression::Tan(arg) => arg._assign(variables, dims)?.tan(),
+            TranscendentalExpression::Sign(arg) => arg._assign(variables, dims)?.sign(),
+            TranscendentalExpression::Heaviside(arg) => arg._assign(variables, dims)?.heaviside(),
+            TranscendentalExpression::Sinh(arg) => arg._assign(variables, dims)?.sinh(),
+            TranscendentalExpression::Cosh(arg) => arg._assign(variables, dims)?.cosh(),
+            TranscendentalExpression::Tanh(arg) => arg._assign(variables, dims)?.tanh(),
+            TranscendentalExpression::Asin(arg) => arg._assign(variables, dims)?.asin(),
+            TranscendentalExpression::Acos(arg) => arg._assign(variables, dims)?.acos(),
+            TranscendentalExpression::Atan(arg) => arg._assign(variables, dims)?.atan(),
+            TranscendentalExpression::Atan2(y, x) => y
+                ._assign(variables, dims)?
+                .try_atan2(x._assign(variables, dims)?)?,
+            TranscendentalExpression::Erf(arg) => arg._assign(variables, dims)?.erf(),
+            TranscendentalExpression::Erfc(arg) => arg._assign(variables, dims)?.erfc(),
+            TranscendentalExpression::Gamma(arg) => arg._assign(variables, dims)?.gamma(),
+            TranscendentalExpression::LnGamma(arg) => arg._assign(variables, dims)?.ln_gamma(),
+            TranscendentalExpression::Digamma(arg) => arg._assign(variables, dims)?.digamma(),
+            TranscendentalExpression::Sigmoid(arg) => arg._assign(variables, dims)?.sigmoid(),
+            TranscendentalExpression::Softplus(arg) => arg._assign(variables, dims)?.softplus(),
+            TranscendentalExpression::Log1p(arg) => arg._assign(variables, dims)?.ln_1p(),
+            TranscendentalExpression::Expm1(arg) => arg._assign(variables, dims)?.exp_m1(),
+            TranscendentalExpression::Polygamma(n, arg) => {
+                arg._assign(variables, dims)?.polygamma(n)
             }
-            TranscendentalExpression::Ln(arg) => arg.assign(variables).ln(),
-            TranscendentalExpression::Sin(arg) => arg.assign(variables).sin(),
-            TranscendentalExpression::Cos(arg) => arg.assign(variables).cos(),
-            TranscendentalExpression::Tan(arg) => arg.assign(variables).tan(),
-        }
+        })
+    }
+
+    pub fn assign(self, variables: &HashMap<&str, ConstantValue>) -> Expression {
+        self.try_assign(variables)
+            .unwrap_or_else(|e| panic!("{}", e))
     }
 }
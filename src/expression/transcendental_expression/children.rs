@@ -0,0 +1,112 @@
+use crate::{Expression, ExpressionRef, SymbolicError, TranscendentalExpression};
+
+impl TranscendentalExpression {
+    pub(crate) fn children(&self) -> Vec<Expression> {
+        match self {
+            TranscendentalExpression::Pow(l, r)
+            | TranscendentalExpression::Log(l, r)
+            | TranscendentalExpression::Atan2(l, r) => {
+                vec![l.as_ref().clone(), r.as_ref().clone()]
+            }
+            TranscendentalExpression::Polygamma(_, arg)
+            | TranscendentalExpression::Abs(arg)
+            | TranscendentalExpression::Exp(arg)
+            | TranscendentalExpression::Ln(arg)
+            | TranscendentalExpression::Sin(arg)
+            | TranscendentalExpression::Cos(arg)
+            | TranscendentalExpression::Tan(arg)
+            | TranscendentalExpression::Sign(arg)
+            | TranscendentalExpression::Heaviside(arg)
+            | TranscendentalExpression::Sinh(arg)
+            | TranscendentalExpression::Cosh(arg)
+            | TranscendentalExpression::Tanh(arg)
+            | TranscendentalExpression::Asin(arg)
+            | TranscendentalExpression::Acos(arg)
+            | TranscendentalExpression::Atan(arg)
+            | TranscendentalExpression::Erf(arg)
+            | TranscendentalExpression::Erfc(arg)
+            | TranscendentalExpression::Gamma(arg)
+            | TranscendentalExpression::LnGamma(arg)
+            | TranscendentalExpression::Digamma(arg)
+            | TranscendentalExpression::Sigmoid(arg)
+            | TranscendentalExpression::Softplus(arg)
+            | TranscendentalExpression::Log1p(arg)
+            | TranscendentalExpression::Expm1(arg) => vec![arg.as_ref().clone()],
+        }
+    }
+
+    pub(crate) fn with_children(&self, children: Vec<Expression>) -> Expression {
+        let mut children = children.into_iter().map(ExpressionRef::from);
+        let mut next = || children.next().unwrap();
+
+        match self {
+            TranscendentalExpression::Pow(_, _) => TranscendentalExpression::Pow(next(), next()),
+            TranscendentalExpression::Log(_, _) => TranscendentalExpression::Log(next(), next()),
+            TranscendentalExpression::Atan2(_, _) => {
+                TranscendentalExpression::Atan2(next(), next())
+            }
+            TranscendentalExpression::Polygamma(n, _) => {
+                TranscendentalExpression::Polygamma(*n, next())
+            }
+            TranscendentalExpression::Abs(_) => TranscendentalExpression::Abs(next()),
+            TranscendentalExpression::Exp(_) => TranscendentalExpression::Exp(next()),
+            TranscendentalExpression::Ln(_) => TranscendentalExpression::Ln(next()),
+            TranscendentalExpression::Sin(_) => TranscendentalExpression::Sin(next()),
+            TranscendentalExpression::Cos(_) => TranscendentalExpression::Cos(next()),
+            TranscendentalExpression::Tan(_) => TranscendentalExpression::Tan(next()),
+            TranscendentalExpression::Sign(_) => TranscendentalExpression::Sign(next()),
+            TranscendentalExpression::Heaviside(_) => TranscendentalExpression::Heaviside(next()),
+            TranscendentalExpression::Sinh(_) => TranscendentalExpression::Sinh(next()),
+            TranscendentalExpression::Cosh(_) => TranscendentalExpression::Cosh(next()),
+            TranscendentalExpression::Tanh(_) => TranscendentalExpression::Tanh(next()),
+            TranscendentalExpression::Asin(_) => TranscendentalExpression::Asin(next()),
+            TranscendentalExpression::Acos(_) => TranscendentalExpression::Acos(next()),
+            TranscendentalExpression::Atan(_) => TranscendentalExpression::Atan(next()),
+            TranscendentalExpression::Erf(_) => TranscendentalExpression::Erf(next()),
+            TranscendentalExpression::Erfc(_) => TranscendentalExpression::Erfc(next()),
+            TranscendentalExpression::Gamma(_) => TranscendentalExpression::Gamma(next()),
+            TranscendentalExpression::LnGamma(_) => TranscendentalExpression::LnGamma(next()),
+            TranscendentalExpression::Digamma(_) => TranscendentalExpression::Digamma(next()),
+            TranscendentalExpression::Sigmoid(_) => TranscendentalExpression::Sigmoid(next()),
+            TranscendentalExpression::Softplus(_) => TranscendentalExpression::Softplus(next()),
+            TranscendentalExpression::Log1p(_) => TranscendentalExpression::Log1p(next()),
+            TranscendentalExpression::Expm1(_) => TranscendentalExpression::Expm1(next()),
+        }
+        .into()
+    }
+
+    pub(crate) fn rebuilt(&self, children: Vec<Expression>) -> Result<Expression, SymbolicError> {
+        let mut children = children.into_iter();
+        let mut next = || children.next().unwrap();
+
+        match self {
+            TranscendentalExpression::Pow(_, _) => next().try_pow(next()),
+            TranscendentalExpression::Log(_, _) => next().try_log(next()),
+            TranscendentalExpression::Atan2(_, _) => next().try_atan2(next()),
+            TranscendentalExpression::Polygamma(n, _) => Ok(next().polygamma(*n)),
+            TranscendentalExpression::Abs(_) => Ok(next().abs()),
+            TranscendentalExpression::Exp(_) => Ok(next().exp()),
+            TranscendentalExpression::Ln(_) => Ok(next().ln()),
+            TranscendentalExpression::Sin(_) => Ok(next().sin()),
+            TranscendentalExpression::Cos(_) => Ok(next().cos()),
+            TranscendentalExpression::Tan(_) => Ok(next().tan()),
+            TranscendentalExpression::Sign(_) => Ok(next().sign()),
+            TranscendentalExpression::Heaviside(_) => Ok(next().heaviside()),
+            TranscendentalExpression::Sinh(_) => Ok(next().sinh()),
+            TranscendentalExpression::Cosh(_) => Ok(next().cosh()),
+            TranscendentalExpression::Tanh(_) => Ok(next().tanh()),
+            TranscendentalExpression::Asin(_) => Ok(next().asin()),
+            TranscendentalExpression::Acos(_) => Ok(next().acos()),
+            TranscendentalExpression::Atan(_) => Ok(next().atan()),
+            TranscendentalExpression::Erf(_) => Ok(next().erf()),
+            TranscendentalExpression::Erfc(_) => Ok(next().erfc()),
+            TranscendentalExpression::Gamma(_) => Ok(next().gamma()),
+            TranscendentalExpression::LnGamma(_) => Ok(next().ln_gamma()),
+            TranscendentalExpression::Digamma(_) => Ok(next().digamma()),
+            TranscendentalExpression::Sigmoid(_) => Ok(next().sigmoid()),
+            TranscendentalExpression::Softplus(_) => Ok(next().softplus()),
+            TranscendentalExpression::Log1p(_) => Ok(next().ln_1p()),
+            TranscendentalExpression::Expm1(_) => Ok(next().exp_m1()),
+        }
+    }
+}
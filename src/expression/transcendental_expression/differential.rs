@@ -1,17 +1,29 @@
-use crate::{Expression, TranscendentalExpression};
+use crate::{Expression, Size, TranscendentalExpression};
 
 impl TranscendentalExpression {
     pub fn differential(&self, variable_ids: &[&str]) -> Vec<Expression> {
+        self._differential(variable_ids, Size::effective_rank(&self.sizes()))
+    }
+
+    pub(crate) fn _differential(
+        &self,
+        variable_ids: &[&str],
+        rank_offset: usize,
+    ) -> Vec<Expression> {
         match self {
             TranscendentalExpression::Abs(arg) => arg
-                .differential(variable_ids)
+                ._differential(variable_ids, rank_offset)
                 .into_iter()
-                .map(|a| a.abs())
+                .map(|a| arg.as_ref().clone().sign() * a)
                 .collect(),
             TranscendentalExpression::Pow(base, exponent) => base
-                .differential(variable_ids)
+                ._differential(variable_ids, rank_offset)
                 .into_iter()
-                .zip(exponent.differential(variable_ids).into_iter())
+                .zip(
+                    exponent
+                        ._differential(variable_ids, rank_offset)
+                        .into_iter(),
+                )
                 .map(|(b, e)| {
                     base.as_ref().clone().pow(exponent.as_ref().clone())
                         * (e * base.as_ref().clone().ln()
@@ -19,31 +31,338 @@ impl TranscendentalExpression {
                 })
                 .collect(),
             TranscendentalExpression::Exp(arg) => arg
-                .differential(variable_ids)
+                ._differential(variable_ids, rank_offset)
+                .into_iter()
+                .map(|a| arg.as_ref().clone().exp() * a)
+                .collect(),
+            TranscendentalExpression::Log(base, antilogarithm) => base
+                ._differential(variable_ids, rank_offset)
                 .into_iter()
-                .map(|a| arg.clone().exp() * a)
+                .zip(antilogarithm._differential(variable_ids, rank_offset))
+                .map(|(b, a)| {
+                    // log_b(a) = ln(a) / ln(b)
+                    (a / antilogarithm.as_ref().clone()
+                        - b * Expression::from(self.clone()) / base.as_ref().clone())
+                        / base.as_ref().clone().ln()
+                })
                 .collect(),
-            TranscendentalExpression::Log(_, _) => todo!(),
             TranscendentalExpression::Ln(arg) => arg
-                .differential(variable_ids)
+                ._differential(variable_ids, rank_offset)
                 .into_iter()
                 .map(|a| a / arg.as_ref().clone())
                 .collect(),
             TranscendentalExpression::Sin(arg) => arg
-                .differential(variable_ids)
+                ._differential(variable_ids, rank_offset)
                 .into_iter()
-                .map(|a| arg.clone().cos() * a)
+                .map(|a| arg.as_ref().clone().cos() * a)
                 .collect(),
             TranscendentalExpression::Cos(arg) => arg
-                .differential(variable_ids)
+                ._differential(variable_ids, rank_offset)
                 .into_iter()
-                .map(|a| -arg.clone().sin() * a)
+                .map(|a| -arg.as_ref().clone().sin() * a)
                 .collect(),
             TranscendentalExpression::Tan(arg) => arg
-                .differential(variable_ids)
+                ._differential(variable_ids, rank_offset)
+                .into_iter()
+                .map(|a| a / (arg.as_ref().clone().cos().pow(2.0.into())))
+                .collect(),
+            TranscendentalExpression::Sinh(arg) => arg
+                ._differential(variable_ids, rank_offset)
+                .into_iter()
+                .map(|a| arg.as_ref().clone().cosh() * a)
+                .collect(),
+            TranscendentalExpression::Cosh(arg) => arg
+                ._differential(variable_ids, rank_offset)
+                .into_iter()
+                .map(|a| arg.as_ref().clone().sinh() * a)
+                .collect(),
+            TranscendentalExpression::Tanh(arg) => arg
+                ._differential(variable_ids, rank_offset)
+                .into_iter()
+                .map(|a| a / arg.as_ref().clone().cosh().pow(2.0.into()))
+                .collect(),
+            TranscendentalExpression::Asin(arg) => arg
+                ._differential(variable_ids, rank_offset)
+                .into_iter()
+                .map(|a| a / (1.0 - arg.as_ref().clone().pow(2.0.into())).pow(0.5.into()))
+                .collect(),
+            TranscendentalExpression::Acos(arg) => arg
+                ._differential(variable_ids, rank_offset)
+                .into_iter()
+                .map(|a| -a / (1.0 - arg.as_ref().clone().pow(2.0.into())).pow(0.5.into()))
+                .collect(),
+            TranscendentalExpression::Atan(arg) => arg
+                ._differential(variable_ids, rank_offset)
+                .into_iter()
+                .map(|a| a / (1.0 + arg.as_ref().clone().pow(2.0.into())))
+                .collect(),
+            TranscendentalExpression::Atan2(y, x) => y
+                ._differential(variable_ids, rank_offset)
+                .into_iter()
+                .zip(x._differential(variable_ids, rank_offset))
+                .map(|(dy, dx)| {
+                    (x.as_ref().clone() * dy - y.as_ref().clone() * dx)
+                        / (x.as_ref().clone().pow(2.0.into()) + y.as_ref().clone().pow(2.0.into()))
+                })
+                .collect(),
+            TranscendentalExpression::Erf(arg) => arg
+                ._differential(variable_ids, rank_offset)
+                .into_iter()
+                .map(|a| {
+                    2.0 / std::f64::consts::PI.sqrt()
+                        * (-arg.as_ref().clone().pow(2.0.into())).exp()
+                        * a
+                })
+                .collect(),
+            TranscendentalExpression::Erfc(arg) => arg
+                ._differential(variable_ids, rank_offset)
+                .into_iter()
+                .map(|a| {
+                    -2.0 / std::f64::consts::PI.sqrt()
+                        * (-arg.as_ref().clone().pow(2.0.into())).exp()
+                        * a
+                })
+                .collect(),
+            TranscendentalExpression::Gamma(arg) => arg
+                ._differential(variable_ids, rank_offset)
+                .into_iter()
+                .map(|a| arg.as_ref().clone().gamma() * arg.as_ref().clone().digamma() * a)
+                .collect(),
+            TranscendentalExpression::LnGamma(arg) => arg
+                ._differential(variable_ids, rank_offset)
+                .into_iter()
+                .map(|a| arg.as_ref().clone().digamma() * a)
+                .collect(),
+            TranscendentalExpression::Digamma(arg) => arg
+                ._differential(variable_ids, rank_offset)
                 .into_iter()
-                .map(|a| a / (arg.clone().cos().pow(2.0.into())))
+                .map(|a| arg.as_ref().clone().polygamma(1) * a)
+                .collect(),
+            TranscendentalExpression::Sigmoid(arg) => arg
+                ._differential(variable_ids, rank_offset)
+                .into_iter()
+                .map(|a| {
+                    arg.as_ref().clone().sigmoid() * (1.0 - arg.as_ref().clone().sigmoid()) * a
+                })
                 .collect(),
+            TranscendentalExpression::Softplus(arg) => arg
+                ._differential(variable_ids, rank_offset)
+                .into_iter()
+                .map(|a| arg.as_ref().clone().sigmoid() * a)
+                .collect(),
+            TranscendentalExpression::Log1p(arg) => arg
+                ._differential(variable_ids, rank_offset)
+                .into_iter()
+                .map(|a| a / (1.0 + arg.as_ref().clone()))
+                .collect(),
+            TranscendentalExpression::Expm1(arg) => arg
+                ._differential(variable_ids, rank_offset)
+                .into_iter()
+                .map(|a| arg.as_ref().clone().exp() * a)
+                .collect(),
+            TranscendentalExpression::Polygamma(n, arg) => arg
+                ._differential(variable_ids, rank_offset)
+                .into_iter()
+                .map(|a| arg.as_ref().clone().polygamma(n + 1) * a)
+                .collect(),
+            // The derivatives are zero except at the origin.
+            TranscendentalExpression::Sign(_) | TranscendentalExpression::Heaviside(_) => {
+                vec![0.0.into(); variable_ids.len()]
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{new_variable, ConstantValue, Expression};
+
+    #[test]
+    fn it_works() {
+        let x = new_variable("x".to_string());
+        let expression = Expression::from(2.0).log(x);
+        let diff = expression.differential(&["x"])[0].clone();
+
+        let mut hash = HashMap::new();
+        hash.insert("x", ConstantValue::Scalar(4.0));
+
+        let result = diff.evaluate(&hash).unwrap().into_scalar();
+        assert!((result - 1.0 / (4.0 * 2f64.ln())).abs() < 1e-12);
+    }
+
+    #[test]
+    fn it_works2() {
+        let x = new_variable("x".to_string());
+        let y = new_variable("y".to_string());
+        let expression = y.log(x);
+        let diff = expression.differential(&["x", "y"]);
+
+        let mut hash = HashMap::new();
+        hash.insert("x", ConstantValue::Scalar(8.0));
+        hash.insert("y", ConstantValue::Scalar(2.0));
+
+        let dx = diff[0].evaluate(&hash).unwrap().into_scalar();
+        let dy = diff[1].evaluate(&hash).unwrap().into_scalar();
+        assert!((dx - 1.0 / (8.0 * 2f64.ln())).abs() < 1e-12);
+        assert!((dy + 3.0 / (2.0 * 2f64.ln())).abs() < 1e-12);
+    }
+
+    #[test]
+    fn it_works3() {
+        let x = new_variable("x".to_string());
+        let expression = Expression::from(std::f64::consts::E).log(x.clone());
+        assert_eq!(expression, x.clone().ln());
+
+        let diff = expression.differential(&["x"])[0].clone();
+
+        let mut hash = HashMap::new();
+        hash.insert("x", ConstantValue::Scalar(4.0));
+
+        assert_eq!(diff.evaluate(&hash).unwrap().into_scalar(), 0.25);
+    }
+
+    #[test]
+    fn it_works4() {
+        let x = new_variable("x".to_string());
+        let y = new_variable("y".to_string());
+        let expression = y.pow(3.0.into()).log(x);
+        let diff = expression.differential(&["x"])[0].clone();
+
+        let mut hash = HashMap::new();
+        hash.insert("x", ConstantValue::Scalar(8.0));
+        hash.insert("y", ConstantValue::Scalar(2.0));
+
+        let value = expression.evaluate(&hash).unwrap().into_scalar();
+        let dx = diff.evaluate(&hash).unwrap().into_scalar();
+        assert!((value - 1.0).abs() < 1e-12);
+        assert!((dx - 1.0 / (8.0 * 8f64.ln())).abs() < 1e-12);
+    }
+
+    #[test]
+    fn it_works5() {
+        let x = new_variable("x".to_string());
+        let diff = x.clone().abs().differential(&["x"])[0].clone();
+
+        for (v, expected) in [(-3.0, -1.0), (2.0, 1.0), (0.0, 0.0)] {
+            let mut hash = HashMap::new();
+            hash.insert("x", ConstantValue::Scalar(v));
+
+            assert_eq!(diff.evaluate(&hash).unwrap().into_scalar(), expected);
+        }
+    }
+
+    #[test]
+    fn it_works6() {
+        let x = new_variable("x".to_string());
+        let sign = x.clone().sign().differential(&["x"])[0].clone();
+        let heaviside = x.clone().heaviside().differential(&["x"])[0].clone();
+
+        assert_eq!(sign, Expression::from(0.0));
+        assert_eq!(heaviside, Expression::from(0.0));
+
+        // Huber loss with delta 1
+        let huber = (x.clone().abs() - 1.0).heaviside().sign() * (x.clone().abs() - 0.5)
+            + (1.0 - x.clone().abs()).heaviside() * 0.5 * x.clone().pow(2.0.into());
+        let diff = huber.differential(&["x"])[0].clone();
+
+        for (v, expected) in [(-3.0, -1.0), (0.5, 0.5), (2.0, 1.0)] {
+            let mut hash = HashMap::new();
+            hash.insert("x", ConstantValue::Scalar(v));
+
+            assert_eq!(diff.evaluate(&hash).unwrap().into_scalar(), expected);
         }
     }
+
+    #[test]
+    fn it_works7() {
+        let x = new_variable("x".to_string());
+        let v = 0.3f64;
+        let expected = [
+            (x.clone().sinh(), v.cosh()),
+            (x.clone().cosh(), v.sinh()),
+            (x.clone().tanh(), 1.0 - v.tanh().powi(2)),
+            (x.clone().asin(), 1.0 / (1.0 - v * v).sqrt()),
+            (x.clone().acos(), -1.0 / (1.0 - v * v).sqrt()),
+            (x.clone().atan(), 1.0 / (1.0 + v * v)),
+        ];
+
+        let mut hash = HashMap::new();
+        hash.insert("x", ConstantValue::Scalar(v));
+
+        for (expression, expected) in expected {
+            let diff = expression.differential(&["x"])[0].clone();
+            let result = diff.evaluate(&hash).unwrap().into_scalar();
+            assert!((result - expected).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn it_works8() {
+        let x = new_variable("x".to_string());
+        let y = new_variable("y".to_string());
+        let expression = y.atan2(x);
+        let diff = expression.differential(&["x", "y"]);
+
+        let mut hash = HashMap::new();
+        hash.insert("x", ConstantValue::Scalar(-1.0));
+        hash.insert("y", ConstantValue::Scalar(2.0));
+
+        let value = expression.evaluate(&hash).unwrap().into_scalar();
+        let dx = diff[0].evaluate(&hash).unwrap().into_scalar();
+        let dy = diff[1].evaluate(&hash).unwrap().into_scalar();
+        assert_eq!(value, 2f64.atan2(-1.0));
+        assert!((dx + 2.0 / 5.0).abs() < 1e-12);
+        assert!((dy + 1.0 / 5.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn it_works9() {
+        let x = new_variable("x".to_string());
+        let v = 1.3;
+        let h = 1e-6;
+        let expressions = [
+            x.clone().erf(),
+            x.clone().erfc(),
+            x.clone().gamma(),
+            x.clone().ln_gamma(),
+            x.clone().digamma(),
+            x.clone().polygamma(1),
+            x.clone().sigmoid(),
+            x.clone().softplus(),
+            x.clone().ln_1p(),
+            x.clone().exp_m1(),
+        ];
+
+        for expression in expressions {
+            let at = |v: f64| {
+                let mut hash = HashMap::new();
+                hash.insert("x", ConstantValue::Scalar(v));
+                hash
+            };
+            let diff = expression.differential(&["x"])[0].clone();
+            let result = diff.evaluate(&at(v)).unwrap().into_scalar();
+            let expected = (expression.evaluate(&at(v + h)).unwrap().into_scalar()
+                - expression.evaluate(&at(v - h)).unwrap().into_scalar())
+                / (2.0 * h);
+            assert!((result - expected).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn it_works10() {
+        // Bernoulli-logit log-likelihood
+        let x = new_variable("x".to_string());
+        let y = new_variable("y".to_string());
+        let expression = y * x.clone() - x.softplus();
+        let diff = expression.differential(&["x"])[0].clone();
+
+        let mut hash = HashMap::new();
+        hash.insert("x", ConstantValue::Scalar(0.7));
+        hash.insert("y", ConstantValue::Scalar(1.0));
+
+        let result = diff.evaluate(&hash).unwrap().into_scalar();
+        assert!((result - (1.0 - 1.0 / (1.0 + (-0.7f64).exp()))).abs() < 1e-12);
+    }
 }
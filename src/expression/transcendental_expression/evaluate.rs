@@ -0,0 +1,286 @@
+use super::functions::{
+    digamma::digamma, heaviside::heaviside, polygamma::polygamma, sigmoid::sigmoid, sign::sign,
+    softplus::softplus,
+};
+use crate::{ConstantValue, ExpressionRef, Size, SymbolicError, TranscendentalExpression};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A transcendental function of one argument, applied to each element.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum UnaryFunction {
+    Abs,
+    Exp,
+    Ln,
+    Sin,
+    Cos,
+    Tan,
+    Sign,
+    Heaviside,
+    Sinh,
+    Cosh,
+    Tanh,
+    Asin,
+    Acos,
+    Atan,
+    Erf,
+    Erfc,
+    Gamma,
+    LnGamma,
+    Digamma,
+    Sigmoid,
+    Softplus,
+    Log1p,
+    Expm1,
+    Polygamma(usize),
+}
+
+impl UnaryFunction {
+    pub fn call(self, x: f64) -> f64 {
+        match self {
+            UnaryFunction::Abs => x.abs(),
+            UnaryFunction::Exp => x.exp(),
+            UnaryFunction::Ln => x.ln(),
+            UnaryFunction::Sin => x.sin(),
+            UnaryFunction::Cos => x.cos(),
+            UnaryFunction::Tan => x.tan(),
+            UnaryFunction::Sign => sign(x),
+            UnaryFunction::Heaviside => heaviside(x),
+            UnaryFunction::Sinh => x.sinh(),
+            UnaryFunction::Cosh => x.cosh(),
+            UnaryFunction::Tanh => x.tanh(),
+            UnaryFunction::Asin => x.asin(),
+            UnaryFunction::Acos => x.acos(),
+            UnaryFunction::Atan => x.atan(),
+            UnaryFunction::Erf => libm::erf(x),
+            UnaryFunction::Erfc => libm::erfc(x),
+            UnaryFunction::Gamma => libm::tgamma(x),
+            UnaryFunction::LnGamma => libm::lgamma(x),
+            UnaryFunction::Digamma => digamma(x),
+            UnaryFunction::Sigmoid => sigmoid(x),
+            UnaryFunction::Softplus => softplus(x),
+            UnaryFunction::Log1p => x.ln_1p(),
+            UnaryFunction::Expm1 => x.exp_m1(),
+            UnaryFunction::Polygamma(n) => polygamma(n, x),
+        }
+    }
+}
+
+impl TranscendentalExpression {
+    pub fn evaluate(
+        &self,
+        variables: &HashMap<&str, ConstantValue>,
+    ) -> Result<ConstantValue, SymbolicError> {
+        Size::bind_dims(self.variable_sizes(), variables)?;
+        self._evaluate(variables)
+    }
+
+    /// The function and the argument of a transcendental function of one argument, or none for the others.
+    pub fn unary(&self) -> Option<(UnaryFunction, &ExpressionRef)> {
+        let function = match self {
+            TranscendentalExpression::Pow(_, _)
+            | TranscendentalExpression::Log(_, _)
+            | TranscendentalExpression::Atan2(_, _) => return None,
+            TranscendentalExpression::Abs(arg) => (UnaryFunction::Abs, arg),
+            TranscendentalExpression::Exp(arg) => (UnaryFunction::Exp, arg),
+            TranscendentalExpression::Ln(arg) => (UnaryFunction::Ln, arg),
+            TranscendentalExpression::Sin(arg) => (UnaryFunction::Sin, arg),
+            TranscendentalExpression::Cos(arg) => (UnaryFunction::Cos, arg),
+            TranscendentalExpression::Tan(arg) => (UnaryFunction::Tan, arg),
+            TranscendentalExpression::Sign(arg) => (UnaryFunction::Sign, arg),
+            TranscendentalExpression::Heaviside(arg) => (UnaryFunction::Heaviside, arg),
+            TranscendentalExpression::Sinh(arg) => (UnaryFunction::Sinh, arg),
+            TranscendentalExpression::Cosh(arg) => (UnaryFunction::Cosh, arg),
+            TranscendentalExpression::Tanh(arg) => (UnaryFunction::Tanh, arg),
+            TranscendentalExpression::Asin(arg) => (UnaryFunction::Asin, arg),
+            TranscendentalExpression::Acos(arg) => (UnaryFunction::Acos, arg),
+            TranscendentalExpression::Atan(arg) => (UnaryFunction::Atan, arg),
+            TranscendentalExpression::Erf(arg) => (UnaryFunction::Erf, arg),
+            TranscendentalExpression::Erfc(arg) => (UnaryFunction::Erfc, arg),
+            TranscendentalExpression::Gamma(arg) => (UnaryFunction::Gamma, arg),
+            TranscendentalExpression::LnGamma(arg) => (UnaryFunction::LnGamma, arg),
+            TranscendentalExpression::Digamma(arg) => (UnaryFunction::Digamma, arg),
+            TranscendentalExpression::Sigmoid(arg) => (UnaryFunction::Sigmoid, arg),
+            TranscendentalExpression::Softplus(arg) => (UnaryFunction::Softplus, arg),
+            TranscendentalExpression::Log1p(arg) => (UnaryFunction::Log1p, arg),
+            TranscendentalExpression::Expm1(arg) => (UnaryFunction::Expm1, arg),
+            TranscendentalExpression::Polygamma(n, arg) => (UnaryFunction::Polygamma(*n), arg),
+        };
+
+        Some(function)
+    }
+
+    pub(crate) fn _evaluate(
+        &self,
+        variables: &HashMap<&str, ConstantValue>,
+    ) -> Result<ConstantValue, SymbolicError> {
+        if let Some((function, arg)) = self.unary() {
+            return Ok(arg._evaluate(variables)?.map(|x| function.call(x)));
+        }
+
+        match self {
+            TranscendentalExpression::Pow(base, exponent) => base
+                ._evaluate(variables)?
+                .zip_map(&exponent._evaluate(variables)?, f64::powf),
+            TranscendentalExpression::Log(base, antilogarithm) => base
+                ._evaluate(variables)?
+                .zip_map(&antilogarithm._evaluate(variables)?, |b, a| a.log(b)),
+            TranscendentalExpression::Atan2(y, x) => y
+                ._evaluate(variables)?
+                .zip_map(&x._evaluate(variables)?, f64::atan2),
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{new_variable, new_variable_tensor, ConstantValue, Expression, Size};
+
+    #[test]
+    fn it_works() {
+        let x = new_variable("x".to_string());
+        let expression = x.clone().sin() + x.clone().cos().exp() + Expression::from(2.0).log(x);
+
+        let mut hash = HashMap::new();
+        hash.insert("x", ConstantValue::Scalar(8.0));
+
+        let result = expression.evaluate(&hash).unwrap().into_scalar();
+
+        assert!((result - (8f64.sin() + 8f64.cos().exp() + 3.0)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn it_works2() {
+        let x = new_variable_tensor("x".to_string(), vec![Size::Many]);
+        let expression = x.cos();
+
+        let mut hash = HashMap::new();
+        hash.insert("x", ConstantValue::Tensor(vec![0.0, 0.0, 1.0].into()));
+
+        let result = expression.evaluate(&hash).unwrap();
+
+        assert_eq!(
+            result,
+            ConstantValue::Tensor(vec![1.0, 1.0, 1f64.cos()].into())
+        );
+    }
+
+    #[test]
+    fn it_works3() {
+        let x = new_variable_tensor("x".to_string(), vec![Size::Many]);
+
+        let mut hash = HashMap::new();
+        hash.insert("x", ConstantValue::Tensor(vec![-2.0, 0.0, 3.0].into()));
+
+        let sign = x.clone().sign().evaluate(&hash).unwrap();
+        let heaviside = x.heaviside().evaluate(&hash).unwrap();
+
+        assert_eq!(sign, ConstantValue::Tensor(vec![-1.0, 0.0, 1.0].into()));
+        assert_eq!(heaviside, ConstantValue::Tensor(vec![0.0, 0.5, 1.0].into()));
+        assert_eq!(
+            Expression::from(-4.0).sign().heaviside(),
+            Expression::from(0.0)
+        );
+    }
+
+    #[test]
+    fn it_works4() {
+        let x = new_variable("x".to_string());
+        let symbols = vec![("x", "x")].into_iter().collect();
+
+        assert_eq!(
+            x.clone().sign().tex_code(&symbols),
+            r"\operatorname{sgn}\left({x}\right)"
+        );
+        assert_eq!(x.heaviside().tex_code(&symbols), r"H\left({x}\right)");
+    }
+
+    #[test]
+    fn it_works5() {
+        let zero = Expression::from(vec![0.0, 0.5]);
+
+        assert_eq!(
+            zero.clone().cosh(),
+            Expression::from(vec![1.0, 0.5f64.cosh()])
+        );
+        assert_eq!(
+            zero.clone().acos(),
+            Expression::from(vec![0f64.acos(), 0.5f64.acos()])
+        );
+        assert_eq!(
+            Expression::from(1.0).atan2(Expression::from(-1.0)),
+            Expression::from(1f64.atan2(-1.0))
+        );
+
+        let x = new_variable("x".to_string());
+        let y = new_variable("y".to_string());
+        let symbols = vec![("x", "x"), ("y", "y")].into_iter().collect();
+
+        assert_eq!(
+            x.clone().tanh().tex_code(&symbols),
+            r"\tanh\left({x}\right)"
+        );
+        assert_eq!(
+            y.atan2(x).tex_code(&symbols),
+            r"\operatorname{atan2}\left({y}, {x}\right)"
+        );
+    }
+
+    #[test]
+    fn it_works6() {
+        let expected = [
+            (Expression::from(0.5).erf(), 0.5204998778130465),
+            (Expression::from(0.5).erfc(), 0.4795001221869535),
+            (Expression::from(5.0).gamma(), 24.0),
+            (Expression::from(10.0).ln_gamma(), 362880f64.ln()),
+            (Expression::from(1.0).digamma(), -0.5772156649015329),
+            (Expression::from(0.5).digamma(), -1.9635100260214235),
+            (
+                Expression::from(1.0).polygamma(1),
+                std::f64::consts::PI.powi(2) / 6.0,
+            ),
+            (Expression::from(1.0).polygamma(2), -2.4041138063191885),
+            (Expression::from(0.0).sigmoid(), 0.5),
+            (Expression::from(-800.0).sigmoid(), 0.0),
+            (Expression::from(1000.0).softplus(), 1000.0),
+            (Expression::from(1e-20).ln_1p(), 1e-20),
+            (Expression::from(1e-20).exp_m1(), 1e-20),
+        ];
+
+        for (expression, expected) in expected {
+            let result = expression.evaluate(&HashMap::new()).unwrap().into_scalar();
+            assert!((result - expected).abs() <= 1e-12 * expected.abs().max(1.0));
+        }
+    }
+
+    #[test]
+    fn it_works7() {
+        let x = new_variable("x".to_string());
+        let y = new_variable("y".to_string());
+
+        let mut hash = HashMap::new();
+        hash.insert("x", ConstantValue::Scalar(-1.5));
+        hash.insert("y", ConstantValue::Scalar(-2.0));
+
+        // The logarithms are defined though the factors are negative.
+        let result = x.clone().pow(2.0.into()).ln().evaluate(&hash).unwrap();
+        assert!((result.into_scalar() - 2.25f64.ln()).abs() < 1e-12);
+        let result = (x * y).ln().evaluate(&hash).unwrap();
+        assert!((result.into_scalar() - 3f64.ln()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn it_works8() {
+        let x = new_variable("x".to_string());
+
+        let mut hash = HashMap::new();
+        hash.insert("x", ConstantValue::Scalar(-2.0));
+
+        // The base is a power of a negative number, which is not the power of a logarithm of it.
+        let result = x.pow(2.0.into()).log(8.0.into()).evaluate(&hash).unwrap();
+        assert!((result.into_scalar() - 1.5).abs() < 1e-12);
+    }
+}
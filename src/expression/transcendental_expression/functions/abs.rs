@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use crate::{BracketsLevel, Expression, TranscendentalExpression};
+use crate::{BracketsLevel, Expression, ExpressionRef, TranscendentalExpression};
 
 impl Expression {
     pub fn abs(self) -> Self {
@@ -14,7 +14,7 @@ impl Expression {
 }
 
 impl TranscendentalExpression {
-    pub(crate) fn tex_code_abs(arg: &Box<Expression>, symbols: &HashMap<&str, &str>) -> String {
+    pub(crate) fn tex_code_abs(arg: &ExpressionRef, symbols: &HashMap<&str, &str>) -> String {
         format!(
             r"\left|{}\right|",
             arg._tex_code(symbols, BracketsLevel::None)
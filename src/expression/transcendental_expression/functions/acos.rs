@@ -0,0 +1,22 @@
+use std::collections::HashMap;
+
+use crate::{BracketsLevel, Expression, TranscendentalExpression};
+
+impl Expression {
+    pub fn acos(self) -> Self {
+        if let Expression::Constant(v) = self {
+            return v.map(f64::acos).into();
+        }
+
+        TranscendentalExpression::Acos(self.into()).into()
+    }
+}
+
+impl TranscendentalExpression {
+    pub(crate) fn tex_code_acos(arg: &Expression, symbols: &HashMap<&str, &str>) -> String {
+        format!(
+            r"\arccos\left({}\right)",
+            arg._tex_code(symbols, BracketsLevel::None)
+        )
+    }
+}
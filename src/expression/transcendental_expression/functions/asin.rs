@@ -0,0 +1,22 @@
+use std::collections::HashMap;
+
+use crate::{BracketsLevel, Expression, TranscendentalExpression};
+
+impl Expression {
+    pub fn asin(self) -> Self {
+        if let Expression::Constant(v) = self {
+            return v.map(f64::asin).into();
+        }
+
+        TranscendentalExpression::Asin(self.into()).into()
+    }
+}
+
+impl TranscendentalExpression {
+    pub(crate) fn tex_code_asin(arg: &Expression, symbols: &HashMap<&str, &str>) -> String {
+        format!(
+            r"\arcsin\left({}\right)",
+            arg._tex_code(symbols, BracketsLevel::None)
+        )
+    }
+}
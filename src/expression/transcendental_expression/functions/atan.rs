@@ -0,0 +1,22 @@
+use std::collections::HashMap;
+
+use crate::{BracketsLevel, Expression, TranscendentalExpression};
+
+impl Expression {
+    pub fn atan(self) -> Self {
+        if let Expression::Constant(v) = self {
+            return v.map(f64::atan).into();
+        }
+
+        TranscendentalExpression::Atan(self.into()).into()
+    }
+}
+
+impl TranscendentalExpression {
+    pub(crate) fn tex_code_atan(arg: &Expression, symbols: &HashMap<&str, &str>) -> String {
+        format!(
+            r"\arctan\left({}\right)",
+            arg._tex_code(symbols, BracketsLevel::None)
+        )
+    }
+}
@@ -0,0 +1,35 @@
+use std::collections::HashMap;
+
+use crate::{BracketsLevel, Expression, SymbolicError, TranscendentalExpression};
+
+impl Expression {
+    /// The four-quadrant arctangent of `self / x`, as `f64::atan2`.
+    pub fn try_atan2(self, x: Expression) -> Result<Self, SymbolicError> {
+        if let Expression::Constant(y) = &self {
+            if let Expression::Constant(x) = &x {
+                return Ok(y.zip_map(x, f64::atan2)?.into());
+            }
+        }
+        self.check_same_size(&x, "take the arctangent of")?;
+
+        Ok(TranscendentalExpression::Atan2(self.into(), x.into()).into())
+    }
+
+    pub fn atan2(self, x: Expression) -> Self {
+        self.try_atan2(x).unwrap_or_else(|e| panic!("{}", e))
+    }
+}
+
+impl TranscendentalExpression {
+    pub(crate) fn tex_code_atan2(
+        y: &Expression,
+        x: &Expression,
+        symbols: &HashMap<&str, &str>,
+    ) -> String {
+        format!(
+            r"\operatorname{{atan2}}\left({}, {}\right)",
+            y._tex_code(symbols, BracketsLevel::None),
+            x._tex_code(symbols, BracketsLevel::None)
+        )
+    }
+}
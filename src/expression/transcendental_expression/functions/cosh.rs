@@ -0,0 +1,22 @@
+use std::collections::HashMap;
+
+use crate::{BracketsLevel, Expression, TranscendentalExpression};
+
+impl Expression {
+    pub fn cosh(self) -> Self {
+        if let Expression::Constant(v) = self {
+            return v.map(f64::cosh).into();
+        }
+
+        TranscendentalExpression::Cosh(self.into()).into()
+    }
+}
+
+impl TranscendentalExpression {
+    pub(crate) fn tex_code_cosh(arg: &Expression, symbols: &HashMap<&str, &str>) -> String {
+        format!(
+            r"\cosh\left({}\right)",
+            arg._tex_code(symbols, BracketsLevel::None)
+        )
+    }
+}
@@ -0,0 +1,43 @@
+use std::collections::HashMap;
+
+use crate::{BracketsLevel, Expression, TranscendentalExpression};
+
+/// The digamma function, the derivative of `ln_gamma`.
+pub(crate) fn digamma(x: f64) -> f64 {
+    if x <= 0.0 && x == x.floor() {
+        return f64::NAN;
+    }
+
+    // Shift `x` by the recurrence psi(x) = psi(x + 1) - 1 / x until the asymptotic expansion is accurate.
+    let mut x = x;
+    let mut result = 0.0;
+    while x < 10.0 {
+        result -= 1.0 / x;
+        x += 1.0;
+    }
+    let x2 = 1.0 / (x * x);
+
+    result + x.ln()
+        - 0.5 / x
+        - x2 * (1.0 / 12.0
+            - x2 * (1.0 / 120.0 - x2 * (1.0 / 252.0 - x2 * (1.0 / 240.0 - x2 / 132.0))))
+}
+
+impl Expression {
+    pub fn digamma(self) -> Self {
+        if let Expression::Constant(v) = self {
+            return v.map(digamma).into();
+        }
+
+        TranscendentalExpression::Digamma(self.into()).into()
+    }
+}
+
+impl TranscendentalExpression {
+    pub(crate) fn tex_code_digamma(arg: &Expression, symbols: &HashMap<&str, &str>) -> String {
+        format!(
+            r"\psi\left({}\right)",
+            arg._tex_code(symbols, BracketsLevel::None)
+        )
+    }
+}
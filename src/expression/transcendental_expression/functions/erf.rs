@@ -0,0 +1,22 @@
+use std::collections::HashMap;
+
+use crate::{BracketsLevel, Expression, TranscendentalExpression};
+
+impl Expression {
+    pub fn erf(self) -> Self {
+        if let Expression::Constant(v) = self {
+            return v.map(libm::erf).into();
+        }
+
+        TranscendentalExpression::Erf(self.into()).into()
+    }
+}
+
+impl TranscendentalExpression {
+    pub(crate) fn tex_code_erf(arg: &Expression, symbols: &HashMap<&str, &str>) -> String {
+        format!(
+            r"\operatorname{{erf}}\left({}\right)",
+            arg._tex_code(symbols, BracketsLevel::None)
+        )
+    }
+}
@@ -0,0 +1,22 @@
+use std::collections::HashMap;
+
+use crate::{BracketsLevel, Expression, TranscendentalExpression};
+
+impl Expression {
+    pub fn erfc(self) -> Self {
+        if let Expression::Constant(v) = self {
+            return v.map(libm::erfc).into();
+        }
+
+        TranscendentalExpression::Erfc(self.into()).into()
+    }
+}
+
+impl TranscendentalExpression {
+    pub(crate) fn tex_code_erfc(arg: &Expression, symbols: &HashMap<&str, &str>) -> String {
+        format!(
+            r"\operatorname{{erfc}}\left({}\right)",
+            arg._tex_code(symbols, BracketsLevel::None)
+        )
+    }
+}
@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use crate::{Expression, TranscendentalExpression};
+use crate::{Expression, ExpressionRef, TranscendentalExpression};
 
 impl Expression {
     pub fn exp(self) -> Self {
@@ -14,7 +14,7 @@ impl Expression {
 }
 
 impl TranscendentalExpression {
-    pub(crate) fn tex_code_exp(arg: &Box<Expression>, symbols: &HashMap<&str, &str>) -> String {
+    pub(crate) fn tex_code_exp(arg: &ExpressionRef, symbols: &HashMap<&str, &str>) -> String {
         format!(
             r"\exp{{{}}}",
             arg._tex_code(symbols, crate::BracketsLevel::ForOperation)
@@ -0,0 +1,22 @@
+use std::collections::HashMap;
+
+use crate::{BracketsLevel, Expression, TranscendentalExpression};
+
+impl Expression {
+    pub fn exp_m1(self) -> Self {
+        if let Expression::Constant(v) = self {
+            return v.map(f64::exp_m1).into();
+        }
+
+        TranscendentalExpression::Expm1(self.into()).into()
+    }
+}
+
+impl TranscendentalExpression {
+    pub(crate) fn tex_code_exp_m1(arg: &Expression, symbols: &HashMap<&str, &str>) -> String {
+        format!(
+            r"\left(\exp\left({}\right) - 1\right)",
+            arg._tex_code(symbols, BracketsLevel::None)
+        )
+    }
+}
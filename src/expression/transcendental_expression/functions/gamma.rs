@@ -0,0 +1,22 @@
+use std::collections::HashMap;
+
+use crate::{BracketsLevel, Expression, TranscendentalExpression};
+
+impl Expression {
+    pub fn gamma(self) -> Self {
+        if let Expression::Constant(v) = self {
+            return v.map(libm::tgamma).into();
+        }
+
+        TranscendentalExpression::Gamma(self.into()).into()
+    }
+}
+
+impl TranscendentalExpression {
+    pub(crate) fn tex_code_gamma(arg: &Expression, symbols: &HashMap<&str, &str>) -> String {
+        format!(
+            r"\Gamma\left({}\right)",
+            arg._tex_code(symbols, BracketsLevel::None)
+        )
+    }
+}
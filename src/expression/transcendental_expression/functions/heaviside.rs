@@ -0,0 +1,33 @@
+use std::collections::HashMap;
+
+use crate::{BracketsLevel, Expression, TranscendentalExpression};
+
+/// `1` for positive, `0` for negative values and `1/2` at zero.
+pub(crate) fn heaviside(x: f64) -> f64 {
+    if x > 0.0 {
+        1.0
+    } else if x < 0.0 {
+        0.0
+    } else {
+        0.5
+    }
+}
+
+impl Expression {
+    pub fn heaviside(self) -> Self {
+        if let Expression::Constant(v) = self {
+            return v.map(heaviside).into();
+        }
+
+        TranscendentalExpression::Heaviside(self.into()).into()
+    }
+}
+
+impl TranscendentalExpression {
+    pub(crate) fn tex_code_heaviside(arg: &Expression, symbols: &HashMap<&str, &str>) -> String {
+        format!(
+            r"H\left({}\right)",
+            arg._tex_code(symbols, BracketsLevel::None)
+        )
+    }
+}
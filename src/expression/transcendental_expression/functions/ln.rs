@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use crate::{BracketsLevel, Expression, TranscendentalExpression};
+use crate::{BracketsLevel, Expression, ExpressionRef, TranscendentalExpression};
 
 impl Expression {
     pub fn ln(self) -> Self {
@@ -8,18 +8,10 @@ impl Expression {
             v.elems_mut().into_iter().for_each(|v| *v = v.ln());
             return v.into();
         }
-        if let Expression::Mul(l, r) = &self {
-            return l.as_ref().clone().ln() + r.as_ref().clone().ln();
-        }
+        // ln(ab) and ln(a^e) are not split, as the logarithms of the factors are undefined when they are negative.
         if let Expression::Transcendental(v) = &self {
-            match v.as_ref() {
-                TranscendentalExpression::Pow(base, exponent) => {
-                    return exponent.as_ref().clone() * base.as_ref().clone().ln();
-                }
-                TranscendentalExpression::Exp(arg) => {
-                    return arg.as_ref().clone().into();
-                }
-                _ => {}
+            if let TranscendentalExpression::Exp(arg) = v.as_ref() {
+                return arg.as_ref().clone().into();
             }
         }
 
@@ -28,7 +20,7 @@ impl Expression {
 }
 
 impl TranscendentalExpression {
-    pub(crate) fn tex_code_ln(arg: &Box<Expression>, symbols: &HashMap<&str, &str>) -> String {
+    pub(crate) fn tex_code_ln(arg: &ExpressionRef, symbols: &HashMap<&str, &str>) -> String {
         format!(
             r"\ln{{{}}}",
             arg._tex_code(symbols, BracketsLevel::ForOperation)
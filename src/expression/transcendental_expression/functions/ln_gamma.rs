@@ -0,0 +1,22 @@
+use std::collections::HashMap;
+
+use crate::{BracketsLevel, Expression, TranscendentalExpression};
+
+impl Expression {
+    pub fn ln_gamma(self) -> Self {
+        if let Expression::Constant(v) = self {
+            return v.map(libm::lgamma).into();
+        }
+
+        TranscendentalExpression::LnGamma(self.into()).into()
+    }
+}
+
+impl TranscendentalExpression {
+    pub(crate) fn tex_code_ln_gamma(arg: &Expression, symbols: &HashMap<&str, &str>) -> String {
+        format!(
+            r"\ln\Gamma\left({}\right)",
+            arg._tex_code(symbols, BracketsLevel::None)
+        )
+    }
+}
@@ -1,35 +1,31 @@
 use std::collections::HashMap;
 
-use crate::{BracketsLevel, Expression, TranscendentalExpression};
+use crate::{BracketsLevel, Expression, ExpressionRef, SymbolicError, TranscendentalExpression};
 
 impl Expression {
-    pub fn log(self, antilogarithm: Expression) -> Self {
+    pub fn try_log(self, antilogarithm: Expression) -> Result<Self, SymbolicError> {
         if let Expression::Constant(base) = &self {
             if let Expression::Constant(antilogarithm) = antilogarithm {
-                return antilogarithm.into_scalar().log(base.into_scalar()).into();
+                return Ok(base.zip_map(&antilogarithm, |b, a| a.log(b))?.into());
             }
         }
-        if let Expression::Mul(l, r) = &self {
-            return l.as_ref().clone().log(antilogarithm.clone())
-                + r.as_ref().clone().log(antilogarithm);
-        }
-        if let Expression::Transcendental(v) = &self {
-            match v.as_ref() {
-                TranscendentalExpression::Pow(base, exponent) => {
-                    return exponent.as_ref().clone() * base.as_ref().clone().log(antilogarithm);
-                }
-                _ => {}
-            }
+        if self == Expression::from(std::f64::consts::E) {
+            return Ok(antilogarithm.ln());
         }
 
-        TranscendentalExpression::Log(self.into(), antilogarithm.into()).into()
+        Ok(TranscendentalExpression::Log(self.into(), antilogarithm.into()).into())
+    }
+
+    pub fn log(self, antilogarithm: Expression) -> Self {
+        self.try_log(antilogarithm)
+            .unwrap_or_else(|e| panic!("{}", e))
     }
 }
 
 impl TranscendentalExpression {
     pub(crate) fn tex_code_log(
-        base: &Box<Expression>,
-        antilogarithm: &Box<Expression>,
+        base: &ExpressionRef,
+        antilogarithm: &ExpressionRef,
         symbols: &HashMap<&str, &str>,
     ) -> String {
         format!(
@@ -0,0 +1,22 @@
+use std::collections::HashMap;
+
+use crate::{BracketsLevel, Expression, TranscendentalExpression};
+
+impl Expression {
+    pub fn ln_1p(self) -> Self {
+        if let Expression::Constant(v) = self {
+            return v.map(f64::ln_1p).into();
+        }
+
+        TranscendentalExpression::Log1p(self.into()).into()
+    }
+}
+
+impl TranscendentalExpression {
+    pub(crate) fn tex_code_ln_1p(arg: &Expression, symbols: &HashMap<&str, &str>) -> String {
+        format!(
+            r"\ln\left(1 + {}\right)",
+            arg._tex_code(symbols, BracketsLevel::None)
+        )
+    }
+}
@@ -1,8 +1,27 @@
 pub mod abs;
+pub mod acos;
+pub mod asin;
+pub mod atan;
+pub mod atan2;
 pub mod cos;
+pub mod cosh;
+pub mod digamma;
+pub mod erf;
+pub mod erfc;
 pub mod exp;
+pub mod expm1;
+pub mod gamma;
+pub mod heaviside;
 pub mod ln;
+pub mod ln_gamma;
 pub mod log;
+pub mod log1p;
+pub mod polygamma;
 pub mod pow;
+pub mod sigmoid;
+pub mod sign;
 pub mod sin;
+pub mod sinh;
+pub mod softplus;
 pub mod tan;
+pub mod tanh;
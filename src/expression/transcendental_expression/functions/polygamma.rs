@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+
+use super::digamma::digamma;
+use crate::{BracketsLevel, Expression, TranscendentalExpression};
+
+/// The `n`-th derivative of the digamma function.
+pub(crate) fn polygamma(n: usize, x: f64) -> f64 {
+    if n == 0 {
+        return digamma(x);
+    }
+    if x <= 0.0 && x == x.floor() {
+        return f64::NAN;
+    }
+
+    let factorial = |from: usize, to: usize| (from + 1..=to).fold(1.0, |p, k| p * k as f64);
+    let sign = (-1f64).powi(n as i32 + 1);
+    let n_factorial = factorial(0, n);
+
+    // Shift `x` by the recurrence psi_n(x) = psi_n(x + 1) + (-1)^(n+1) n! / x^(n+1) until the asymptotic expansion is accurate.
+    let mut x = x;
+    let mut result = 0.0;
+    while x < 10.0 + n as f64 {
+        result += sign * n_factorial / x.powi(n as i32 + 1);
+        x += 1.0;
+    }
+
+    let bernoulli = [1.0 / 6.0, -1.0 / 30.0, 1.0 / 42.0, -1.0 / 30.0, 5.0 / 66.0];
+    let series = bernoulli
+        .iter()
+        .enumerate()
+        .map(|(k, b)| {
+            let k = 2 * (k + 1);
+            b * factorial(k, k + n - 1) / x.powi((k + n) as i32)
+        })
+        .sum::<f64>();
+
+    result
+        + sign
+            * (factorial(0, n - 1) / x.powi(n as i32)
+                + n_factorial / (2.0 * x.powi(n as i32 + 1))
+                + series)
+}
+
+impl Expression {
+    pub fn polygamma(self, n: usize) -> Self {
+        if n == 0 {
+            return self.digamma();
+        }
+        if let Expression::Constant(v) = self {
+            return v.map(|x| polygamma(n, x)).into();
+        }
+
+        TranscendentalExpression::Polygamma(n, self.into()).into()
+    }
+}
+
+impl TranscendentalExpression {
+    pub(crate) fn tex_code_polygamma(
+        n: usize,
+        arg: &Expression,
+        symbols: &HashMap<&str, &str>,
+    ) -> String {
+        format!(
+            r"\psi^{{({})}}\left({}\right)",
+            n,
+            arg._tex_code(symbols, BracketsLevel::None)
+        )
+    }
+}
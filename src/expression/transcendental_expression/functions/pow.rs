@@ -1,30 +1,37 @@
 use std::collections::HashMap;
 
-use crate::{BracketsLevel, ConstantValue, Expression, TranscendentalExpression};
+use crate::{
+    BracketsLevel, ConstantValue, Expression, ExpressionRef, SymbolicError,
+    TranscendentalExpression,
+};
 
 impl Expression {
-    pub fn pow(self, exponent: Expression) -> Self {
+    pub fn try_pow(self, exponent: Expression) -> Result<Self, SymbolicError> {
         if let Expression::Constant(exponent) = &exponent {
             if exponent == &ConstantValue::Scalar(0.0) {
-                return 1.0.into();
+                return Ok(1.0.into());
             }
             if exponent == &ConstantValue::Scalar(1.0) {
-                return self;
+                return Ok(self);
             }
 
             if let Expression::Constant(base) = self {
-                return base.into_scalar().powf(exponent.into_scalar() + 1.0).into();
+                return Ok(base.zip_map(exponent, f64::powf)?.into());
             }
         }
 
-        TranscendentalExpression::Pow(self.into(), exponent.into()).into()
+        Ok(TranscendentalExpression::Pow(self.into(), exponent.into()).into())
+    }
+
+    pub fn pow(self, exponent: Expression) -> Self {
+        self.try_pow(exponent).unwrap_or_else(|e| panic!("{}", e))
     }
 }
 
 impl TranscendentalExpression {
     pub(crate) fn tex_code_pow(
-        base: &Box<Expression>,
-        exponent: &Box<Expression>,
+        base: &ExpressionRef,
+        exponent: &ExpressionRef,
         symbols: &HashMap<&str, &str>,
     ) -> String {
         format!(
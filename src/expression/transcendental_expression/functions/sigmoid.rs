@@ -0,0 +1,32 @@
+use std::collections::HashMap;
+
+use crate::{BracketsLevel, Expression, TranscendentalExpression};
+
+/// The logistic function `1 / (1 + exp(-x))`, computed without overflow.
+pub(crate) fn sigmoid(x: f64) -> f64 {
+    if x >= 0.0 {
+        1.0 / (1.0 + (-x).exp())
+    } else {
+        let e = x.exp();
+        e / (1.0 + e)
+    }
+}
+
+impl Expression {
+    pub fn sigmoid(self) -> Self {
+        if let Expression::Constant(v) = self {
+            return v.map(sigmoid).into();
+        }
+
+        TranscendentalExpression::Sigmoid(self.into()).into()
+    }
+}
+
+impl TranscendentalExpression {
+    pub(crate) fn tex_code_sigmoid(arg: &Expression, symbols: &HashMap<&str, &str>) -> String {
+        format!(
+            r"\sigma\left({}\right)",
+            arg._tex_code(symbols, BracketsLevel::None)
+        )
+    }
+}
@@ -0,0 +1,38 @@
+use std::collections::HashMap;
+
+use crate::{BracketsLevel, Expression, TranscendentalExpression};
+
+/// `1` for positive, `-1` for negative and `0` for zero values.
+pub(crate) fn sign(x: f64) -> f64 {
+    if x > 0.0 {
+        1.0
+    } else if x < 0.0 {
+        -1.0
+    } else {
+        0.0
+    }
+}
+
+impl Expression {
+    pub fn sign(self) -> Self {
+        if let Expression::Constant(v) = self {
+            return v.map(sign).into();
+        }
+        if let Expression::Transcendental(v) = &self {
+            if let TranscendentalExpression::Sign(_) = v.as_ref() {
+                return self;
+            }
+        }
+
+        TranscendentalExpression::Sign(self.into()).into()
+    }
+}
+
+impl TranscendentalExpression {
+    pub(crate) fn tex_code_sign(arg: &Expression, symbols: &HashMap<&str, &str>) -> String {
+        format!(
+            r"\operatorname{{sgn}}\left({}\right)",
+            arg._tex_code(symbols, BracketsLevel::None)
+        )
+    }
+}
@@ -0,0 +1,22 @@
+use std::collections::HashMap;
+
+use crate::{BracketsLevel, Expression, TranscendentalExpression};
+
+impl Expression {
+    pub fn sinh(self) -> Self {
+        if let Expression::Constant(v) = self {
+            return v.map(f64::sinh).into();
+        }
+
+        TranscendentalExpression::Sinh(self.into()).into()
+    }
+}
+
+impl TranscendentalExpression {
+    pub(crate) fn tex_code_sinh(arg: &Expression, symbols: &HashMap<&str, &str>) -> String {
+        format!(
+            r"\sinh\left({}\right)",
+            arg._tex_code(symbols, BracketsLevel::None)
+        )
+    }
+}
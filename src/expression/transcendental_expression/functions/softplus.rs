@@ -0,0 +1,27 @@
+use std::collections::HashMap;
+
+use crate::{BracketsLevel, Expression, TranscendentalExpression};
+
+/// `ln(1 + exp(x))`, computed without overflow.
+pub(crate) fn softplus(x: f64) -> f64 {
+    x.max(0.0) + (-x.abs()).exp().ln_1p()
+}
+
+impl Expression {
+    pub fn softplus(self) -> Self {
+        if let Expression::Constant(v) = self {
+            return v.map(softplus).into();
+        }
+
+        TranscendentalExpression::Softplus(self.into()).into()
+    }
+}
+
+impl TranscendentalExpression {
+    pub(crate) fn tex_code_softplus(arg: &Expression, symbols: &HashMap<&str, &str>) -> String {
+        format!(
+            r"\operatorname{{softplus}}\left({}\right)",
+            arg._tex_code(symbols, BracketsLevel::None)
+        )
+    }
+}
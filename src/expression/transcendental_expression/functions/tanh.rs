@@ -0,0 +1,22 @@
+use std::collections::HashMap;
+
+use crate::{BracketsLevel, Expression, TranscendentalExpression};
+
+impl Expression {
+    pub fn tanh(self) -> Self {
+        if let Expression::Constant(v) = self {
+            return v.map(f64::tanh).into();
+        }
+
+        TranscendentalExpression::Tanh(self.into()).into()
+    }
+}
+
+impl TranscendentalExpression {
+    pub(crate) fn tex_code_tanh(arg: &Expression, symbols: &HashMap<&str, &str>) -> String {
+        format!(
+            r"\tanh\left({}\right)",
+            arg._tex_code(symbols, BracketsLevel::None)
+        )
+    }
+}
@@ -1,29 +1,52 @@
 pub mod assign;
+pub mod children;
 pub mod differential;
+pub mod evaluate;
 pub mod functions;
+pub mod simplify;
 pub mod size;
 pub mod tex_code;
 pub mod variable;
 
 pub use assign::*;
 pub use differential::*;
+pub use evaluate::*;
 pub use size::*;
 pub use tex_code::*;
 pub use variable::*;
 
-use crate::Expression;
+use crate::{Expression, ExpressionRef};
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Hash, Serialize, Deserialize)]
 pub enum TranscendentalExpression {
-    Abs(Box<Expression>),
-    Pow(Box<Expression>, Box<Expression>),
-    Exp(Box<Expression>),
-    Log(Box<Expression>, Box<Expression>),
-    Ln(Box<Expression>),
-    Sin(Box<Expression>),
-    Cos(Box<Expression>),
-    Tan(Box<Expression>),
+    Abs(ExpressionRef),
+    Pow(ExpressionRef, ExpressionRef),
+    Exp(ExpressionRef),
+    Log(ExpressionRef, ExpressionRef),
+    Ln(ExpressionRef),
+    Sin(ExpressionRef),
+    Cos(ExpressionRef),
+    Tan(ExpressionRef),
+    Sign(ExpressionRef),
+    Heaviside(ExpressionRef),
+    Sinh(ExpressionRef),
+    Cosh(ExpressionRef),
+    Tanh(ExpressionRef),
+    Asin(ExpressionRef),
+    Acos(ExpressionRef),
+    Atan(ExpressionRef),
+    Atan2(ExpressionRef, ExpressionRef),
+    Erf(ExpressionRef),
+    Erfc(ExpressionRef),
+    Gamma(ExpressionRef),
+    LnGamma(ExpressionRef),
+    Digamma(ExpressionRef),
+    Sigmoid(ExpressionRef),
+    Softplus(ExpressionRef),
+    Log1p(ExpressionRef),
+    Expm1(ExpressionRef),
+    Polygamma(usize, ExpressionRef),
 }
 
 impl From<TranscendentalExpression> for Expression {
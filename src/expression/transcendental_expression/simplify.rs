@@ -0,0 +1,39 @@
+use crate::{Expression, TranscendentalExpression};
+
+impl TranscendentalExpression {
+    pub fn simplify(&self) -> Expression {
+        match self {
+            TranscendentalExpression::Pow(base, exponent) => {
+                base.simplify().pow(exponent.simplify())
+            }
+            TranscendentalExpression::Log(base, antilogarithm) => {
+                base.simplify().log(antilogarithm.simplify())
+            }
+            TranscendentalExpression::Atan2(y, x) => y.simplify().atan2(x.simplify()),
+            TranscendentalExpression::Polygamma(n, arg) => arg.simplify().polygamma(*n),
+            TranscendentalExpression::Abs(arg) => arg.simplify().abs(),
+            TranscendentalExpression::Exp(arg) => arg.simplify().exp(),
+            TranscendentalExpression::Ln(arg) => arg.simplify().ln(),
+            TranscendentalExpression::Sin(arg) => arg.simplify().sin(),
+            TranscendentalExpression::Cos(arg) => arg.simplify().cos(),
+            TranscendentalExpression::Tan(arg) => arg.simplify().tan(),
+            TranscendentalExpression::Sign(arg) => arg.simplify().sign(),
+            TranscendentalExpression::Heaviside(arg) => arg.simplify().heaviside(),
+            TranscendentalExpression::Sinh(arg) => arg.simplify().sinh(),
+            TranscendentalExpression::Cosh(arg) => arg.simplify().cosh(),
+            TranscendentalExpression::Tanh(arg) => arg.simplify().tanh(),
+            TranscendentalExpression::Asin(arg) => arg.simplify().asin(),
+            TranscendentalExpression::Acos(arg) => arg.simplify().acos(),
+            TranscendentalExpression::Atan(arg) => arg.simplify().atan(),
+            TranscendentalExpression::Erf(arg) => arg.simplify().erf(),
+            TranscendentalExpression::Erfc(arg) => arg.simplify().erfc(),
+            TranscendentalExpression::Gamma(arg) => arg.simplify().gamma(),
+            TranscendentalExpression::LnGamma(arg) => arg.simplify().ln_gamma(),
+            TranscendentalExpression::Digamma(arg) => arg.simplify().digamma(),
+            TranscendentalExpression::Sigmoid(arg) => arg.simplify().sigmoid(),
+            TranscendentalExpression::Softplus(arg) => arg.simplify().softplus(),
+            TranscendentalExpression::Log1p(arg) => arg.simplify().ln_1p(),
+            TranscendentalExpression::Expm1(arg) => arg.simplify().exp_m1(),
+        }
+    }
+}
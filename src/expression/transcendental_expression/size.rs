@@ -1,20 +1,39 @@
-use crate::{Size, TranscendentalExpression};
+use crate::{Expression, Size, TranscendentalExpression};
 
 impl TranscendentalExpression {
     pub fn sizes(&self) -> Vec<Size> {
         match self {
             TranscendentalExpression::Abs(arg) => arg.sizes(),
             TranscendentalExpression::Pow(base, exponent) => {
-                [base.sizes(), exponent.sizes()].concat()
+                Expression::size_elementwise(base, exponent)
             }
             TranscendentalExpression::Exp(arg) => arg.sizes(),
             TranscendentalExpression::Log(base, antilogarithm) => {
-                [base.sizes(), antilogarithm.sizes()].concat()
+                Expression::size_elementwise(base, antilogarithm)
             }
             TranscendentalExpression::Ln(arg) => arg.sizes(),
             TranscendentalExpression::Sin(arg) => arg.sizes(),
             TranscendentalExpression::Cos(arg) => arg.sizes(),
             TranscendentalExpression::Tan(arg) => arg.sizes(),
+            TranscendentalExpression::Sign(arg) => arg.sizes(),
+            TranscendentalExpression::Heaviside(arg) => arg.sizes(),
+            TranscendentalExpression::Sinh(arg) => arg.sizes(),
+            TranscendentalExpression::Cosh(arg) => arg.sizes(),
+            TranscendentalExpression::Tanh(arg) => arg.sizes(),
+            TranscendentalExpression::Asin(arg) => arg.sizes(),
+            TranscendentalExpression::Acos(arg) => arg.sizes(),
+            TranscendentalExpression::Atan(arg) => arg.sizes(),
+            TranscendentalExpression::Atan2(y, x) => Expression::size_elementwise(y, x),
+            TranscendentalExpression::Erf(arg) => arg.sizes(),
+            TranscendentalExpression::Erfc(arg) => arg.sizes(),
+            TranscendentalExpression::Gamma(arg) => arg.sizes(),
+            TranscendentalExpression::LnGamma(arg) => arg.sizes(),
+            TranscendentalExpression::Digamma(arg) => arg.sizes(),
+            TranscendentalExpression::Sigmoid(arg) => arg.sizes(),
+            TranscendentalExpression::Softplus(arg) => arg.sizes(),
+            TranscendentalExpression::Log1p(arg) => arg.sizes(),
+            TranscendentalExpression::Expm1(arg) => arg.sizes(),
+            TranscendentalExpression::Polygamma(_, arg) => arg.sizes(),
         }
     }
 }
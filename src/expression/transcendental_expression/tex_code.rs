@@ -32,6 +32,63 @@ impl TranscendentalExpression {
             TranscendentalExpression::Tan(arg) => {
                 TranscendentalExpression::tex_code_tan(arg, variables)
             }
+            TranscendentalExpression::Sign(arg) => {
+                TranscendentalExpression::tex_code_sign(arg, variables)
+            }
+            TranscendentalExpression::Heaviside(arg) => {
+                TranscendentalExpression::tex_code_heaviside(arg, variables)
+            }
+            TranscendentalExpression::Sinh(arg) => {
+                TranscendentalExpression::tex_code_sinh(arg, variables)
+            }
+            TranscendentalExpression::Cosh(arg) => {
+                TranscendentalExpression::tex_code_cosh(arg, variables)
+            }
+            TranscendentalExpression::Tanh(arg) => {
+                TranscendentalExpression::tex_code_tanh(arg, variables)
+            }
+            TranscendentalExpression::Asin(arg) => {
+                TranscendentalExpression::tex_code_asin(arg, variables)
+            }
+            TranscendentalExpression::Acos(arg) => {
+                TranscendentalExpression::tex_code_acos(arg, variables)
+            }
+            TranscendentalExpression::Atan(arg) => {
+                TranscendentalExpression::tex_code_atan(arg, variables)
+            }
+            TranscendentalExpression::Atan2(y, x) => {
+                TranscendentalExpression::tex_code_atan2(y, x, variables)
+            }
+            TranscendentalExpression::Erf(arg) => {
+                TranscendentalExpression::tex_code_erf(arg, variables)
+            }
+            TranscendentalExpression::Erfc(arg) => {
+                TranscendentalExpression::tex_code_erfc(arg, variables)
+            }
+            TranscendentalExpression::Gamma(arg) => {
+                TranscendentalExpression::tex_code_gamma(arg, variables)
+            }
+            TranscendentalExpression::LnGamma(arg) => {
+                TranscendentalExpression::tex_code_ln_gamma(arg, variables)
+            }
+            TranscendentalExpression::Digamma(arg) => {
+                TranscendentalExpression::tex_code_digamma(arg, variables)
+            }
+            TranscendentalExpression::Sigmoid(arg) => {
+                TranscendentalExpression::tex_code_sigmoid(arg, variables)
+            }
+            TranscendentalExpression::Softplus(arg) => {
+                TranscendentalExpression::tex_code_softplus(arg, variables)
+            }
+            TranscendentalExpression::Log1p(arg) => {
+                TranscendentalExpression::tex_code_ln_1p(arg, variables)
+            }
+            TranscendentalExpression::Expm1(arg) => {
+                TranscendentalExpression::tex_code_exp_m1(arg, variables)
+            }
+            TranscendentalExpression::Polygamma(n, arg) => {
+                TranscendentalExpression::tex_code_polygamma(*n, arg, variables)
+            }
         }
     }
 
@@ -1,5 +1,5 @@
-use crate::TranscendentalExpression;
-use std::collections::HashSet;
+use crate::{Size, TranscendentalExpression};
+use std::collections::{HashMap, HashSet};
 
 impl TranscendentalExpression {
     pub fn variable_ids(&self) -> HashSet<&str> {
@@ -20,6 +20,73 @@ impl TranscendentalExpression {
             TranscendentalExpression::Sin(arg) => arg.variable_ids(),
             TranscendentalExpression::Cos(arg) => arg.variable_ids(),
             TranscendentalExpression::Tan(arg) => arg.variable_ids(),
+            TranscendentalExpression::Sign(arg) => arg.variable_ids(),
+            TranscendentalExpression::Heaviside(arg) => arg.variable_ids(),
+            TranscendentalExpression::Sinh(arg) => arg.variable_ids(),
+            TranscendentalExpression::Cosh(arg) => arg.variable_ids(),
+            TranscendentalExpression::Tanh(arg) => arg.variable_ids(),
+            TranscendentalExpression::Asin(arg) => arg.variable_ids(),
+            TranscendentalExpression::Acos(arg) => arg.variable_ids(),
+            TranscendentalExpression::Atan(arg) => arg.variable_ids(),
+            TranscendentalExpression::Atan2(y, x) => y
+                .variable_ids()
+                .into_iter()
+                .chain(x.variable_ids())
+                .collect(),
+            TranscendentalExpression::Erf(arg) => arg.variable_ids(),
+            TranscendentalExpression::Erfc(arg) => arg.variable_ids(),
+            TranscendentalExpression::Gamma(arg) => arg.variable_ids(),
+            TranscendentalExpression::LnGamma(arg) => arg.variable_ids(),
+            TranscendentalExpression::Digamma(arg) => arg.variable_ids(),
+            TranscendentalExpression::Sigmoid(arg) => arg.variable_ids(),
+            TranscendentalExpression::Softplus(arg) => arg.variable_ids(),
+            TranscendentalExpression::Log1p(arg) => arg.variable_ids(),
+            TranscendentalExpression::Expm1(arg) => arg.variable_ids(),
+            TranscendentalExpression::Polygamma(_, arg) => arg.variable_ids(),
+        }
+    }
+
+    pub fn variable_sizes(&self) -> HashMap<&str, &[Size]> {
+        match self {
+            TranscendentalExpression::Abs(arg) => arg.variable_sizes(),
+            TranscendentalExpression::Pow(base, exponential) => base
+                .variable_sizes()
+                .into_iter()
+                .chain(exponential.variable_sizes())
+                .collect(),
+            TranscendentalExpression::Exp(arg) => arg.variable_sizes(),
+            TranscendentalExpression::Log(l, antilogarithm) => l
+                .variable_sizes()
+                .into_iter()
+                .chain(antilogarithm.variable_sizes())
+                .collect(),
+            TranscendentalExpression::Ln(arg) => arg.variable_sizes(),
+            TranscendentalExpression::Sin(arg) => arg.variable_sizes(),
+            TranscendentalExpression::Cos(arg) => arg.variable_sizes(),
+            TranscendentalExpression::Tan(arg) => arg.variable_sizes(),
+            TranscendentalExpression::Sign(arg) => arg.variable_sizes(),
+            TranscendentalExpression::Heaviside(arg) => arg.variable_sizes(),
+            TranscendentalExpression::Sinh(arg) => arg.variable_sizes(),
+            TranscendentalExpression::Cosh(arg) => arg.variable_sizes(),
+            TranscendentalExpression::Tanh(arg) => arg.variable_sizes(),
+            TranscendentalExpression::Asin(arg) => arg.variable_sizes(),
+            TranscendentalExpression::Acos(arg) => arg.variable_sizes(),
+            TranscendentalExpression::Atan(arg) => arg.variable_sizes(),
+            TranscendentalExpression::Atan2(y, x) => y
+                .variable_sizes()
+                .into_iter()
+                .chain(x.variable_sizes())
+                .collect(),
+            TranscendentalExpression::Erf(arg) => arg.variable_sizes(),
+            TranscendentalExpression::Erfc(arg) => arg.variable_sizes(),
+            TranscendentalExpression::Gamma(arg) => arg.variable_sizes(),
+            TranscendentalExpression::LnGamma(arg) => arg.variable_sizes(),
+            TranscendentalExpression::Digamma(arg) => arg.variable_sizes(),
+            TranscendentalExpression::Sigmoid(arg) => arg.variable_sizes(),
+            TranscendentalExpression::Softplus(arg) => arg.variable_sizes(),
+            TranscendentalExpression::Log1p(arg) => arg.variable_sizes(),
+            TranscendentalExpression::Expm1(arg) => arg.variable_sizes(),
+            TranscendentalExpression::Polygamma(_, arg) => arg.variable_sizes(),
         }
     }
 }
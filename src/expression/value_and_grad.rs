@@ -0,0 +1,499 @@
+use crate::{
+    expression::transcendental_expression::functions::{
+        digamma::digamma, polygamma::polygamma, sigmoid::sigmoid, sign::sign,
+    },
+    matrix_expression::operations::cholesky::lower_half,
+    ConstantValue, Expression, ExpressionRef, MatrixExpression, SymbolicError, TensorExpression,
+    TranscendentalExpression,
+};
+use opensrdk_linear_algebra::{indices_cartesian_product, sparse::SparseTensor, Tensor};
+use std::collections::HashMap;
+
+/// Sums `adjoint` over the ranks `like` is broadcast along, and puts it in the same kind of value as `like`.
+fn conform(adjoint: ConstantValue, like: &ConstantValue) -> Result<ConstantValue, SymbolicError> {
+    let sizes = like.sizes();
+    match (&adjoint, like) {
+        (ConstantValue::Scalar(_), ConstantValue::Scalar(_)) => return Ok(adjoint),
+        (ConstantValue::Tensor(_), ConstantValue::Tensor(_))
+        | (ConstantValue::Matrix(_), ConstantValue::Matrix(_))
+            if adjoint.sizes() == sizes =>
+        {
+            return Ok(adjoint)
+        }
+        (_, ConstantValue::Scalar(_)) => {
+            return Ok(ConstantValue::Scalar(adjoint.elems().iter().sum()))
+        }
+        _ => {}
+    }
+
+    let mut elems = HashMap::<Vec<usize>, f64>::new();
+    for (indices, v) in adjoint.to_tensor().elems().iter() {
+        let indices = (0..sizes.len())
+            .map(|rank| match sizes[rank] {
+                1 => 0,
+                _ => indices.get(rank).copied().unwrap_or(0),
+            })
+            .collect();
+        *elems.entry(indices).or_insert(0.0) += v;
+    }
+    let tensor = ConstantValue::Tensor(SparseTensor::from(sizes, elems)?);
+
+    match like {
+        ConstantValue::Matrix(_) => Ok(ConstantValue::Matrix(tensor.to_matrix()?)),
+        _ => Ok(tensor),
+    }
+}
+
+/// The derivatives of an elementwise function with respect to each of its operands, at their values.
+pub(crate) fn derivatives(
+    v: &TranscendentalExpression,
+    args: &[ConstantValue],
+) -> Result<Vec<ConstantValue>, SymbolicError> {
+    let x = args[0].clone();
+    let d = match v {
+        TranscendentalExpression::Abs(_) => x.map(sign),
+        TranscendentalExpression::Pow(_, _) => {
+            return Ok(vec![
+                x.zip_map(&args[1], |b, e| e * b.powf(e - 1.0))?,
+                x.zip_map(&args[1], |b, e| b.powf(e) * b.ln())?,
+            ])
+        }
+        TranscendentalExpression::Exp(_) => x.map(f64::exp),
+        TranscendentalExpression::Log(_, _) => {
+            return Ok(vec![
+                x.zip_map(&args[1], |b, a| -a.ln() / (b * b.ln().powi(2)))?,
+                x.zip_map(&args[1], |b, a| 1.0 / (a * b.ln()))?,
+            ])
+        }
+        TranscendentalExpression::Ln(_) => x.map(|x| 1.0 / x),
+        TranscendentalExpression::Sin(_) => x.map(f64::cos),
+        TranscendentalExpression::Cos(_) => x.map(|x| -x.sin()),
+        TranscendentalExpression::Tan(_) => x.map(|x| 1.0 / x.cos().powi(2)),
+        TranscendentalExpression::Sign(_) | TranscendentalExpression::Heaviside(_) => {
+            x.map(|_| 0.0)
+        }
+        TranscendentalExpression::Sinh(_) => x.map(f64::cosh),
+        TranscendentalExpression::Cosh(_) => x.map(f64::sinh),
+        TranscendentalExpression::Tanh(_) => x.map(|x| 1.0 - x.tanh().powi(2)),
+        TranscendentalExpression::Asin(_) => x.map(|x| 1.0 / (1.0 - x * x).sqrt()),
+        TranscendentalExpression::Acos(_) => x.map(|x| -1.0 / (1.0 - x * x).sqrt()),
+        TranscendentalExpression::Atan(_) => x.map(|x| 1.0 / (1.0 + x * x)),
+        TranscendentalExpression::Atan2(_, _) => {
+            return Ok(vec![
+                x.zip_map(&args[1], |y, x| x / (x * x + y * y))?,
+                x.zip_map(&args[1], |y, x| -y / (x * x + y * y))?,
+            ])
+        }
+        TranscendentalExpression::Erf(_) => {
+            x.map(|x| 2.0 / std::f64::consts::PI.sqrt() * (-x * x).exp())
+        }
+        TranscendentalExpression::Erfc(_) => {
+            x.map(|x| -2.0 / std::f64::consts::PI.sqrt() * (-x * x).exp())
+        }
+        TranscendentalExpression::Gamma(_) => x.map(|x| libm::tgamma(x) * digamma(x)),
+        TranscendentalExpression::LnGamma(_) => x.map(digamma),
+        TranscendentalExpression::Digamma(_) => x.map(|x| polygamma(1, x)),
+        TranscendentalExpression::Sigmoid(_) => x.map(|x| sigmoid(x) * (1.0 - sigmoid(x))),
+        TranscendentalExpression::Softplus(_) => x.map(sigmoid),
+        TranscendentalExpression::Log1p(_) => x.map(|x| 1.0 / (1.0 + x)),
+        TranscendentalExpression::Expm1(_) => x.map(f64::exp),
+        TranscendentalExpression::Polygamma(n, _) => x.map(|x| polygamma(n + 1, x)),
+    };
+
+    Ok(vec![d])
+}
+
+struct Tape<'a> {
+    assignment: &'a HashMap<&'a str, ConstantValue>,
+    /// The value of each node recorded, or none for the nodes without a value of their own.
+    values: HashMap<usize, Option<ConstantValue>>,
+    /// The nodes in the order they are evaluated, which is reversed to backpropagate.
+    nodes: Vec<ExpressionRef>,
+}
+
+impl<'a> Tape<'a> {
+    fn record(&mut self, e: &Expression) -> Result<Option<ConstantValue>, SymbolicError> {
+        let node = ExpressionRef::from(e.clone());
+        if let Some(value) = self.values.get(&node.id()) {
+            return Ok(value.clone());
+        }
+
+        let children = e.children();
+        let operands = children
+            .iter()
+            .map(|c| self.record(c))
+            .collect::<Result<Vec<_>, _>>()?;
+        let standalone = operands.iter().map(|o| o.is_some()).collect::<Vec<_>>();
+
+        let value = if e.is_standalone(&standalone) {
+            Some(self.evaluate(e, children, &operands)?)
+        } else {
+            None
+        };
+
+        self.values.insert(node.id(), value.clone());
+        self.nodes.push(node);
+
+        Ok(value)
+    }
+
+    fn evaluate(
+        &self,
+        e: &Expression,
+        children: Vec<Expression>,
+        operands: &[Option<ConstantValue>],
+    ) -> Result<ConstantValue, SymbolicError> {
+        let mut values = operands.iter().flatten();
+        let mut next = || values.next().unwrap();
+
+        match e {
+            Expression::Variable(id, _) => Expression::evaluate_variable(id, self.assignment),
+            Expression::Constant(v) => Ok(v.clone()),
+            Expression::Add(_, _) => next().try_add(next().clone()),
+            Expression::Sub(_, _) => next().try_sub(next().clone()),
+            Expression::Mul(_, _) => next().try_mul(next().clone()),
+            Expression::Div(_, _) => next().clone().try_div(next()),
+            Expression::Neg(_) => Ok(next().clone().map(|v| -v)),
+            _ => {
+                // The other nodes are evaluated on their operands put in as variables.
+                let mut variables = HashMap::new();
+                let children = children
+                    .into_iter()
+                    .zip(operands.iter())
+                    .enumerate()
+                    .map(|(k, (c, o))| match o {
+                        Some(v) => {
+                            variables.insert(format!("${}", k), v.clone());
+                            Expression::Variable(format!("${}", k), c.sizes())
+                        }
+                        None => c,
+                    })
+                    .collect();
+                let variables = variables
+                    .iter()
+                    .map(|(id, v)| (id.as_str(), v.clone()))
+                    .collect::<HashMap<_, _>>();
+
+                e.with_children(children)._evaluate(&variables)
+            }
+        }
+    }
+
+    /// The adjoints of the operands of `e`, given the adjoint of it.
+    fn backpropagate(
+        e: &Expression,
+        value: &ConstantValue,
+        operands: &[Option<ConstantValue>],
+        adjoint: &ConstantValue,
+    ) -> Result<Vec<Option<ConstantValue>>, SymbolicError> {
+        let values = operands.iter().flatten().collect::<Vec<_>>();
+        let all = |adjoints: Vec<ConstantValue>| adjoints.into_iter().map(Some).collect();
+
+        Ok(match e {
+            Expression::Variable(_, _) | Expression::Constant(_) => vec![],
+            Expression::PartialVariable(v) => {
+                let g = adjoint.to_tensor();
+                indices_cartesian_product(v.sizes())
+                    .into_iter()
+                    .map(|indices| {
+                        let indices = (0..g.rank())
+                            .map(|rank| indices.get(rank).copied().unwrap_or(0))
+                            .collect::<Vec<_>>();
+                        Some(ConstantValue::Scalar(g[&indices]))
+                    })
+                    .collect()
+            }
+            Expression::Add(_, _) => all(vec![adjoint.clone(), adjoint.clone()]),
+            Expression::Sub(_, _) => all(vec![adjoint.clone(), adjoint.clone().map(|v| -v)]),
+            Expression::Mul(_, _) => all(vec![
+                adjoint.try_mul(values[1].clone())?,
+                adjoint.try_mul(values[0].clone())?,
+            ]),
+            Expression::Div(_, _) => all(vec![
+                adjoint.clone().try_div(values[1])?,
+                adjoint
+                    .try_mul(value.clone())?
+                    .try_div(values[1])?
+                    .map(|v| -v),
+            ]),
+            Expression::Neg(_) => all(vec![adjoint.clone().map(|v| -v)]),
+            Expression::Transcendental(v) => {
+                let args = values.into_iter().cloned().collect::<Vec<_>>();
+                all(derivatives(v, &args)?
+                    .into_iter()
+                    .map(|d| adjoint.try_mul(d))
+                    .collect::<Result<Vec<_>, _>>()?)
+            }
+            Expression::Tensor(v) => match v.as_ref() {
+                TensorExpression::KroneckerDeltas(_)
+                | TensorExpression::SizedKroneckerDeltas(_) => vec![],
+                TensorExpression::DotProduct {
+                    terms,
+                    rank_combinations,
+                } => TensorExpression::dot_product_adjoints(
+                    terms,
+                    rank_combinations,
+                    operands,
+                    adjoint,
+                )?,
+                TensorExpression::DirectProduct(_) => {
+                    let args = values.into_iter().cloned().collect::<Vec<_>>();
+                    all(TensorExpression::direct_product_adjoints(&args, adjoint)?)
+                }
+                TensorExpression::LogSumExp { rank, .. } => {
+                    all(vec![TensorExpression::log_sum_exp_adjoint(
+                        values[0], value, *rank, adjoint,
+                    )?])
+                }
+                TensorExpression::Softmax { rank, .. } => {
+                    all(vec![TensorExpression::softmax_adjoint(
+                        value, *rank, adjoint,
+                    )?])
+                }
+            },
+            Expression::Matrix(v) => {
+                let g = adjoint.to_matrix()?;
+                let adjoints = match v.as_ref() {
+                    MatrixExpression::T(_) => vec![g.t()],
+                    MatrixExpression::Inv(_) => {
+                        // -A^-T G A^-T
+                        let inv_t = value.to_matrix()?.t();
+                        vec![inv_t.dot(&g).dot(&inv_t) * -1.0]
+                    }
+                    MatrixExpression::Det(_) => {
+                        // G det(A) A^-T
+                        let inv_t = values[0].inv()?.to_matrix()?.t();
+                        vec![inv_t * (g[(0, 0)] * value.elems()[0])]
+                    }
+                    MatrixExpression::Cholesky(_) => {
+                        // L^-T Φ(L^T G) L^-1
+                        let l = value.to_matrix()?;
+                        let l_inv = value.inv()?.to_matrix()?;
+                        vec![l_inv.t().dot(&lower_half(&l.t().dot(&g))).dot(&l_inv)]
+                    }
+                    MatrixExpression::Solve(_, _) => {
+                        // A^-T G for B, and -A^-T G X^T for A
+                        let b = values[0].t()?.solve(adjoint)?.to_matrix()?;
+                        let a = b.dot(&value.to_matrix()?.t()) * -1.0;
+                        vec![a, b]
+                    }
+                    MatrixExpression::LogDet(_) => {
+                        // G A^-T
+                        vec![values[0].inv()?.to_matrix()?.t() * g[(0, 0)]]
+                    }
+                    MatrixExpression::LowerHalf(_) => vec![lower_half(&g)],
+                };
+                all(adjoints.into_iter().map(ConstantValue::Matrix).collect())
+            }
+        })
+    }
+}
+
+impl Expression {
+    /// The value of a scalar expression and its gradient with respect to each of `variable_ids`, computed numerically.
+    ///
+    /// The expression is evaluated once, recording the value of each node, and the adjoints are then propagated back through the recorded values.
+    /// Unlike `differential` and `gradient`, no derivative expression is built.
+    pub fn value_and_grad(
+        &self,
+        assignment: &HashMap<&str, ConstantValue>,
+        variable_ids: &[&str],
+    ) -> Result<(ConstantValue, Vec<ConstantValue>), SymbolicError> {
+        self.bind_dims(assignment)?;
+
+        let mut tape = Tape {
+            assignment,
+            values: HashMap::new(),
+            nodes: vec![],
+        };
+        let value = tape.record(self)?.ok_or(SymbolicError::UndeterminedSize)?;
+        if value.elems().len() != 1 {
+            return Err(SymbolicError::TypeMismatch {
+                expected: "scalar",
+                found: value.kind(),
+            });
+        }
+
+        let mut adjoints = HashMap::<usize, ConstantValue>::new();
+        adjoints.insert(tape.nodes.last().unwrap().id(), value.clone().map(|_| 1.0));
+        let mut gradient = HashMap::<&str, ConstantValue>::new();
+
+        for node in tape.nodes.iter().rev() {
+            let adjoint = match adjoints.remove(&node.id()) {
+                Some(adjoint) => adjoint,
+                None => continue,
+            };
+            let node_value = tape.values[&node.id()].as_ref().unwrap();
+
+            if let Expression::Variable(id, _) = node.as_ref() {
+                if let Some(&id) = variable_ids.iter().find(|&&v| v == id.as_str()) {
+                    gradient.insert(id, adjoint);
+                }
+                continue;
+            }
+
+            let children = node
+                .children()
+                .into_iter()
+                .map(ExpressionRef::from)
+                .collect::<Vec<_>>();
+            let operands = children
+                .iter()
+                .map(|c| tape.values[&c.id()].clone())
+                .collect::<Vec<_>>();
+
+            let operand_adjoints = Tape::backpropagate(node, node_value, &operands, &adjoint)?;
+            for ((child, operand), child_adjoint) in
+                children.iter().zip(operands.iter()).zip(operand_adjoints)
+            {
+                let (operand, child_adjoint) = match (operand, child_adjoint) {
+                    (Some(operand), Some(child_adjoint)) => (operand, child_adjoint),
+                    _ => continue,
+                };
+                let child_adjoint = conform(child_adjoint, operand)?;
+                let sum = match adjoints.remove(&child.id()) {
+                    Some(sum) => sum.try_add(child_adjoint)?,
+                    None => child_adjoint,
+                };
+                adjoints.insert(child.id(), sum);
+            }
+        }
+
+        let gradient = variable_ids
+            .iter()
+            .map(|id| match gradient.remove(id) {
+                Some(g) => g,
+                None => match assignment.get(id) {
+                    Some(v) => v.clone().map(|_| 0.0),
+                    None => ConstantValue::Scalar(0.0),
+                },
+            })
+            .collect();
+
+        Ok((value, gradient))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use opensrdk_linear_algebra::{indices_cartesian_product, sparse::SparseTensor};
+
+    use crate::{new_variable, new_variable_tensor, ConstantValue, Expression, Size};
+
+    fn central_difference(
+        expression: &Expression,
+        point: &HashMap<&str, ConstantValue>,
+        id: &str,
+    ) -> SparseTensor {
+        let h = 1e-6;
+        let sizes = point[id].sizes();
+        let mut elems = HashMap::new();
+        for indices in indices_cartesian_product(&sizes) {
+            let moved = |s: f64| {
+                let mut moved = point.clone();
+                let mut v = point[id].to_tensor();
+                v[&indices] += s * h;
+                moved.insert(id, ConstantValue::Tensor(v));
+                expression.evaluate(&moved).unwrap().elems()[0]
+            };
+            let d = (moved(1.0) - moved(-1.0)) / (2.0 * h);
+            elems.insert(indices, d);
+        }
+
+        SparseTensor::from(sizes, elems).unwrap()
+    }
+
+    #[test]
+    fn it_works() {
+        let n = 30;
+        let ids = (0..n).map(|i| format!("u_{{{}}}", i)).collect::<Vec<_>>();
+        let u = ids
+            .iter()
+            .map(|id| new_variable(id.clone()))
+            .collect::<Vec<_>>();
+
+        let expression = u
+            .iter()
+            .zip(u.iter().skip(1))
+            .map(|(a, b)| (a.clone() * b.clone()).sin().exp() / (1.0 + b.clone().pow(2.0.into())))
+            .fold(Expression::from(0.0), |acc, k| acc + k)
+            .ln()
+            - u[0].clone().atan2(u[n - 1].clone()).sigmoid();
+
+        let variable_ids = ids.iter().map(|id| id.as_str()).collect::<Vec<_>>();
+        let mut hash = HashMap::new();
+        for (i, id) in ids.iter().enumerate() {
+            hash.insert(id.as_str(), ConstantValue::Scalar((i as f64 * 0.7).sin()));
+        }
+
+        let (value, gradient) = expression.value_and_grad(&hash, &variable_ids).unwrap();
+        assert_eq!(value, expression.evaluate(&hash).unwrap());
+
+        let differential = expression.differential(&variable_ids);
+        for (g, d) in gradient.iter().zip(differential.iter()) {
+            let d = d.evaluate(&hash).unwrap().into_scalar();
+            assert!((g.into_scalar() - d).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn it_works2() {
+        let x = new_variable_tensor("x".to_string(), vec![Size::Many]);
+        let a = new_variable_tensor("a".to_string(), vec![Size::Many, Size::Many]);
+        let s = new_variable("s".to_string());
+
+        let expressions = [
+            a.clone().det().ln()
+                + x.clone()
+                    .dot(a.clone().inv(), &[[0, 0]])
+                    .dot(x.clone(), &[[1, 0]])
+                    * s.clone()
+                + x.clone()
+                    .softmax(0)
+                    .dot(a.clone().t().dot(x.clone(), &[[1, 0]]), &[[0, 0]]),
+            x.clone().direct(x.clone()).log_sum_exp(0) * s.clone(),
+        ];
+
+        let mut point = HashMap::new();
+        point.insert("x", ConstantValue::Tensor(vec![0.5, -1.0, 2.0].into()));
+        let mut elems = HashMap::new();
+        for (indices, v) in [
+            ([0, 0], 2.0),
+            ([0, 1], 0.5),
+            ([1, 1], 1.5),
+            ([1, 2], -0.3),
+            ([2, 0], 0.2),
+            ([2, 2], 1.0),
+        ] {
+            elems.insert(indices.to_vec(), v);
+        }
+        point.insert(
+            "a",
+            ConstantValue::Tensor(SparseTensor::from(vec![3, 3], elems).unwrap()),
+        );
+        point.insert("s", ConstantValue::Scalar(0.8));
+
+        for expression in expressions.iter() {
+            let (value, gradient) = expression
+                .value_and_grad(&point, &["x", "a", "s", "y"])
+                .unwrap();
+            // The sparse elements are summed in no fixed order.
+            let expected = expression.evaluate(&point).unwrap();
+            assert_eq!(value.sizes(), expected.sizes());
+            assert!((value.elems()[0] - expected.elems()[0]).abs() < 1e-12);
+
+            for (id, g) in ["x", "a", "s"].iter().zip(gradient.iter()) {
+                let expected = central_difference(expression, &point, id);
+                let g = g.to_tensor();
+                for indices in indices_cartesian_product(&point[id].sizes()) {
+                    assert!((g[&indices] - expected[&indices]).abs() < 1e-6);
+                }
+            }
+
+            // The variables which do not appear have zero gradients.
+            assert_eq!(gradient[3], ConstantValue::Scalar(0.0));
+        }
+    }
+}
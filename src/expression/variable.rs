@@ -1,5 +1,8 @@
-use crate::{Expression, Size, TensorExpression};
-use std::{collections::HashSet, iter::once};
+use crate::{ConstantValue, Expression, Size, SymbolicError, TensorExpression};
+use std::{
+    collections::{HashMap, HashSet},
+    iter::once,
+};
 
 pub fn new_variable(id: String) -> Expression {
     Expression::Variable(id, vec![])
@@ -43,12 +46,40 @@ impl Expression {
         }
     }
 
+    pub fn variable_sizes(&self) -> HashMap<&str, &[Size]> {
+        match self {
+            Expression::Variable(id, sizes) => once((id.as_str(), sizes.as_slice())).collect(),
+            Expression::Constant(_) => HashMap::new(),
+            Expression::PartialVariable(v) => v
+                .elems()
+                .values()
+                .flat_map(|v| v.variable_sizes())
+                .collect(),
+            Expression::Add(l, r)
+            | Expression::Sub(l, r)
+            | Expression::Mul(l, r)
+            | Expression::Div(l, r) => l
+                .variable_sizes()
+                .into_iter()
+                .chain(r.variable_sizes())
+                .collect(),
+            Expression::Neg(v) => v.variable_sizes(),
+            Expression::Transcendental(v) => v.variable_sizes(),
+            Expression::Tensor(v) => v.variable_sizes(),
+            Expression::Matrix(v) => v.variable_sizes(),
+        }
+    }
+
     pub(crate) fn diff_variable(
         symbol: &String,
         sizes: &Vec<Size>,
         variable_ids: &[&str],
+        rank_offset: usize,
     ) -> Vec<Expression> {
         let rank = sizes.len();
+        // The ranks of the derivative are placed after both the ranks of the variable and those of the
+        // expression being differentiated.
+        let offset = rank.max(rank_offset);
         variable_ids
             .iter()
             .map(|&s| {
@@ -56,8 +87,10 @@ impl Expression {
                     if rank == 0 {
                         1.0.into()
                     } else {
-                        TensorExpression::KroneckerDeltas(
-                            (0..rank).map(|r| [r, r + rank]).collect(),
+                        TensorExpression::kronecker_deltas(
+                            (0..rank)
+                                .map(|r| ([r, r + offset], sizes[r].clone()))
+                                .collect(),
                         )
                         .into()
                     }
@@ -67,6 +100,16 @@ impl Expression {
             })
             .collect()
     }
+
+    pub(crate) fn evaluate_variable(
+        id: &str,
+        variables: &HashMap<&str, ConstantValue>,
+    ) -> Result<ConstantValue, SymbolicError> {
+        variables
+            .get(id)
+            .cloned()
+            .ok_or_else(|| SymbolicError::UnassignedVariable(id.to_owned()))
+    }
 }
 
 #[cfg(test)]
@@ -5,7 +5,10 @@ use opensrdk_linear_algebra::indices_cartesian_product;
 
 use crate::Expression;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    hash::{Hash, Hasher},
+};
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct ExpressionArray {
@@ -14,6 +17,16 @@ pub struct ExpressionArray {
     default: Box<Expression>,
 }
 
+impl Hash for ExpressionArray {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.sizes.hash(state);
+        let mut elems = self.elems.iter().collect::<Vec<_>>();
+        elems.sort_by(|a, b| a.0.cmp(b.0));
+        elems.hash(state);
+        self.default.hash(state);
+    }
+}
+
 impl ExpressionArray {
     pub fn new(sizes: Vec<usize>) -> Self {
         Self {
@@ -0,0 +1,522 @@
+use crate::transcendental_expression::functions::{
+    heaviside::heaviside, polygamma::polygamma, sigmoid::sigmoid, sign::sign, softplus::softplus,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    f64::consts::{FRAC_PI_2, PI},
+    ops::{Add, Div, Mul, Neg, Sub},
+};
+
+/// The ulps the bounds computed by the library functions are widened by, which covers their rounding errors.
+const FUNCTION_ULPS: usize = 4;
+
+/// A closed range of real numbers, whose bounds may be infinite.
+/// It is empty when `lo > hi`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Interval {
+    pub lo: f64,
+    pub hi: f64,
+}
+
+/// The least float above `x`, which `f64::next_up` only gives from Rust 1.86.
+fn next_up(x: f64) -> f64 {
+    if x.is_nan() || x == f64::INFINITY {
+        return x;
+    }
+    if x == 0.0 {
+        return f64::from_bits(1);
+    }
+
+    let bits = x.to_bits();
+    f64::from_bits(if x > 0.0 { bits + 1 } else { bits - 1 })
+}
+
+fn next_down(x: f64) -> f64 {
+    -next_up(-x)
+}
+
+fn widened(lo: f64, hi: f64, ulps: usize) -> Interval {
+    if lo.is_nan() || hi.is_nan() {
+        return Interval::entire();
+    }
+    let (mut lo, mut hi) = (lo, hi);
+    for _ in 0..ulps {
+        lo = next_down(lo);
+        hi = next_up(hi);
+    }
+
+    Interval { lo, hi }
+}
+
+/// The product of bounds, where zero times infinity is zero.
+fn product(a: f64, b: f64) -> f64 {
+    if a == 0.0 || b == 0.0 {
+        0.0
+    } else {
+        a * b
+    }
+}
+
+impl Interval {
+    pub fn new(lo: f64, hi: f64) -> Self {
+        Self { lo, hi }
+    }
+
+    pub fn point(v: f64) -> Self {
+        Self { lo: v, hi: v }
+    }
+
+    pub fn empty() -> Self {
+        Self {
+            lo: f64::INFINITY,
+            hi: f64::NEG_INFINITY,
+        }
+    }
+
+    pub fn entire() -> Self {
+        Self {
+            lo: f64::NEG_INFINITY,
+            hi: f64::INFINITY,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lo > self.hi || self.lo.is_nan() || self.hi.is_nan()
+    }
+
+    pub fn is_bounded(&self) -> bool {
+        self.is_empty() || (self.lo.is_finite() && self.hi.is_finite())
+    }
+
+    pub fn contains(&self, v: f64) -> bool {
+        self.lo <= v && v <= self.hi
+    }
+
+    pub fn is_point(&self) -> bool {
+        self.lo == self.hi
+    }
+
+    pub fn hull(&self, other: &Interval) -> Interval {
+        match (self.is_empty(), other.is_empty()) {
+            (true, _) => *other,
+            (_, true) => *self,
+            _ => Interval::new(self.lo.min(other.lo), self.hi.max(other.hi)),
+        }
+    }
+
+    pub fn intersect(&self, other: &Interval) -> Interval {
+        let result = Interval::new(self.lo.max(other.lo), self.hi.min(other.hi));
+        if result.is_empty() {
+            return Interval::empty();
+        }
+
+        result
+    }
+
+    fn increasing(&self, f: impl Fn(f64) -> f64) -> Interval {
+        if self.is_empty() {
+            return *self;
+        }
+        widened(f(self.lo), f(self.hi), FUNCTION_ULPS)
+    }
+
+    fn decreasing(&self, f: impl Fn(f64) -> f64) -> Interval {
+        if self.is_empty() {
+            return *self;
+        }
+        widened(f(self.hi), f(self.lo), FUNCTION_ULPS)
+    }
+
+    /// Whether `center + period * k` is in the interval for some integer `k`.
+    fn contains_periodic(&self, center: f64, period: f64) -> bool {
+        let k = ((self.lo - center) / period).ceil();
+        center + period * k <= self.hi
+    }
+
+    /// Whether a non-positive integer, a pole of the gamma functions, is in the interval.
+    pub(crate) fn contains_non_positive_integer(&self) -> bool {
+        !self.is_empty() && self.lo <= 0.0 && self.hi.min(0.0).floor() >= self.lo
+    }
+
+    /// Whether `pi / 2 + pi * k`, a pole of `tan`, is in the interval.
+    pub(crate) fn contains_tan_pole(&self) -> bool {
+        !self.is_empty() && self.contains_periodic(FRAC_PI_2, PI)
+    }
+}
+
+impl Add for Interval {
+    type Output = Interval;
+
+    fn add(self, rhs: Interval) -> Interval {
+        if self.is_empty() || rhs.is_empty() {
+            return Interval::empty();
+        }
+        widened(self.lo + rhs.lo, self.hi + rhs.hi, 1)
+    }
+}
+
+impl Sub for Interval {
+    type Output = Interval;
+
+    fn sub(self, rhs: Interval) -> Interval {
+        if self.is_empty() || rhs.is_empty() {
+            return Interval::empty();
+        }
+        widened(self.lo - rhs.hi, self.hi - rhs.lo, 1)
+    }
+}
+
+impl Neg for Interval {
+    type Output = Interval;
+
+    fn neg(self) -> Interval {
+        Interval::new(-self.hi, -self.lo)
+    }
+}
+
+impl Mul for Interval {
+    type Output = Interval;
+
+    fn mul(self, rhs: Interval) -> Interval {
+        if self.is_empty() || rhs.is_empty() {
+            return Interval::empty();
+        }
+        let products = [
+            product(self.lo, rhs.lo),
+            product(self.lo, rhs.hi),
+            product(self.hi, rhs.lo),
+            product(self.hi, rhs.hi),
+        ];
+        let lo = products.iter().copied().fold(f64::INFINITY, f64::min);
+        let hi = products.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+        widened(lo, hi, 1)
+    }
+}
+
+impl Div for Interval {
+    type Output = Interval;
+
+    /// The quotients by the nonzero elements of `rhs`.
+    fn div(self, rhs: Interval) -> Interval {
+        if self.is_empty() || rhs.is_empty() || rhs == Interval::point(0.0) {
+            return Interval::empty();
+        }
+        let reciprocal = if rhs.lo > 0.0 || rhs.hi < 0.0 {
+            widened(1.0 / rhs.hi, 1.0 / rhs.lo, 1)
+        } else if rhs.lo == 0.0 {
+            Interval::new(1.0 / rhs.hi, f64::INFINITY)
+        } else if rhs.hi == 0.0 {
+            Interval::new(f64::NEG_INFINITY, 1.0 / rhs.lo)
+        } else {
+            return Interval::entire();
+        };
+
+        self * reciprocal
+    }
+}
+
+impl Interval {
+    pub fn abs(&self) -> Interval {
+        if self.is_empty() || self.lo >= 0.0 {
+            *self
+        } else if self.hi <= 0.0 {
+            -*self
+        } else {
+            Interval::new(0.0, self.hi.max(-self.lo))
+        }
+    }
+
+    /// `self` to the power of `exponent`, over the bases where it is defined.
+    pub fn pow(&self, exponent: &Interval) -> Interval {
+        if self.is_empty() || exponent.is_empty() {
+            return Interval::empty();
+        }
+
+        if exponent.is_point() && exponent.lo.fract() == 0.0 {
+            let n = exponent.lo;
+            if n == 0.0 {
+                return Interval::point(1.0);
+            }
+            if n < 0.0 {
+                return Interval::point(1.0) / self.pow(&Interval::point(-n));
+            }
+            let power = |v: f64| v.powf(n);
+            if n % 2.0 == 1.0 {
+                return self.increasing(power);
+            }
+            return self
+                .abs()
+                .increasing(power)
+                .intersect(&Interval::new(0.0, f64::INFINITY));
+        }
+
+        // Negative bases only have powers of integers.
+        let base = self.intersect(&Interval::new(0.0, f64::INFINITY));
+        (*exponent * base.ln()).exp()
+    }
+
+    pub fn exp(&self) -> Interval {
+        self.increasing(f64::exp)
+            .intersect(&Interval::new(0.0, f64::INFINITY))
+    }
+
+    pub fn ln(&self) -> Interval {
+        self.intersect(&Interval::new(0.0, f64::INFINITY))
+            .increasing(f64::ln)
+    }
+
+    pub fn log(&self, antilogarithm: &Interval) -> Interval {
+        antilogarithm.ln() / self.ln()
+    }
+
+    /// The range of `f` of period `2 pi`, which takes its maximum 1 at `argmax` and its minimum -1 at `argmin`.
+    fn periodic(&self, f: impl Fn(f64) -> f64, argmax: f64, argmin: f64) -> Interval {
+        if self.is_empty() {
+            return *self;
+        }
+        if !self.is_bounded() || self.hi - self.lo >= 2.0 * PI {
+            return Interval::new(-1.0, 1.0);
+        }
+
+        let values = widened(
+            f(self.lo).min(f(self.hi)),
+            f(self.lo).max(f(self.hi)),
+            FUNCTION_ULPS,
+        );
+        let lo = if self.contains_periodic(argmin, 2.0 * PI) {
+            -1.0
+        } else {
+            values.lo
+        };
+        let hi = if self.contains_periodic(argmax, 2.0 * PI) {
+            1.0
+        } else {
+            values.hi
+        };
+
+        Interval::new(lo, hi).intersect(&Interval::new(-1.0, 1.0))
+    }
+
+    pub fn sin(&self) -> Interval {
+        self.periodic(f64::sin, FRAC_PI_2, -FRAC_PI_2)
+    }
+
+    pub fn cos(&self) -> Interval {
+        self.periodic(f64::cos, 0.0, PI)
+    }
+
+    pub fn tan(&self) -> Interval {
+        if self.contains_tan_pole() || !self.is_bounded() {
+            return Interval::entire();
+        }
+        self.increasing(f64::tan)
+    }
+
+    /// `sign` and `heaviside` are nondecreasing, and their values are exact.
+    pub fn sign(&self) -> Interval {
+        if self.is_empty() {
+            return *self;
+        }
+        Interval::new(sign(self.lo), sign(self.hi))
+    }
+
+    pub fn heaviside(&self) -> Interval {
+        if self.is_empty() {
+            return *self;
+        }
+        Interval::new(heaviside(self.lo), heaviside(self.hi))
+    }
+
+    pub fn sinh(&self) -> Interval {
+        self.increasing(f64::sinh)
+    }
+
+    pub fn cosh(&self) -> Interval {
+        self.abs()
+            .increasing(f64::cosh)
+            .intersect(&Interval::new(1.0, f64::INFINITY))
+    }
+
+    pub fn tanh(&self) -> Interval {
+        self.increasing(f64::tanh)
+            .intersect(&Interval::new(-1.0, 1.0))
+    }
+
+    pub fn asin(&self) -> Interval {
+        self.intersect(&Interval::new(-1.0, 1.0))
+            .increasing(f64::asin)
+    }
+
+    pub fn acos(&self) -> Interval {
+        self.intersect(&Interval::new(-1.0, 1.0))
+            .decreasing(f64::acos)
+    }
+
+    pub fn atan(&self) -> Interval {
+        self.increasing(f64::atan)
+    }
+
+    /// The angle of the points `(x, y)` with `y` in `self`.
+    pub fn atan2(&self, x: &Interval) -> Interval {
+        if self.is_empty() || x.is_empty() {
+            return Interval::empty();
+        }
+        if x.lo > 0.0 {
+            return (*self / *x).atan();
+        }
+
+        widened(-PI, PI, FUNCTION_ULPS)
+    }
+
+    pub fn erf(&self) -> Interval {
+        self.increasing(libm::erf)
+    }
+
+    pub fn erfc(&self) -> Interval {
+        self.decreasing(libm::erfc)
+    }
+
+    pub fn sigmoid(&self) -> Interval {
+        self.increasing(sigmoid)
+    }
+
+    pub fn softplus(&self) -> Interval {
+        self.increasing(softplus)
+    }
+
+    pub fn log1p(&self) -> Interval {
+        self.intersect(&Interval::new(-1.0, f64::INFINITY))
+            .increasing(f64::ln_1p)
+    }
+
+    pub fn expm1(&self) -> Interval {
+        self.increasing(f64::exp_m1)
+    }
+}
+
+const POLYGAMMA_TOLERANCE: f64 = 1e-12;
+
+/// Where the gamma function takes its minimum over the positive numbers.
+const GAMMA_ARGMIN: f64 = 1.4616321449683623;
+
+impl Interval {
+    /// The range of `f` which decreases until `argmin` and increases after it.
+    fn unimodal(&self, f: impl Fn(f64) -> f64, argmin: f64) -> Interval {
+        if self.hi <= argmin {
+            return self.decreasing(f);
+        }
+        if self.lo >= argmin {
+            return self.increasing(f);
+        }
+
+        widened(
+            f(argmin).min(f(next_down(argmin))).min(f(next_up(argmin))),
+            f(self.lo).max(f(self.hi)),
+            FUNCTION_ULPS,
+        )
+    }
+
+    /// The gamma function, which is only enclosed over positive numbers and otherwise has no bound.
+    pub fn gamma(&self) -> Interval {
+        if self.is_empty() {
+            return *self;
+        }
+        if self.lo <= 0.0 {
+            return Interval::entire();
+        }
+        self.unimodal(libm::tgamma, GAMMA_ARGMIN)
+    }
+
+    pub fn ln_gamma(&self) -> Interval {
+        if self.is_empty() {
+            return *self;
+        }
+        if self.lo <= 0.0 {
+            return Interval::entire();
+        }
+        self.unimodal(libm::lgamma, GAMMA_ARGMIN)
+    }
+
+    /// The `n`-th derivative of the digamma function, which is monotone over positive numbers and otherwise has no bound.
+    pub fn polygamma(&self, n: usize) -> Interval {
+        if self.is_empty() {
+            return *self;
+        }
+        if self.lo <= 0.0 {
+            return Interval::entire();
+        }
+        let values = if n % 2 == 0 {
+            self.increasing(|x| polygamma(n, x))
+        } else {
+            self.decreasing(|x| polygamma(n, x))
+        };
+
+        // Covers the truncation of the series the values are computed with.
+        let margin = |v: f64| POLYGAMMA_TOLERANCE * (v.abs() + 1.0);
+        Interval::new(values.lo - margin(values.lo), values.hi + margin(values.hi))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::{next_down, next_up};
+    use crate::Interval;
+
+    #[test]
+    fn it_works() {
+        let a = Interval::new(-1.0, 2.0);
+        let b = Interval::new(3.0, 4.0);
+
+        let sum = a + b;
+        assert!(sum.contains(2.0) && sum.contains(6.0) && sum.hi < 6.0 + 1e-12);
+
+        let product = a * b;
+        assert!(product.contains(-4.0) && product.contains(8.0));
+
+        assert_eq!(Interval::point(1.0) / a, Interval::entire());
+        assert!((Interval::point(1.0) / Interval::new(0.0, 2.0))
+            .hi
+            .is_infinite());
+
+        let square = a.pow(&Interval::point(2.0));
+        assert!(square.lo == 0.0 && square.contains(4.0) && square.hi < 4.0 + 1e-12);
+
+        assert!(Interval::new(-2.0, -1.0).ln().is_empty());
+        assert!(Interval::new(-2.0, 1.0).ln().lo.is_infinite());
+    }
+
+    #[test]
+    fn it_works2() {
+        let sin = Interval::new(0.0, PI).sin();
+        assert!(sin.hi == 1.0 && sin.lo <= 0.0 && sin.lo > -1e-12);
+
+        let cos = Interval::new(-0.5, 4.0).cos();
+        assert!(cos.lo == -1.0 && cos.hi == 1.0);
+
+        let cos = Interval::new(0.5, 1.0).cos();
+        assert!(cos.contains(0.5f64.cos()) && cos.contains(1.0f64.cos()));
+        assert!(cos.hi - cos.lo < 0.5f64.cos() - 1.0f64.cos() + 1e-12);
+
+        assert_eq!(Interval::new(1.0, 2.0).tan(), Interval::entire());
+
+        let gamma = Interval::new(1.0, 3.0).gamma();
+        assert!(gamma.contains(0.8856031944108887) && gamma.contains(2.0));
+        assert!(gamma.lo > 0.88 && gamma.hi < 2.0 + 1e-12);
+    }
+
+    #[test]
+    fn it_works3() {
+        assert_eq!(next_up(1.0), 1.0 + f64::EPSILON);
+        assert_eq!(next_down(1.0), 1.0 - f64::EPSILON / 2.0);
+        assert_eq!(next_up(0.0), f64::from_bits(1));
+        assert_eq!(next_down(0.0), -f64::from_bits(1));
+        assert_eq!(next_up(-f64::from_bits(1)), -0.0);
+        assert_eq!(next_up(f64::MAX), f64::INFINITY);
+        assert_eq!(next_up(f64::NEG_INFINITY), f64::MIN);
+        assert_eq!(next_up(f64::INFINITY), f64::INFINITY);
+        assert!(next_down(f64::NAN).is_nan());
+    }
+}
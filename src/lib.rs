@@ -8,11 +8,17 @@ extern crate serde;
 extern crate thiserror;
 
 pub mod constant_value;
+pub mod error;
 pub mod expression;
 pub mod expression_array;
 pub mod float;
+pub mod interval;
+pub mod program;
 
 pub use constant_value::*;
+pub use error::*;
 pub use expression::*;
 pub use expression_array::*;
 pub use float::*;
+pub use interval::*;
+pub use program::*;
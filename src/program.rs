@@ -0,0 +1,481 @@
+use crate::{
+    tensor_expression::operations::{
+        dot::{contract, ContractionTerm},
+        log_sum_exp::log_sum_exp,
+        softmax::softmax,
+    },
+    ConstantValue, Expression, ExpressionRef, Size, SymbolicError, TensorExpression, UnaryFunction,
+};
+use opensrdk_linear_algebra::RankIndex;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A term of a dot product in a `Program`, which is read from a register unless it is Kronecker deltas.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum DotProductTerm {
+    Register(usize),
+    KroneckerDeltas(Vec<([RankIndex; 2], Size)>),
+}
+
+/// A step of a `Program`, which writes the value of one node to the next register.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Instruction {
+    Input(usize),
+    Constant(ConstantValue),
+    Add(usize, usize),
+    Sub(usize, usize),
+    Mul(usize, usize),
+    Div(usize, usize),
+    Neg(usize),
+    Unary(UnaryFunction, usize),
+    DotProduct {
+        terms: Vec<DotProductTerm>,
+        rank_combinations: Vec<HashMap<usize, String>>,
+    },
+    DirectProduct(Vec<usize>),
+    LogSumExp {
+        arg: usize,
+        rank: RankIndex,
+    },
+    Softmax {
+        arg: usize,
+        rank: RankIndex,
+    },
+    /// Any other node, evaluated with its operands read from the registers into the placeholder variables.
+    Apply {
+        expression: Expression,
+        operands: Vec<(String, usize)>,
+    },
+}
+
+/// Expressions lowered to a flat tape of instructions, evaluated over registers instead of by walking the expressions.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Program {
+    pub inputs: Vec<String>,
+    /// The sizes of each input in the expressions, or none if it does not appear.
+    pub input_sizes: Vec<Option<Vec<Size>>>,
+    pub instructions: Vec<Instruction>,
+    pub outputs: Vec<usize>,
+}
+
+struct Compiler<'a> {
+    inputs: &'a [&'a str],
+    input_sizes: Vec<Option<Vec<Size>>>,
+    instructions: Vec<Instruction>,
+    /// The register of each node lowered, or none for the nodes without a value of their own.
+    registers: HashMap<usize, Option<usize>>,
+    nodes: Vec<ExpressionRef>,
+}
+
+impl<'a> Compiler<'a> {
+    fn lower(&mut self, e: &Expression) -> Result<Option<usize>, SymbolicError> {
+        let node = ExpressionRef::from(e.clone());
+        if let Some(&register) = self.registers.get(&node.id()) {
+            return Ok(register);
+        }
+
+        let children = e.children();
+        let operands = children
+            .iter()
+            .map(|c| self.lower(c))
+            .collect::<Result<Vec<_>, _>>()?;
+        let standalone = operands.iter().map(|o| o.is_some()).collect::<Vec<_>>();
+
+        let register = if e.is_standalone(&standalone) {
+            let instruction = match e {
+                Expression::Variable(id, sizes) => {
+                    match self.inputs.iter().position(|&v| v == id) {
+                        Some(i) => {
+                            match &self.input_sizes[i] {
+                                Some(lhs) if lhs != sizes => {
+                                    return Err(SymbolicError::VariableSizeConflict {
+                                        id: id.clone(),
+                                        lhs: lhs.clone(),
+                                        rhs: sizes.clone(),
+                                    })
+                                }
+                                _ => self.input_sizes[i] = Some(sizes.clone()),
+                            }
+                            Instruction::Input(i)
+                        }
+                        None => return Err(SymbolicError::UnassignedVariable(id.clone())),
+                    }
+                }
+                Expression::Constant(v) => Instruction::Constant(v.clone()),
+                Expression::Add(_, _) => {
+                    Instruction::Add(operands[0].unwrap(), operands[1].unwrap())
+                }
+                Expression::Sub(_, _) => {
+                    Instruction::Sub(operands[0].unwrap(), operands[1].unwrap())
+                }
+                Expression::Mul(_, _) => {
+                    Instruction::Mul(operands[0].unwrap(), operands[1].unwrap())
+                }
+                Expression::Div(_, _) => {
+                    Instruction::Div(operands[0].unwrap(), operands[1].unwrap())
+                }
+                Expression::Neg(_) => Instruction::Neg(operands[0].unwrap()),
+                Expression::Transcendental(v) => match v.unary() {
+                    Some((function, _)) => Instruction::Unary(function, operands[0].unwrap()),
+                    None => apply(e, children, operands),
+                },
+                Expression::Tensor(v) => match v.as_ref() {
+                    TensorExpression::DotProduct {
+                        rank_combinations, ..
+                    } => Instruction::DotProduct {
+                        terms: children
+                            .into_iter()
+                            .zip(operands)
+                            .map(|(c, o)| match o {
+                                Some(o) => DotProductTerm::Register(o),
+                                None => DotProductTerm::KroneckerDeltas(
+                                    c.tensor().and_then(|t| t.delta_pairs()).unwrap(),
+                                ),
+                            })
+                            .collect(),
+                        rank_combinations: rank_combinations.clone(),
+                    },
+                    TensorExpression::DirectProduct(_) => {
+                        Instruction::DirectProduct(operands.into_iter().flatten().collect())
+                    }
+                    TensorExpression::LogSumExp { rank, .. } => Instruction::LogSumExp {
+                        arg: operands[0].unwrap(),
+                        rank: *rank,
+                    },
+                    TensorExpression::Softmax { rank, .. } => Instruction::Softmax {
+                        arg: operands[0].unwrap(),
+                        rank: *rank,
+                    },
+                    _ => apply(e, children, operands),
+                },
+                _ => apply(e, children, operands),
+            };
+            self.instructions.push(instruction);
+
+            Some(self.instructions.len() - 1)
+        } else {
+            None
+        };
+
+        self.registers.insert(node.id(), register);
+        self.nodes.push(node);
+
+        Ok(register)
+    }
+}
+
+/// The instruction evaluating `e` with its operands read from the registers into placeholder variables.
+fn apply(e: &Expression, children: Vec<Expression>, operands: Vec<Option<usize>>) -> Instruction {
+    let mut placeholders = vec![];
+    let children = children
+        .into_iter()
+        .zip(operands)
+        .map(|(c, o)| match o {
+            Some(o) => {
+                let id = format!("${}", placeholders.len());
+                let placeholder = Expression::Variable(id.clone(), c.sizes());
+                placeholders.push((id, o));
+                placeholder
+            }
+            None => c,
+        })
+        .collect();
+
+    Instruction::Apply {
+        expression: e.with_children(children),
+        operands: placeholders,
+    }
+}
+
+/// Lowers `expressions` to a program which takes the values of `variable_ids` in this order.
+/// The nodes shared among the expressions are computed once.
+pub fn try_compile(
+    expressions: &[Expression],
+    variable_ids: &[&str],
+) -> Result<Program, SymbolicError> {
+    let mut compiler = Compiler {
+        inputs: variable_ids,
+        input_sizes: vec![None; variable_ids.len()],
+        instructions: vec![],
+        registers: HashMap::new(),
+        nodes: vec![],
+    };
+    let outputs = expressions
+        .iter()
+        .enumerate()
+        .map(|(i, e)| {
+            compiler
+                .lower(e)?
+                .ok_or(SymbolicError::NonStandaloneOutput(i))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Program {
+        inputs: variable_ids.iter().map(|&id| id.to_owned()).collect(),
+        input_sizes: compiler.input_sizes,
+        instructions: compiler.instructions,
+        outputs,
+    })
+}
+
+pub fn compile(expressions: &[Expression], variable_ids: &[&str]) -> Program {
+    try_compile(expressions, variable_ids).unwrap_or_else(|e| panic!("{}", e))
+}
+
+impl Program {
+    /// Evaluates the expressions, `values` being the values of the variables in the order given to `compile`.
+    pub fn run(&self, values: &[ConstantValue]) -> Result<Vec<ConstantValue>, SymbolicError> {
+        if values.len() != self.inputs.len() {
+            return Err(SymbolicError::InputCountMismatch {
+                expected: self.inputs.len(),
+                actual: values.len(),
+            });
+        }
+
+        let mut dims = HashMap::new();
+        for ((id, sizes), v) in self.inputs.iter().zip(self.input_sizes.iter()).zip(values) {
+            if let Some(sizes) = sizes {
+                Size::bind_variable(id, sizes, &v.sizes(), &mut dims)?;
+            }
+        }
+
+        let mut registers = Vec::<ConstantValue>::with_capacity(self.instructions.len());
+        for instruction in self.instructions.iter() {
+            let value = match instruction {
+                Instruction::Input(i) => values[*i].clone(),
+                Instruction::Constant(v) => v.clone(),
+                Instruction::Add(l, r) => registers[*l].try_add(registers[*r].clone())?,
+                Instruction::Sub(l, r) => registers[*l].try_sub(registers[*r].clone())?,
+                Instruction::Mul(l, r) => registers[*l].try_mul(registers[*r].clone())?,
+                Instruction::Div(l, r) => registers[*l].clone().try_div(&registers[*r])?,
+                Instruction::Neg(v) => registers[*v].clone().map(|v| -v),
+                Instruction::Unary(function, v) => registers[*v].clone().map(|v| function.call(v)),
+                Instruction::DotProduct {
+                    terms,
+                    rank_combinations,
+                } => {
+                    let terms = terms
+                        .iter()
+                        .map(|t| match t {
+                            DotProductTerm::Register(v) => {
+                                ContractionTerm::Value(registers[*v].to_tensor())
+                            }
+                            DotProductTerm::KroneckerDeltas(rank_pairs) => {
+                                ContractionTerm::Deltas(rank_pairs.clone())
+                            }
+                        })
+                        .collect::<Vec<_>>();
+                    contract(&terms, rank_combinations, &HashMap::new())?
+                }
+                Instruction::DirectProduct(terms) => TensorExpression::direct_product_value(
+                    &terms
+                        .iter()
+                        .map(|v| registers[*v].clone())
+                        .collect::<Vec<_>>(),
+                ),
+                Instruction::LogSumExp { arg, rank } => {
+                    registers[*arg].map_rank(*rank, log_sum_exp)?
+                }
+                Instruction::Softmax { arg, rank } => registers[*arg].map_rank(*rank, softmax)?,
+                Instruction::Apply {
+                    expression,
+                    operands,
+                } => {
+                    let variables = operands
+                        .iter()
+                        .map(|(id, o)| (id.as_str(), registers[*o].clone()))
+                        .collect();
+                    expression._evaluate(&variables)?
+                }
+            };
+            registers.push(value);
+        }
+
+        Ok(self.outputs.iter().map(|&o| registers[o].clone()).collect())
+    }
+
+    /// Runs the program for each of the inputs in parallel.
+    pub fn run_batch(
+        &self,
+        batch: &[Vec<ConstantValue>],
+    ) -> Result<Vec<Vec<ConstantValue>>, SymbolicError> {
+        batch.par_iter().map(|values| self.run(values)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use opensrdk_linear_algebra::{indices_cartesian_product, sparse::SparseTensor};
+
+    use crate::{
+        compile, new_variable, new_variable_tensor, try_compile, ConstantValue, Expression,
+        Instruction, Program, Size, SymbolicError, UnaryFunction,
+    };
+
+    #[test]
+    fn it_works() {
+        let x = new_variable("x".to_string());
+        let y = new_variable("y".to_string());
+
+        let f = (x.clone() * y.clone()).exp().sin() / (x.clone().pow(2.0.into()) + 1.0);
+        let mut expressions = vec![f.clone()];
+        expressions.extend(f.gradient(&["x", "y"]));
+
+        let program = compile(&expressions, &["x", "y"]);
+
+        let json = ron::to_string(&program).unwrap();
+        let program = ron::from_str::<Program>(&json).unwrap();
+
+        for (x, y) in [(0.3, -1.2), (1.5, 0.25), (-2.0, 0.0)] {
+            let values = program
+                .run(&[ConstantValue::Scalar(x), ConstantValue::Scalar(y)])
+                .unwrap();
+
+            let mut hash = HashMap::new();
+            hash.insert("x", ConstantValue::Scalar(x));
+            hash.insert("y", ConstantValue::Scalar(y));
+            for (value, e) in values.iter().zip(expressions.iter()) {
+                let expected = e.evaluate(&hash).unwrap().into_scalar();
+                assert!((value.into_scalar() - expected).abs() < 1e-12);
+            }
+        }
+
+        assert!(matches!(
+            program.run(&[ConstantValue::Scalar(1.0)]),
+            Err(SymbolicError::InputCountMismatch {
+                expected: 2,
+                actual: 1
+            })
+        ));
+    }
+
+    #[test]
+    fn it_works2() {
+        let x = new_variable("x".to_string());
+        let y = new_variable("y".to_string());
+
+        let shared = (x.clone() + y.clone()).exp();
+        let program = compile(
+            &[shared.clone() * 2.0, shared.clone() + x.clone()],
+            &["x", "y"],
+        );
+        let exps = program
+            .instructions
+            .iter()
+            .filter(|i| matches!(i, Instruction::Unary(UnaryFunction::Exp, _)))
+            .count();
+        assert_eq!(exps, 1);
+
+        assert!(try_compile(&[shared], &["x"]).is_err());
+    }
+
+    #[test]
+    fn it_works3() {
+        let x = new_variable_tensor("x".to_string(), vec![Size::Many]);
+        let a = Expression::from(vec![1.0, 2.0, 3.0]);
+        let expression = a.dot(x, &[[0, 0]]);
+        let diff = expression.differential(&["x"])[0].clone();
+
+        // The Kronecker deltas of the derivative stay inside the dot product.
+        let program = compile(&[expression.clone(), diff.clone()], &["x"]);
+        let v = ConstantValue::Tensor(vec![4.0, 5.0, 6.0].into());
+        let values = program.run(std::slice::from_ref(&v)).unwrap();
+        let batch = program
+            .run_batch(&[vec![v.clone()], vec![v.clone()]])
+            .unwrap();
+        assert_eq!(batch, vec![values.clone(), values.clone()]);
+
+        let mut hash = HashMap::new();
+        hash.insert("x", v);
+        assert_eq!(values[0], expression.evaluate(&hash).unwrap());
+        assert_eq!(values[1], diff.evaluate(&hash).unwrap());
+    }
+
+    #[test]
+    fn it_works4() {
+        let x = new_variable_tensor("x".to_string(), vec![Size::Many]);
+        let y = new_variable_tensor("y".to_string(), vec![Size::Dim("n".to_string())]);
+        let program = compile(&[x.clone().dot(y.clone(), &[[0, 0]])], &["x", "y"]);
+
+        let v = ConstantValue::Tensor(vec![1.0, 2.0, 3.0].into());
+        assert!(program.run(&[v.clone(), v.clone()]).is_ok());
+        assert!(matches!(
+            program.run(&[ConstantValue::Scalar(1.0), v.clone()]),
+            Err(SymbolicError::VariableSizeMismatch { .. })
+        ));
+
+        // The sizes bound to the dimensions agree among the inputs.
+        let z = new_variable_tensor("z".to_string(), vec![Size::Dim("n".to_string())]);
+        let program = compile(&[y * 2.0, z * 2.0], &["y", "z"]);
+        let w = ConstantValue::Tensor(vec![1.0, 2.0].into());
+        assert!(matches!(
+            program.run(&[v.clone(), w]),
+            Err(SymbolicError::DimensionConflict { .. })
+        ));
+
+        let delta = x.clone().differential(&["x"])[0].clone();
+        assert!(matches!(
+            try_compile(&[x, delta], &["x"]),
+            Err(SymbolicError::NonStandaloneOutput(1))
+        ));
+    }
+
+    #[test]
+    fn it_works5() {
+        let x = new_variable_tensor("x".to_string(), vec![Size::Many]);
+        let y = new_variable_tensor("y".to_string(), vec![Size::Many, Size::Many]);
+        let expression = x
+            .clone()
+            .softmax(0)
+            .dot(y.clone(), &[[0, 0]])
+            .direct(x.clone().tanh())
+            .log_sum_exp(1);
+        let other = x.clone().exp().softmax(0).dot(x.clone(), &[[0, 0]]);
+
+        // The tensor operations read their operands from the registers.
+        let program = compile(&[expression.clone(), other.clone()], &["x", "y"]);
+        assert!(program
+            .instructions
+            .iter()
+            .all(|i| !matches!(i, Instruction::Apply { .. })));
+
+        let x_value = ConstantValue::Tensor(vec![0.5, -1.0, 2.0].into());
+        let mut elems = HashMap::new();
+        for (i, v) in [0.1, 0.2, 0.3, -0.4, 0.5, 0.6, 0.7, 0.8, -0.9]
+            .iter()
+            .enumerate()
+        {
+            elems.insert(vec![i / 3, i % 3], *v);
+        }
+        let y_value = ConstantValue::Tensor(SparseTensor::from(vec![3, 3], elems).unwrap());
+        let values = program.run(&[x_value.clone(), y_value.clone()]).unwrap();
+
+        let mut hash = HashMap::new();
+        hash.insert("x", x_value);
+        hash.insert("y", y_value);
+        for (value, e) in values.iter().zip([expression, other].iter()) {
+            let expected = e.evaluate(&hash).unwrap();
+            let sizes = expected.sizes();
+            assert_eq!(value.sizes(), sizes);
+            let (value, expected) = (value.to_tensor(), expected.to_tensor());
+            for indices in indices_cartesian_product(&sizes) {
+                assert!((value[&indices] - expected[&indices]).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn it_works6() {
+        let x = new_variable_tensor("x".to_string(), vec![Size::Many]);
+        let other = new_variable_tensor("x".to_string(), vec![Size::Many, Size::Many]);
+
+        assert!(matches!(
+            try_compile(&[x.clone() * 2.0, other], &["x"]),
+            Err(SymbolicError::VariableSizeConflict { .. })
+        ));
+        assert!(try_compile(&[x.clone() * 2.0, x.sin()], &["x"]).is_ok());
+    }
+}